@@ -1,41 +1,51 @@
 use std::marker::PhantomData;
 
-use image::{GenericImageView, Rgba, RgbaImage};
-
 use crate::{
-    basic::BasicRenderer,
-    blend_pixel,
-    geom::inside_arc,
-    types::{Angle, Circle, Rect},
+    blend_span,
+    color::{blend_coverage, Blending},
+    geom::{clip_line, clockwise_arc, diagonal, inside_arc, pixel, TOLERANCE},
+    paint::{blend_paint, Paint},
+    paint_span,
+    path::Path,
+    raster,
+    state::{render_state, RenderState},
+    target::Target,
+    types::{Angle, Circle, Ellipse, FillRule, Point, Rect, RoundedRect},
     Renderer,
 };
 
 pub struct AntiAliasingRender<I> {
+    state: RenderState,
     _phantom_data: PhantomData<I>,
 }
 
 impl<I> Default for AntiAliasingRender<I> {
     fn default() -> Self {
         Self {
-            _phantom_data: PhantomData::default(),
+            state: RenderState::default(),
+            _phantom_data: PhantomData,
         }
     }
 }
 
-impl Renderer for AntiAliasingRender<RgbaImage> {
-    type Image = RgbaImage;
-    type Pixel = <RgbaImage as GenericImageView>::Pixel;
+render_state!(AntiAliasingRender<I>, stroke);
+
+impl<I> AntiAliasingRender<I>
+where
+    I: Target,
+{
+    fn hairline(&self, img: &mut I, from: Point, to: Point, color: I::Pixel) {
+        let (width, height) = img.size();
+        let bounds = Rect::new(-2f64, -2f64, width as f64 + 3f64, height as f64 + 3f64);
+
+        let (from, to) = match clip_line(from, to, bounds) {
+            Some(clipped) => clipped,
+            None => return,
+        };
 
-    fn draw_line(
-        &self,
-        img: &mut Self::Image,
-        from: (u32, u32),
-        to: (u32, u32),
-        color: Self::Pixel,
-    ) {
         let (steep, x0, x1, y0, y1) = {
-            let (mut x0, mut y0) = (from.0 as i64, from.1 as i64);
-            let (mut x1, mut y1) = (to.0 as i64, to.1 as i64);
+            let (mut x0, mut y0) = (from.x, from.y);
+            let (mut x1, mut y1) = (to.x, to.y);
 
             let steep = (y1 - y0).abs() > (x1 - x0).abs();
 
@@ -53,454 +63,278 @@ impl Renderer for AntiAliasingRender<RgbaImage> {
         };
 
         let dx = x1 - x0;
-        let gradient = if dx == 0 {
-            1f64
-        } else {
-            (y1 - y0) as f64 / dx as f64
-        };
+        let gradient = if dx == 0f64 { 1f64 } else { (y1 - y0) / dx };
 
-        let mut intersect_y = y0 as f64;
+        for x in pixel(x0)..=pixel(x1) {
+            let intersect_y = y0 + gradient * (x as f64 - x0);
+            let y = intersect_y.floor() as i64;
+            let k = intersect_y - intersect_y.floor();
 
-        if steep {
-            for x in x0..=x1 {
-                blend_pixel(
-                    img,
-                    intersect_y as u32,
-                    x as u32,
-                    rgba_u8_pixel_with_brightness(color, 1f64 - intersect_y.fract()),
-                );
-                blend_pixel(
-                    img,
-                    intersect_y as u32 + 1,
-                    x as u32,
-                    rgba_u8_pixel_with_brightness(color, intersect_y.fract()),
-                );
-                intersect_y += gradient;
-            }
-        } else {
-            for x in x0..=x1 {
-                blend_pixel(
-                    img,
-                    x as u32,
-                    intersect_y as u32,
-                    rgba_u8_pixel_with_brightness(color, 1f64 - intersect_y.fract()),
-                );
-                blend_pixel(
-                    img,
-                    x as u32,
-                    intersect_y as u32 + 1,
-                    rgba_u8_pixel_with_brightness(color, intersect_y.fract()),
-                );
-                intersect_y += gradient;
+            if steep {
+                blend_coverage(img, y, x, color, 1f64 - k, self.blending());
+                blend_coverage(img, y + 1, x, color, k, self.blending());
+            } else {
+                blend_coverage(img, x, y, color, 1f64 - k, self.blending());
+                blend_coverage(img, x, y + 1, color, k, self.blending());
             }
         }
     }
 
-    fn draw_rect(&self, img: &mut Self::Image, rect: Rect, color: Self::Pixel) {
-        BasicRenderer::default().draw_rect(img, rect, color);
-    }
-
-    fn draw_filled_rect(&self, img: &mut Self::Image, rect: Rect, color: Self::Pixel) {
-        BasicRenderer::default().draw_filled_rect(img, rect, color);
-    }
+    fn stroke_polylines(&self, img: &mut I, polylines: &[(Vec<Point>, bool)], color: I::Pixel) {
+        let pieces: Vec<_> = polylines
+            .iter()
+            .flat_map(|(points, closed)| self.state.stroke.pieces(points, *closed, img.size()))
+            .collect();
+
+        if self.state.stroke.is_hairline() {
+            for (piece, closed) in pieces {
+                let closing = if closed { piece.first().copied() } else { None };
+
+                match piece.as_slice() {
+                    [p] => self.hairline(img, *p, *p, color),
+                    _ => {
+                        for (from, to) in
+                            piece.iter().zip(piece.iter().skip(1).chain(closing.iter()))
+                        {
+                            self.hairline(img, *from, *to, color);
+                        }
+                    }
+                }
+            }
 
-    fn draw_circle(&self, img: &mut Self::Image, circle: Circle, color: Self::Pixel) {
-        let radius = circle.radius();
-        if radius == 0 {
             return;
         }
 
-        let center = circle.center();
-        let (x0, y0) = center;
+        let polygons: Vec<_> = pieces
+            .iter()
+            .flat_map(|(piece, closed)| self.state.stroke.outline(piece, *closed))
+            .collect();
 
-        blend_pixel(img, x0 + radius, y0, color);
-        blend_pixel(img, x0 - radius, y0, color);
-        blend_pixel(img, x0, y0 + radius, color);
-        blend_pixel(img, x0, y0 - radius, color);
+        raster::fill_aa(
+            &polygons,
+            FillRule::NonZero,
+            img.size(),
+            |x, y, coverage| blend_coverage(img, x, y, color, coverage, self.blending()),
+        );
+    }
+}
 
-        let mut x = radius;
-        let mut y = 0;
+impl<I> Renderer for AntiAliasingRender<I>
+where
+    I: Target,
+{
+    type Image = I;
+    type Pixel = I::Pixel;
 
-        while x > y {
-            y += 1;
+    fn draw_line(&self, img: &mut Self::Image, from: Point, to: Point, color: Self::Pixel) {
+        if !self.state.stroke.is_solid_hairline() {
+            return self.stroke_path(img, &Path::polyline(&[from, to]), color);
+        }
 
-            let real_x = ((radius * radius - y * y) as f64).sqrt();
-            let real_x_ceil = real_x.ceil();
-            x = real_x_ceil as u32;
-            let k = real_x_ceil - real_x;
+        self.hairline(img, from, to, color);
+    }
 
-            let c1 = rgba_u8_pixel_with_brightness(color, 1f64 - k);
-            let c2 = rgba_u8_pixel_with_brightness(color, k);
+    fn draw_rect(&self, img: &mut Self::Image, rect: Rect, color: Self::Pixel) {
+        if !self.state.stroke.is_solid_hairline() {
+            return self.stroke_path(img, &Path::rect(rect), color);
+        }
 
-            blend_pixel(img, x0 + x, y0 + y, c1);
-            blend_pixel(img, x0 + x - 1, y0 + y, c2);
+        let (left, right) = (pixel(rect.left()), pixel(rect.right()));
+        let (top, bottom) = (pixel(rect.top()), pixel(rect.bottom()));
 
-            blend_pixel(img, x0 + y, y0 + x, c1);
-            blend_pixel(img, x0 + y, y0 + x - 1, c2);
+        // Each pixel is drawn once, even where sides meet or coincide.
+        blend_span(img, top, left, right, color, self.blending(), |_, _| true);
+        if bottom != top {
+            blend_span(img, bottom, left, right, color, self.blending(), |_, _| {
+                true
+            });
+        }
 
-            blend_pixel(img, x0 - y, y0 + x, c1);
-            blend_pixel(img, x0 - y, y0 + x - 1, c2);
+        let height = img.size().1 as i64;
 
-            blend_pixel(img, x0 - x, y0 + y, c1);
-            blend_pixel(img, x0 - x + 1, y0 + y, c2);
+        for y in top.clamp(-1, height) + 1..bottom.min(height) {
+            blend_coverage(img, left, y, color, 1f64, self.blending());
+            if right != left {
+                blend_coverage(img, right, y, color, 1f64, self.blending());
+            }
+        }
+    }
 
-            blend_pixel(img, x0 - x, y0 - y, c1);
-            blend_pixel(img, x0 - x + 1, y0 - y, c2);
+    fn draw_filled_rect<'a>(
+        &self,
+        img: &mut Self::Image,
+        rect: Rect,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = paint.into();
 
-            blend_pixel(img, x0 - y, y0 - x, c1);
-            blend_pixel(img, x0 - y, y0 - x + 1, c2);
+        let (left, right) = (pixel(rect.left()), pixel(rect.right()));
+        let (top, bottom) = (pixel(rect.top()), pixel(rect.bottom()));
 
-            blend_pixel(img, x0 + y, y0 - x, c1);
-            blend_pixel(img, x0 + y, y0 - x + 1, c2);
+        let height = img.size().1 as i64;
 
-            blend_pixel(img, x0 + x, y0 - y, c1);
-            blend_pixel(img, x0 + x - 1, y0 - y, c2);
+        for y in top.max(0)..=bottom.min(height - 1) {
+            paint_span(img, y, left, right, &paint, self.blending(), |_, _| true);
         }
     }
 
-    fn draw_filled_circle(&self, img: &mut Self::Image, circle: Circle, color: Self::Pixel) {
-        let radius = circle.radius();
-        if radius == 0 {
-            return;
+    fn draw_circle(&self, img: &mut Self::Image, circle: Circle, color: Self::Pixel) {
+        if !self.state.stroke.is_solid_hairline() {
+            return self.stroke_path(img, &Path::circle(circle), color);
         }
 
-        let center = circle.center();
-        let (x0, y0) = center;
-
-        blend_pixel(img, x0 + radius, y0, color);
-        blend_pixel(img, x0 - radius, y0, color);
-        blend_pixel(img, x0, y0 + radius, color);
-        blend_pixel(img, x0, y0 - radius, color);
-
-        self.draw_line(img, (x0 - radius, y0), (x0 + radius, y0), color);
-        self.draw_line(img, (x0, y0 - radius), (x0, y0 + radius), color);
-
-        let mut x = radius;
-        let mut y = 0;
-
-        while x > y {
-            y += 1;
-
-            let real_x = ((radius * radius - y * y) as f64).sqrt();
-            let real_x_ceil = real_x.ceil();
-            x = real_x_ceil as u32;
-            let k = real_x_ceil - real_x;
-
-            let c1 = rgba_u8_pixel_with_brightness(color, 1f64 - k);
-            let c2 = rgba_u8_pixel_with_brightness(color, k);
-
-            blend_pixel(img, x0 + x, y0 + y, c1);
-            blend_pixel(img, x0 + x - 1, y0 + y, c2);
-
-            blend_pixel(img, x0 + y, y0 + x, c1);
-            blend_pixel(img, x0 + y, y0 + x - 1, c2);
-
-            blend_pixel(img, x0 - y, y0 + x, c1);
-            blend_pixel(img, x0 - y, y0 + x - 1, c2);
-
-            blend_pixel(img, x0 - x, y0 + y, c1);
-            blend_pixel(img, x0 - x + 1, y0 + y, c2);
-
-            blend_pixel(img, x0 - x, y0 - y, c1);
-            blend_pixel(img, x0 - x + 1, y0 - y, c2);
-
-            blend_pixel(img, x0 - y, y0 - x, c1);
-            blend_pixel(img, x0 - y, y0 - x + 1, c2);
-
-            blend_pixel(img, x0 + y, y0 - x, c1);
-            blend_pixel(img, x0 + y, y0 - x + 1, c2);
-
-            blend_pixel(img, x0 + x, y0 - y, c1);
-            blend_pixel(img, x0 + x - 1, y0 - y, c2);
-
-            if x0 >= x {
-                self.draw_line(
-                    img,
-                    ((x0 - x + 1), (y0 + y)),
-                    ((x0 + x - 1), (y0 + y)),
-                    color,
-                );
-            }
-
-            if x0 >= y {
-                self.draw_line(
-                    img,
-                    ((x0 - y), (y0 + x - 1)),
-                    ((x0 + y), (y0 + x - 1)),
-                    color,
-                );
+        wu_ellipse(img.size(), circle.into(), |x, y, brightness, _| {
+            if brightness > 0f64 {
+                blend_coverage(img, x, y, color, brightness, self.blending());
             }
+        });
+    }
 
-            if x0 >= x && y0 >= y {
-                self.draw_line(
-                    img,
-                    ((x0 - x + 1), (y0 - y)),
-                    ((x0 + x - 1), (y0 - y)),
-                    color,
-                );
-            }
+    fn draw_filled_circle<'a>(
+        &self,
+        img: &mut Self::Image,
+        circle: Circle,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = paint.into();
 
-            if x0 >= y && y0 >= x {
-                self.draw_line(
-                    img,
-                    ((x0 - y), (y0 - x + 1)),
-                    ((x0 + y), (y0 - x + 1)),
-                    color,
-                );
-            }
-        }
+        wu_filled_ellipse(img, circle.into(), &paint, self.blending(), |_, _| true);
     }
 
     fn draw_arc(
         &self,
         img: &mut Self::Image,
         circle: Circle,
-        mut start: Angle,
-        mut end: Angle,
+        start: Angle,
+        end: Angle,
         color: Self::Pixel,
     ) {
-        let radius = circle.radius();
-        if radius == 0 {
-            return;
-        }
-
-        let center = circle.center();
-        let (x0, y0) = center;
-
-        let mut x = radius;
-        let mut y = 0;
-
-        start.normalize();
-        end.normalize();
-
-        let (start, end) = if start.to_degrees() > end.to_degrees() {
-            (end, start)
-        } else {
-            (start, end)
-        };
-
-        if 0f64 >= start.to_degrees() && 0f64 <= end.to_degrees() {
-            blend_pixel(img, x0 + radius, y0, color);
-        }
-
-        if 90f64 >= start.to_degrees() && 90f64 <= end.to_degrees() {
-            blend_pixel(img, x0, y0 + radius, color);
-        }
+        let (start, end) = clockwise_arc(start, end);
 
-        if 180f64 >= start.to_degrees() && 180f64 <= end.to_degrees() {
-            blend_pixel(img, x0 - radius, y0, color);
+        if !self.state.stroke.is_solid_hairline() {
+            return self.stroke_path(img, &Path::arc(circle, start, end), color);
         }
 
-        if 270f64 >= start.to_degrees() && 270f64 <= end.to_degrees() {
-            blend_pixel(img, x0, y0 - radius, color);
-        }
-
-        while x > y {
-            y += 1;
-
-            let real_x = ((radius * radius - y * y) as f64).sqrt();
-            let real_x_ceil = real_x.ceil();
-            x = real_x_ceil as u32;
-            let k = real_x_ceil - real_x;
-
-            let c1 = rgba_u8_pixel_with_brightness(color, 1f64 - k);
-            let c2 = rgba_u8_pixel_with_brightness(color, k);
-
-            if inside_arc((x as f64, y as f64), start, end) {
-                blend_pixel(img, x0 + x, y0 + y, c1);
-                blend_pixel(img, x0 + x - 1, y0 + y, c2);
-            }
-
-            if inside_arc((y as f64, x as f64), start, end) {
-                blend_pixel(img, x0 + y, y0 + x, c1);
-                blend_pixel(img, x0 + y, y0 + x - 1, c2);
-            }
-
-            if inside_arc((-(y as f64), x as f64), start, end) {
-                blend_pixel(img, x0 - y, y0 + x, c1);
-                blend_pixel(img, x0 - y, y0 + x - 1, c2);
-            }
-
-            if inside_arc((-(x as f64), y as f64), start, end) {
-                blend_pixel(img, x0 - x, y0 + y, c1);
-                blend_pixel(img, x0 - x + 1, y0 + y, c2);
-            }
-
-            if inside_arc((-(x as f64), -(y as f64)), start, end) {
-                blend_pixel(img, x0 - x, y0 - y, c1);
-                blend_pixel(img, x0 - x + 1, y0 - y, c2);
-            }
-
-            if inside_arc((-(y as f64), -(x as f64)), start, end) {
-                blend_pixel(img, x0 - y, y0 - x, c1);
-                blend_pixel(img, x0 - y, y0 - x + 1, c2);
-            }
-
-            if inside_arc((y as f64, -(x as f64)), start, end) {
-                blend_pixel(img, x0 + y, y0 - x, c1);
-                blend_pixel(img, x0 + y, y0 - x + 1, c2);
+        wu_ellipse(img.size(), circle.into(), |x, y, brightness, real| {
+            if brightness > 0f64 && inside_arc(real, start, end) {
+                blend_coverage(img, x, y, color, brightness, self.blending());
             }
-
-            if inside_arc((x as f64, -(y as f64)), start, end) {
-                blend_pixel(img, x0 + x, y0 - y, c1);
-                blend_pixel(img, x0 + x - 1, y0 - y, c2);
-            }
-        }
+        });
     }
 
-    fn draw_filled_arc(
+    fn draw_filled_arc<'a>(
         &self,
         img: &mut Self::Image,
         circle: Circle,
-        mut start: Angle,
-        mut end: Angle,
-        color: Self::Pixel,
+        start: Angle,
+        end: Angle,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
     ) {
-        let radius = circle.radius();
-        if radius == 0 {
-            return;
-        }
-
-        let center = circle.center();
-        let (x0, y0) = center;
-
-        let mut x = radius;
-        let mut y = 0;
+        let paint = paint.into();
 
-        start.normalize();
-        end.normalize();
+        let (start, end) = clockwise_arc(start, end);
 
-        let (start, end) = if start.to_degrees() > end.to_degrees() {
-            (end, start)
-        } else {
-            (start, end)
+        let center = circle.center();
+        let filter_fn = |x: i64, y: i64| {
+            (x as f64 == center.x && y as f64 == center.y)
+                || inside_arc((x as f64 - center.x, y as f64 - center.y), start, end)
         };
 
-        let filter_fn = |x, y| inside_arc((x as f64 - x0 as f64, y as f64 - y0 as f64), start, end);
-
-        let basic_renderer = BasicRenderer::default();
-
-        basic_renderer.filtered_draw_line(
-            img,
-            (x0 - radius, y0),
-            (x0 + radius, y0),
-            color,
-            filter_fn,
-        );
-        basic_renderer.filtered_draw_line(
-            img,
-            (x0, y0 - radius),
-            (x0, y0 + radius),
-            color,
-            filter_fn,
-        );
-
-        while x > y {
-            y += 1;
-
-            let real_x = ((radius * radius - y * y) as f64).sqrt();
-            let real_x_ceil = real_x.ceil();
-            x = real_x_ceil as u32;
-            let k = real_x_ceil - real_x;
-
-            let c1 = rgba_u8_pixel_with_brightness(color, 1f64 - k);
-            let c2 = rgba_u8_pixel_with_brightness(color, k);
-
-            if inside_arc((x as f64, y as f64), start, end) {
-                blend_pixel(img, x0 + x, y0 + y, c1);
-                blend_pixel(img, x0 + x - 1, y0 + y, c2);
-            }
+        wu_filled_ellipse(img, circle.into(), &paint, self.blending(), filter_fn);
+    }
 
-            if inside_arc((y as f64, x as f64), start, end) {
-                blend_pixel(img, x0 + y, y0 + x, c1);
-                blend_pixel(img, x0 + y, y0 + x - 1, c2);
-            }
+    fn draw_ellipse(&self, img: &mut Self::Image, ellipse: Ellipse, color: Self::Pixel) {
+        if !self.state.stroke.is_solid_hairline() || !ellipse.is_axis_aligned() {
+            return self.stroke_path(img, &Path::ellipse(ellipse), color);
+        }
 
-            if inside_arc((-(y as f64), x as f64), start, end) {
-                blend_pixel(img, x0 - y, y0 + x, c1);
-                blend_pixel(img, x0 - y, y0 + x - 1, c2);
+        wu_ellipse(img.size(), ellipse, |x, y, brightness, _| {
+            if brightness > 0f64 {
+                blend_coverage(img, x, y, color, brightness, self.blending());
             }
+        });
+    }
 
-            if inside_arc((-(x as f64), y as f64), start, end) {
-                blend_pixel(img, x0 - x, y0 + y, c1);
-                blend_pixel(img, x0 - x + 1, y0 + y, c2);
-            }
+    fn draw_filled_ellipse<'a>(
+        &self,
+        img: &mut Self::Image,
+        ellipse: Ellipse,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = paint.into();
 
-            if inside_arc((-(x as f64), -(y as f64)), start, end) {
-                blend_pixel(img, x0 - x, y0 - y, c1);
-                blend_pixel(img, x0 - x + 1, y0 - y, c2);
-            }
+        if !ellipse.is_axis_aligned() {
+            return self.fill_path(img, &Path::ellipse(ellipse), FillRule::NonZero, paint);
+        }
 
-            if inside_arc((-(y as f64), -(x as f64)), start, end) {
-                blend_pixel(img, x0 - y, y0 - x, c1);
-                blend_pixel(img, x0 - y, y0 - x + 1, c2);
-            }
+        wu_filled_ellipse(img, ellipse, &paint, self.blending(), |_, _| true);
+    }
 
-            if inside_arc((y as f64, -(x as f64)), start, end) {
-                blend_pixel(img, x0 + y, y0 - x, c1);
-                blend_pixel(img, x0 + y, y0 - x + 1, c2);
-            }
+    fn draw_elliptical_arc(
+        &self,
+        img: &mut Self::Image,
+        ellipse: Ellipse,
+        start: Angle,
+        end: Angle,
+        color: Self::Pixel,
+    ) {
+        let (start, end) = clockwise_arc(start, end);
 
-            if inside_arc((x as f64, -(y as f64)), start, end) {
-                blend_pixel(img, x0 + x, y0 - y, c1);
-                blend_pixel(img, x0 + x - 1, y0 - y, c2);
-            }
+        if !self.state.stroke.is_solid_hairline() || !ellipse.is_axis_aligned() {
+            return self.stroke_path(img, &Path::elliptical_arc(ellipse, start, end), color);
+        }
 
-            if x0 >= x {
-                basic_renderer.filtered_draw_line(
-                    img,
-                    (x0 - x + 1, y0 + y),
-                    (x0 + x - 1, y0 + y),
-                    color,
-                    filter_fn,
-                );
+        wu_ellipse(img.size(), ellipse, |x, y, brightness, real| {
+            if brightness > 0f64 && inside_arc(real, start, end) {
+                blend_coverage(img, x, y, color, brightness, self.blending());
             }
+        });
+    }
 
-            if x0 >= y {
-                basic_renderer.filtered_draw_line(
-                    img,
-                    ((x0 - y), (y0 + x - 1)),
-                    ((x0 + y), (y0 + x - 1)),
-                    color,
-                    filter_fn,
-                );
-            }
+    fn draw_filled_elliptical_arc<'a>(
+        &self,
+        img: &mut Self::Image,
+        ellipse: Ellipse,
+        start: Angle,
+        end: Angle,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = paint.into();
 
-            if x0 >= x && y0 >= y {
-                basic_renderer.filtered_draw_line(
-                    img,
-                    ((x0 - x + 1), (y0 - y)),
-                    ((x0 + x - 1), (y0 - y)),
-                    color,
-                    filter_fn,
-                );
-            }
+        let (start, end) = clockwise_arc(start, end);
 
-            if x0 >= y && y0 >= x {
-                basic_renderer.filtered_draw_line(
-                    img,
-                    ((x0 - y), (y0 - x + 1)),
-                    ((x0 + y), (y0 - x + 1)),
-                    color,
-                    filter_fn,
-                );
-            }
+        if !ellipse.is_axis_aligned() {
+            let path = Path::elliptical_pie(ellipse, start, end);
+            return self.fill_path(img, &path, FillRule::NonZero, paint);
         }
 
-        blend_pixel(img, x0, y0, color);
+        let center = ellipse.center();
+        let filter_fn = |x: i64, y: i64| {
+            (x as f64 == center.x && y as f64 == center.y)
+                || inside_arc((x as f64 - center.x, y as f64 - center.y), start, end)
+        };
+
+        wu_filled_ellipse(img, ellipse, &paint, self.blending(), filter_fn);
     }
 
     fn draw_rounded_rect(
         &self,
         img: &mut Self::Image,
         rect: Rect,
-        corner_radius: u32,
+        corner_radius: f64,
         color: Self::Pixel,
     ) {
+        let corner_radius = RoundedRect::new(rect, corner_radius).top_left().x;
+
+        if !self.state.stroke.is_solid_hairline() {
+            let path = Path::rounded_rect(rect, corner_radius);
+            return self.stroke_path(img, &path, color);
+        }
+
         self.draw_line(
             img,
-            (rect.left(), rect.top() + corner_radius),
-            (rect.left(), rect.bottom() - corner_radius),
+            Point::new(rect.left(), rect.top() + corner_radius),
+            Point::new(rect.left(), rect.bottom() - corner_radius),
             color,
         );
         self.draw_arc(
@@ -526,8 +360,8 @@ impl Renderer for AntiAliasingRender<RgbaImage> {
 
         self.draw_line(
             img,
-            (rect.right(), rect.top() + corner_radius),
-            (rect.right(), rect.bottom() - corner_radius),
+            Point::new(rect.right(), rect.top() + corner_radius),
+            Point::new(rect.right(), rect.bottom() - corner_radius),
             color,
         );
         self.draw_arc(
@@ -553,109 +387,282 @@ impl Renderer for AntiAliasingRender<RgbaImage> {
 
         self.draw_line(
             img,
-            (rect.left() + corner_radius, rect.top()),
-            (rect.right() - corner_radius, rect.top()),
+            Point::new(rect.left() + corner_radius, rect.top()),
+            Point::new(rect.right() - corner_radius, rect.top()),
             color,
         );
         self.draw_line(
             img,
-            (rect.left() + corner_radius, rect.bottom()),
-            (rect.right() - corner_radius, rect.bottom()),
+            Point::new(rect.left() + corner_radius, rect.bottom()),
+            Point::new(rect.right() - corner_radius, rect.bottom()),
             color,
         );
     }
 
-    fn draw_filled_rounded_rect(
+    fn draw_filled_rounded_rect<'a>(
         &self,
         img: &mut Self::Image,
         rect: Rect,
-        corner_radius: u32,
-        color: Self::Pixel,
+        corner_radius: f64,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
     ) {
-        self.draw_filled_rect(
-            img,
-            Rect::new(
-                rect.left() + corner_radius,
-                rect.top(),
-                rect.width() - corner_radius * 2,
-                rect.height(),
-            ),
-            color,
-        );
+        // One outline, so no pixel is blended twice where sides meet corners.
+        let path = Path::rounded_rect(rect, corner_radius);
+        self.fill_path(img, &path, FillRule::NonZero, paint);
+    }
 
-        self.draw_filled_rect(
-            img,
-            Rect::new(
-                rect.left(),
-                rect.top() + corner_radius,
-                corner_radius,
-                rect.height() - corner_radius * 2,
-            ),
-            color,
-        );
+    fn draw_polyline(&self, img: &mut Self::Image, points: &[Point], color: Self::Pixel) {
+        self.stroke_path(img, &Path::polyline(points), color);
+    }
 
-        self.draw_filled_rect(
-            img,
-            Rect::new(
-                rect.right() - corner_radius,
-                rect.top() + corner_radius,
-                corner_radius,
-                rect.height() - corner_radius * 2,
-            ),
-            color,
-        );
+    fn draw_polygon(&self, img: &mut Self::Image, points: &[Point], color: Self::Pixel) {
+        self.stroke_path(img, &Path::polygon(points), color);
+    }
 
-        self.draw_filled_arc(
-            img,
-            Circle::new(
-                (rect.left() + corner_radius, rect.top() + corner_radius),
-                corner_radius,
-            ),
-            Angle::Degrees(180f64),
-            Angle::Degrees(270f64),
-            color,
-        );
+    fn draw_filled_polygon<'a>(
+        &self,
+        img: &mut Self::Image,
+        points: &[Point],
+        rule: FillRule,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = paint.into();
 
-        self.draw_filled_arc(
-            img,
-            Circle::new(
-                (rect.left() + corner_radius, rect.bottom() - corner_radius),
-                corner_radius,
-            ),
-            Angle::Degrees(90f64),
-            Angle::Degrees(180f64),
-            color,
-        );
+        raster::fill_aa(&[points.to_vec()], rule, img.size(), |x, y, coverage| {
+            blend_paint(img, x, y, &paint, coverage, self.blending())
+        });
+    }
 
-        self.draw_filled_arc(
-            img,
-            Circle::new(
-                (rect.right() - corner_radius, rect.top() + corner_radius),
-                corner_radius,
-            ),
-            Angle::Degrees(270f64),
-            Angle::Degrees(360f64),
-            color,
+    fn stroke_path(&self, img: &mut Self::Image, path: &Path, color: Self::Pixel) {
+        self.stroke_polylines(img, &path.flatten(TOLERANCE), color);
+    }
+
+    fn fill_path<'a>(
+        &self,
+        img: &mut Self::Image,
+        path: &Path,
+        rule: FillRule,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = paint.into();
+
+        let polygons: Vec<_> = path
+            .flatten(TOLERANCE)
+            .into_iter()
+            .map(|(p, _)| p)
+            .collect();
+
+        raster::fill_aa(&polygons, rule, img.size(), |x, y, coverage| {
+            blend_paint(img, x, y, &paint, coverage, self.blending())
+        });
+    }
+}
+
+/// Walks the outline of the axis-aligned `ellipse` Wu-style, calling `plot`
+/// with each pixel, its brightness and the exact outline point (relative to
+/// the center) it was derived from.
+///
+/// Rows are scanned where the outline is steep and columns where it is
+/// flat, so every octant gets one pair of pixels per step.
+fn wu_ellipse<F: FnMut(i64, i64, f64, (f64, f64))>(
+    dimensions: (u32, u32),
+    ellipse: Ellipse,
+    mut plot: F,
+) {
+    let (rx, ry) = (ellipse.radius_x(), ellipse.radius_y());
+    if !(rx > 0f64 && ry > 0f64) {
+        return;
+    }
+
+    let center = ellipse.center();
+    let (width, height) = dimensions;
+    let (diagonal_x, diagonal_y) = diagonal(rx, ry);
+
+    let top = (center.y - diagonal_y).ceil().max(0f64) as i64;
+    let bottom = (center.y + diagonal_y).floor().min(height as f64 - 1f64) as i64;
+
+    for y in top..=bottom {
+        let dy = y as f64 - center.y;
+        let dx = rx * (1f64 - (dy / ry).powi(2)).max(0f64).sqrt();
+
+        for real_x in [center.x + dx, center.x - dx] {
+            let (x, k) = (clamped(real_x.floor(), width), real_x - real_x.floor());
+
+            plot(x, y, 1f64 - k, (real_x - center.x, dy));
+            plot(x + 1, y, k, (real_x - center.x, dy));
+        }
+    }
+
+    let left = (center.x - diagonal_x).floor().max(-1f64) as i64 + 1;
+    let right = (center.x + diagonal_x).ceil().min(width as f64) as i64 - 1;
+
+    for x in left..=right {
+        let dx = x as f64 - center.x;
+        if dx.abs() >= diagonal_x {
+            continue;
+        }
+
+        let dy = ry * (1f64 - (dx / rx).powi(2)).max(0f64).sqrt();
+
+        for real_y in [center.y + dy, center.y - dy] {
+            let (y, k) = (clamped(real_y.floor(), height), real_y - real_y.floor());
+
+            plot(x, y, 1f64 - k, (dx, real_y - center.y));
+            plot(x, y + 1, k, (dx, real_y - center.y));
+        }
+    }
+}
+
+/// Fills the axis-aligned `ellipse` with full-brightness spans and
+/// anti-aliases the pixels just outside its outline, skipping pixels
+/// rejected by `filter`.
+fn wu_filled_ellipse<I, F>(
+    img: &mut I,
+    ellipse: Ellipse,
+    paint: &Paint<I::Pixel>,
+    blending: Blending,
+    filter: F,
+) where
+    I: Target,
+    F: Fn(i64, i64) -> bool,
+{
+    let (rx, ry) = (ellipse.radius_x(), ellipse.radius_y());
+    if !(rx > 0f64 && ry > 0f64) {
+        return;
+    }
+
+    let center = ellipse.center();
+    let (width, height) = img.size();
+    let (diagonal_x, diagonal_y) = diagonal(rx, ry);
+
+    let top = (center.y - ry).ceil().max(0f64) as i64;
+    let bottom = (center.y + ry).floor().min(height as f64 - 1f64) as i64;
+
+    for y in top..=bottom {
+        let dy = y as f64 - center.y;
+        let dx = rx * (1f64 - (dy / ry).powi(2)).max(0f64).sqrt();
+        let (real_left, real_right) = (center.x - dx, center.x + dx);
+        let (left, right) = (
+            clamped(real_left.ceil(), width),
+            clamped(real_right.floor(), width),
         );
 
-        self.draw_filled_arc(
-            img,
-            Circle::new(
-                (rect.right() - corner_radius, rect.bottom() - corner_radius),
-                corner_radius,
-            ),
-            Angle::Degrees(0f64),
-            Angle::Degrees(90f64),
-            color,
+        paint_span(img, y, left, right, paint, blending, &filter);
+
+        if dy.abs() <= diagonal_y {
+            if filter(left - 1, y) {
+                blend_paint(
+                    img,
+                    left - 1,
+                    y,
+                    paint,
+                    real_left.ceil() - real_left,
+                    blending,
+                );
+            }
+
+            if filter(right + 1, y) {
+                blend_paint(
+                    img,
+                    right + 1,
+                    y,
+                    paint,
+                    real_right - real_right.floor(),
+                    blending,
+                );
+            }
+        }
+    }
+
+    let left = (center.x - diagonal_x).floor().max(-1f64) as i64 + 1;
+    let right = (center.x + diagonal_x).ceil().min(width as f64) as i64 - 1;
+
+    for x in left..=right {
+        let dx = x as f64 - center.x;
+        if dx.abs() >= diagonal_x {
+            continue;
+        }
+
+        let dy = ry * (1f64 - (dx / rx).powi(2)).max(0f64).sqrt();
+        let (real_top, real_bottom) = (center.y - dy, center.y + dy);
+        let (top, bottom) = (
+            clamped(real_top.ceil(), height),
+            clamped(real_bottom.floor(), height),
         );
+
+        if filter(x, top - 1) {
+            blend_paint(img, x, top - 1, paint, real_top.ceil() - real_top, blending);
+        }
+
+        if filter(x, bottom + 1) {
+            blend_paint(
+                img,
+                x,
+                bottom + 1,
+                paint,
+                real_bottom - real_bottom.floor(),
+                blending,
+            );
+        }
     }
 }
 
-fn rgba_u8_pixel_with_brightness(pixel: Rgba<u8>, brightness: f64) -> Rgba<u8> {
-    Rgba([
-        pixel.0[0],
-        pixel.0[1],
-        pixel.0[2],
-        (255.0 * brightness) as u8,
-    ])
+/// The pixel index `v`, kept within a couple of pixels of `0..size` so
+/// stepping to a neighbour can't overflow. Indices moved by this are off the
+/// image either way.
+fn clamped(v: f64, size: u32) -> i64 {
+    v.clamp(-2f64, size as f64 + 1f64) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stroke::Stroke;
+
+    use super::*;
+
+    #[test]
+    fn anti_aliasing_works_for_any_pixel_type() {
+        let circle = Circle::new((10f64, 10f64), 6.5f64);
+
+        let mut img = image::GrayImage::new(20, 20);
+        AntiAliasingRender::default().draw_filled_circle(&mut img, circle, image::Luma([200]));
+        assert_eq!(*img.get_pixel(10, 10), image::Luma([200]));
+        assert_eq!(*img.get_pixel(10, 3), image::Luma([100]));
+        assert_eq!(*img.get_pixel(10, 2), image::Luma([0]));
+
+        let mut img = image::RgbImage::from_pixel(20, 20, image::Rgb([255, 255, 255]));
+        AntiAliasingRender::default()
+            .with_stroke(Stroke::new(2f64))
+            .draw_line(
+                &mut img,
+                Point::new(2f64, 10f64),
+                Point::new(18f64, 10.5f64),
+                image::Rgb([0, 0, 0]),
+            );
+        let edge = img.get_pixel(10, 9)[0];
+        assert!(edge > 0 && edge < 255);
+
+        let mut img = image::ImageBuffer::<image::Rgba<u16>, _>::new(20, 20);
+        AntiAliasingRender::default().draw_filled_circle(
+            &mut img,
+            circle,
+            image::Rgba([65535, 0, 0, 65535]),
+        );
+        assert_eq!(*img.get_pixel(10, 3), image::Rgba([65535, 0, 0, 32768]));
+
+        let mut img = image::ImageBuffer::<image::Rgba<f32>, _>::new(20, 20);
+        AntiAliasingRender::default().draw_filled_circle(
+            &mut img,
+            circle,
+            image::Rgba([1f32, 0f32, 0f32, 1f32]),
+        );
+        assert_eq!(
+            *img.get_pixel(10, 10),
+            image::Rgba([1f32, 0f32, 0f32, 1f32])
+        );
+        assert_eq!(
+            *img.get_pixel(10, 3),
+            image::Rgba([1f32, 0f32, 0f32, 0.5f32])
+        );
+    }
 }
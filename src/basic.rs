@@ -1,53 +1,88 @@
 use std::marker::PhantomData;
 
-use image::{GenericImage, GenericImageView};
-
 use crate::{
-    blend_pixel,
-    geom::inside_arc,
-    types::{Angle, Circle, Rect},
+    blend_pixel, blend_span,
+    error::MAX_COORDINATE,
+    geom::{clip_line, clockwise_arc, inside_arc, pixel, TOLERANCE},
+    layout::{TextBox, TextRun},
+    paint::Paint,
+    paint_span,
+    path::Path,
+    raster,
+    state::{render_state, RenderState},
+    target::Target,
+    text::{render_text, TextStyle},
+    types::{Angle, Circle, Ellipse, FillRule, Point, Rect, RoundedRect},
     Renderer,
 };
 
 pub struct BasicRenderer<I> {
+    state: RenderState,
     _phantom_data: PhantomData<I>,
 }
 
 impl<I> Default for BasicRenderer<I> {
     fn default() -> Self {
         Self {
-            _phantom_data: PhantomData::default(),
+            state: RenderState::default(),
+            _phantom_data: PhantomData,
         }
     }
 }
 
-impl<I: GenericImage> BasicRenderer<I> {
-    pub(crate) fn filtered_draw_line<F: Fn(u32, u32) -> bool>(
+render_state!(BasicRenderer<I>, stroke);
+
+impl<I> BasicRenderer<I>
+where
+    I: Target,
+{
+    /// Draws `text` on a single line placed at `at` as `style` says, through
+    /// the renderer's clip and composite.
+    pub fn draw_text(
         &self,
         img: &mut I,
-        from: (u32, u32),
-        to: (u32, u32),
-        color: <I as GenericImageView>::Pixel,
-        filter: F,
+        text: &str,
+        at: impl Into<Point>,
+        style: &TextStyle,
+        color: I::Pixel,
     ) {
-        let (x0, y0) = from;
-        let (x1, y1) = to;
-        let (dx, dy) = (
-            (x1 as i64 - x0 as i64).abs(),
-            -((y1 as i64 - y0 as i64).abs()),
-        );
+        render_text(img, text, at.into(), style, color, self.blending());
+    }
+
+    /// Lays `runs` out in `rect` as `text_box` says and draws them through
+    /// the renderer's clip and composite.
+    pub fn draw_text_box(
+        &self,
+        img: &mut I,
+        rect: Rect,
+        text_box: &TextBox,
+        runs: &[TextRun<I::Pixel>],
+    ) {
+        text_box.layout(rect, runs).blend(img, self.blending());
+    }
+
+    pub(crate) fn hairline(&self, img: &mut I, from: Point, to: Point, color: I::Pixel) {
+        let (width, height) = img.size();
+        let bounds = Rect::new(-1f64, -1f64, width as f64 + 1f64, height as f64 + 1f64);
+
+        let (from, to) = match clip_line(from, to, bounds) {
+            Some(clipped) => clipped,
+            None => return,
+        };
+
+        let (x0, y0) = (pixel(from.x), pixel(from.y));
+        let (x1, y1) = (pixel(to.x), pixel(to.y));
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
         let sx = if x0 < x1 { 1 } else { -1 };
         let sy = if y0 < y1 { 1 } else { -1 };
 
         let mut err = dx + dy;
-        let (mut x, mut y) = (x0 as i64, y0 as i64);
+        let (mut x, mut y) = (x0, y0);
 
         loop {
-            if filter(x as u32, y as u32) {
-                blend_pixel(img, x as u32, y as u32, color);
-            }
+            blend_pixel(img, x, y, color, self.blending());
 
-            if x as u32 == x1 && y as u32 == y1 {
+            if x == x1 && y == y1 {
                 break;
             }
 
@@ -64,282 +99,298 @@ impl<I: GenericImage> BasicRenderer<I> {
             }
         }
     }
-}
-
-impl<I: GenericImage> Renderer for BasicRenderer<I> {
-    type Image = I;
-    type Pixel = <I as GenericImageView>::Pixel;
 
-    fn draw_line(
+    pub(crate) fn stroke_polylines(
         &self,
-        img: &mut Self::Image,
-        from: (u32, u32),
-        to: (u32, u32),
-        color: Self::Pixel,
+        img: &mut I,
+        polylines: &[(Vec<Point>, bool)],
+        color: I::Pixel,
     ) {
-        self.filtered_draw_line(img, from, to, color, |_, _| true);
-    }
+        let pieces: Vec<_> = polylines
+            .iter()
+            .flat_map(|(points, closed)| self.state.stroke.pieces(points, *closed, img.size()))
+            .collect();
+
+        if self.state.stroke.is_hairline() {
+            for (piece, closed) in pieces {
+                let closing = if closed { piece.first().copied() } else { None };
+
+                match piece.as_slice() {
+                    [p] => self.hairline(img, *p, *p, color),
+                    _ => {
+                        for (from, to) in
+                            piece.iter().zip(piece.iter().skip(1).chain(closing.iter()))
+                        {
+                            self.hairline(img, *from, *to, color);
+                        }
+                    }
+                }
+            }
 
-    fn draw_rect(&self, img: &mut Self::Image, rect: Rect, color: Self::Pixel) {
-        for x in rect.left()..=rect.right() {
-            blend_pixel(img, x, rect.top(), color);
-            blend_pixel(img, x, rect.bottom(), color);
+            return;
         }
 
-        for y in rect.top()..=rect.bottom() {
-            blend_pixel(img, rect.left(), y, color);
-            blend_pixel(img, rect.right(), y, color);
-        }
-    }
+        let polygons: Vec<_> = pieces
+            .iter()
+            .flat_map(|(piece, closed)| self.state.stroke.outline(piece, *closed))
+            .collect();
 
-    fn draw_filled_rect(&self, img: &mut Self::Image, rect: Rect, color: Self::Pixel) {
-        for x in rect.left()..=rect.right() {
-            for y in rect.top()..=rect.bottom() {
-                blend_pixel(img, x, y, color);
-            }
-        }
+        raster::fill(&polygons, FillRule::NonZero, img.size(), |y, from, to| {
+            blend_span(img, y, from, to, color, self.blending(), |_, _| true)
+        });
     }
 
-    fn draw_circle(&self, img: &mut Self::Image, circle: Circle, color: Self::Pixel) {
-        let radius = circle.radius();
-        if radius == 0 {
+    /// Fills the axis-aligned `ellipse` row by row, skipping pixels rejected
+    /// by `filter`.
+    pub(crate) fn fill_ellipse_spans<F: Fn(i64, i64) -> bool>(
+        &self,
+        img: &mut I,
+        ellipse: Ellipse,
+        paint: &Paint<I::Pixel>,
+        filter: F,
+    ) {
+        let (rx, ry) = (ellipse.radius_x(), ellipse.radius_y());
+        if !(rx > 0f64 && ry > 0f64) {
             return;
         }
 
-        let center = circle.center();
-        let (x0, y0) = center;
-
-        let mut x = 0;
-        let mut y = radius;
-        let mut p = 1 - radius as i64;
-
-        while x <= y {
-            blend_pixel(img, x0 + x, y0 + y, color);
-            blend_pixel(img, x0 + y, y0 + x, color);
-            blend_pixel(img, x0 - y, y0 + x, color);
-            blend_pixel(img, x0 - x, y0 + y, color);
-            blend_pixel(img, x0 - x, y0 - y, color);
-            blend_pixel(img, x0 - y, y0 - x, color);
-            blend_pixel(img, x0 + y, y0 - x, color);
-            blend_pixel(img, x0 + x, y0 - y, color);
+        let center = ellipse.center();
+        let height = img.size().1 as i64;
+
+        let top = (center.y - ry).ceil().max(0f64) as i64;
+        let bottom = (center.y + ry).floor().min(height as f64 - 1f64) as i64;
+
+        for y in top..=bottom {
+            let dy = (y as f64 - center.y) / ry;
+            let dx = rx * (1f64 - dy * dy).max(0f64).sqrt();
+
+            paint_span(
+                img,
+                y,
+                (center.x - dx).ceil() as i64,
+                (center.x + dx).floor() as i64,
+                paint,
+                self.blending(),
+                &filter,
+            );
+        }
+    }
+}
 
-            x += 1;
-            if p < 0 {
-                p += 2 * x as i64 + 1;
-            } else {
-                y -= 1;
-                p += 2 * (x as i64 - y as i64) + 1;
-            }
+impl<I> Renderer for BasicRenderer<I>
+where
+    I: Target,
+{
+    type Image = I;
+    type Pixel = I::Pixel;
+
+    fn draw_line(&self, img: &mut Self::Image, from: Point, to: Point, color: Self::Pixel) {
+        if !self.state.stroke.is_solid_hairline() {
+            return self.stroke_path(img, &Path::polyline(&[from, to]), color);
         }
+
+        self.hairline(img, from, to, color);
     }
 
-    fn draw_filled_circle(&self, img: &mut Self::Image, circle: Circle, color: Self::Pixel) {
-        let r = circle.radius();
-        if r == 0 {
-            return;
+    fn draw_rect(&self, img: &mut Self::Image, rect: Rect, color: Self::Pixel) {
+        if !self.state.stroke.is_solid_hairline() {
+            return self.stroke_path(img, &Path::rect(rect), color);
         }
 
-        let center = circle.center();
-        let (x0, y0) = center;
+        let (left, right) = (pixel(rect.left()), pixel(rect.right()));
+        let (top, bottom) = (pixel(rect.top()), pixel(rect.bottom()));
 
-        self.draw_line(img, (x0 + r, y0), (x0 - r, y0), color);
-        self.draw_line(img, (x0, y0 + r), (x0, y0 - r), color);
+        // Each pixel is drawn once, even where sides meet or coincide.
+        blend_span(img, top, left, right, color, self.blending(), |_, _| true);
+        if bottom != top {
+            blend_span(img, bottom, left, right, color, self.blending(), |_, _| {
+                true
+            });
+        }
 
-        let mut p: i64 = 1 - r as i64;
-        let mut x = 0;
-        let mut y = r;
+        let height = img.size().1 as i64;
 
-        while x <= y {
-            if x0 >= x {
-                self.draw_line(img, ((x0 - x), (y0 + y)), ((x0 + x), (y0 + y)), color);
+        for y in top.clamp(-1, height) + 1..bottom.min(height) {
+            blend_pixel(img, left, y, color, self.blending());
+            if right != left {
+                blend_pixel(img, right, y, color, self.blending());
             }
+        }
+    }
 
-            if x0 >= y {
-                self.draw_line(img, ((x0 - y), (y0 + x)), ((x0 + y), (y0 + x)), color);
-            }
+    fn draw_filled_rect<'a>(
+        &self,
+        img: &mut Self::Image,
+        rect: Rect,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = paint.into();
 
-            if x0 >= x && y0 >= y {
-                self.draw_line(img, ((x0 - x), (y0 - y)), ((x0 + x), (y0 - y)), color);
-            }
+        let (left, right) = (pixel(rect.left()), pixel(rect.right()));
+        let (top, bottom) = (pixel(rect.top()), pixel(rect.bottom()));
 
-            if x0 >= y && y0 >= x {
-                self.draw_line(img, ((x0 - y), (y0 - x)), ((x0 + y), (y0 - x)), color);
-            }
+        let height = img.size().1 as i64;
 
-            x += 1;
-            if p < 0 {
-                p += 2 * x as i64 + 1;
-            } else {
-                y -= 1;
-                p += 2 * (x as i64 - y as i64) + 1;
-            }
+        for y in top.max(0)..=bottom.min(height - 1) {
+            paint_span(img, y, left, right, &paint, self.blending(), |_, _| true);
+        }
+    }
+
+    fn draw_circle(&self, img: &mut Self::Image, circle: Circle, color: Self::Pixel) {
+        if !self.state.stroke.is_solid_hairline() {
+            return self.stroke_path(img, &Path::circle(circle), color);
         }
+
+        midpoint_circle(img.size(), circle.center(), circle.radius(), |x, y| {
+            blend_pixel(img, x, y, color, self.blending())
+        });
+    }
+
+    fn draw_filled_circle<'a>(
+        &self,
+        img: &mut Self::Image,
+        circle: Circle,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = paint.into();
+
+        self.fill_ellipse_spans(img, circle.into(), &paint, |_, _| true);
     }
 
     fn draw_arc(
         &self,
         img: &mut Self::Image,
         circle: Circle,
-        mut start: Angle,
-        mut end: Angle,
+        start: Angle,
+        end: Angle,
         color: Self::Pixel,
     ) {
-        let radius = circle.radius();
-        if radius == 0 {
-            return;
+        let (start, end) = clockwise_arc(start, end);
+
+        if !self.state.stroke.is_solid_hairline() {
+            return self.stroke_path(img, &Path::arc(circle, start, end), color);
         }
 
         let center = circle.center();
-        let (x0, y0) = center;
+        midpoint_circle(img.size(), circle.center(), circle.radius(), |x, y| {
+            if inside_arc((x as f64 - center.x, y as f64 - center.y), start, end) {
+                blend_pixel(img, x, y, color, self.blending());
+            }
+        });
+    }
 
-        let mut x = 0;
-        let mut y = radius;
-        let mut p = 1 - radius as i64;
+    fn draw_filled_arc<'a>(
+        &self,
+        img: &mut Self::Image,
+        circle: Circle,
+        start: Angle,
+        end: Angle,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = paint.into();
 
-        start.normalize();
-        end.normalize();
+        let (start, end) = clockwise_arc(start, end);
 
-        let (start, end) = if start.to_degrees() > end.to_degrees() {
-            (end, start)
-        } else {
-            (start, end)
+        let center = circle.center();
+        let filter_fn = |x: i64, y: i64| {
+            (x as f64 == center.x && y as f64 == center.y)
+                || inside_arc((x as f64 - center.x, y as f64 - center.y), start, end)
         };
 
-        while x <= y {
-            if inside_arc((x as f64, y as f64), start, end) {
-                blend_pixel(img, x0 + x, y0 + y, color);
-            }
+        self.fill_ellipse_spans(img, circle.into(), &paint, filter_fn);
+    }
 
-            if inside_arc((y as f64, x as f64), start, end) {
-                blend_pixel(img, x0 + y, y0 + x, color);
-            }
+    fn draw_ellipse(&self, img: &mut Self::Image, ellipse: Ellipse, color: Self::Pixel) {
+        if !self.state.stroke.is_solid_hairline() || !ellipse.is_axis_aligned() {
+            return self.stroke_path(img, &Path::ellipse(ellipse), color);
+        }
 
-            if inside_arc((-(y as f64), x as f64), start, end) {
-                blend_pixel(img, x0 - y, y0 + x, color);
-            }
+        midpoint_ellipse(img.size(), ellipse, |x, y| {
+            blend_pixel(img, x, y, color, self.blending())
+        });
+    }
 
-            if inside_arc((-(x as f64), y as f64), start, end) {
-                blend_pixel(img, x0 - x, y0 + y, color);
-            }
+    fn draw_filled_ellipse<'a>(
+        &self,
+        img: &mut Self::Image,
+        ellipse: Ellipse,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = paint.into();
 
-            if inside_arc((-(x as f64), -(y as f64)), start, end) {
-                blend_pixel(img, x0 - x, y0 - y, color);
-            }
+        if !ellipse.is_axis_aligned() {
+            return self.fill_path(img, &Path::ellipse(ellipse), FillRule::NonZero, paint);
+        }
 
-            if inside_arc((-(y as f64), -(x as f64)), start, end) {
-                blend_pixel(img, x0 - y, y0 - x, color);
-            }
+        self.fill_ellipse_spans(img, ellipse, &paint, |_, _| true);
+    }
 
-            if inside_arc((y as f64, -(x as f64)), start, end) {
-                blend_pixel(img, x0 + y, y0 - x, color);
-            }
+    fn draw_elliptical_arc(
+        &self,
+        img: &mut Self::Image,
+        ellipse: Ellipse,
+        start: Angle,
+        end: Angle,
+        color: Self::Pixel,
+    ) {
+        let (start, end) = clockwise_arc(start, end);
 
-            if inside_arc((x as f64, -(y as f64)), start, end) {
-                blend_pixel(img, x0 + x, y0 - y, color);
-            }
+        if !self.state.stroke.is_solid_hairline() || !ellipse.is_axis_aligned() {
+            return self.stroke_path(img, &Path::elliptical_arc(ellipse, start, end), color);
+        }
 
-            x += 1;
-            if p < 0 {
-                p += 2 * x as i64 + 1;
-            } else {
-                y -= 1;
-                p += 2 * (x as i64 - y as i64) + 1;
+        let center = ellipse.center();
+        midpoint_ellipse(img.size(), ellipse, |x, y| {
+            if inside_arc((x as f64 - center.x, y as f64 - center.y), start, end) {
+                blend_pixel(img, x, y, color, self.blending());
             }
-        }
+        });
     }
 
-    fn draw_filled_arc(
+    fn draw_filled_elliptical_arc<'a>(
         &self,
         img: &mut Self::Image,
-        circle: Circle,
-        mut start: Angle,
-        mut end: Angle,
-        color: Self::Pixel,
+        ellipse: Ellipse,
+        start: Angle,
+        end: Angle,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
     ) {
-        let radius = circle.radius();
-        if radius == 0 {
-            return;
-        }
-
-        let center = circle.center();
-        let (x0, y0) = center;
+        let paint = paint.into();
 
-        let mut x = 0;
-        let mut y = radius;
-        let mut p = 1 - radius as i64;
+        let (start, end) = clockwise_arc(start, end);
 
-        start.normalize();
-        end.normalize();
+        if !ellipse.is_axis_aligned() {
+            let path = Path::elliptical_pie(ellipse, start, end);
+            return self.fill_path(img, &path, FillRule::NonZero, paint);
+        }
 
-        let (start, end) = if start.to_degrees() > end.to_degrees() {
-            (end, start)
-        } else {
-            (start, end)
+        let center = ellipse.center();
+        let filter_fn = |x: i64, y: i64| {
+            (x as f64 == center.x && y as f64 == center.y)
+                || inside_arc((x as f64 - center.x, y as f64 - center.y), start, end)
         };
 
-        let filter_fn = |x, y| inside_arc((x as f64 - x0 as f64, y as f64 - y0 as f64), start, end);
-
-        blend_pixel(img, x0, y0, color);
-
-        self.filtered_draw_line(img, (x0 - radius, y0), (x0 + radius, y0), color, filter_fn);
-        self.filtered_draw_line(img, (x0, y0 - radius), (x0, y0 + radius), color, filter_fn);
-
-        while x <= y {
-            if x0 >= x {
-                self.filtered_draw_line(img, (x0 - x, y0 + y), (x0 + x, y0 + y), color, filter_fn);
-            }
-
-            if x0 >= y {
-                self.filtered_draw_line(
-                    img,
-                    ((x0 - y), (y0 + x)),
-                    ((x0 + y), (y0 + x)),
-                    color,
-                    filter_fn,
-                );
-            }
-
-            if x0 >= x && y0 >= y {
-                self.filtered_draw_line(
-                    img,
-                    ((x0 - x), (y0 - y)),
-                    ((x0 + x), (y0 - y)),
-                    color,
-                    filter_fn,
-                );
-            }
-
-            if x0 >= y && y0 >= x {
-                self.filtered_draw_line(
-                    img,
-                    ((x0 - y), (y0 - x)),
-                    ((x0 + y), (y0 - x)),
-                    color,
-                    filter_fn,
-                );
-            }
-
-            x += 1;
-            if p < 0 {
-                p += 2 * x as i64 + 1;
-            } else {
-                y -= 1;
-                p += 2 * (x as i64 - y as i64) + 1;
-            }
-        }
+        self.fill_ellipse_spans(img, ellipse, &paint, filter_fn);
     }
 
     fn draw_rounded_rect(
         &self,
         img: &mut Self::Image,
         rect: Rect,
-        corner_radius: u32,
+        corner_radius: f64,
         color: Self::Pixel,
     ) {
+        let corner_radius = RoundedRect::new(rect, corner_radius).top_left().x;
+
+        if !self.state.stroke.is_solid_hairline() {
+            let path = Path::rounded_rect(rect, corner_radius);
+            return self.stroke_path(img, &path, color);
+        }
+
         self.draw_line(
             img,
-            (rect.left(), rect.top() + corner_radius),
-            (rect.left(), rect.bottom() - corner_radius),
+            Point::new(rect.left(), rect.top() + corner_radius),
+            Point::new(rect.left(), rect.bottom() - corner_radius),
             color,
         );
         self.draw_arc(
@@ -365,8 +416,8 @@ impl<I: GenericImage> Renderer for BasicRenderer<I> {
 
         self.draw_line(
             img,
-            (rect.right(), rect.top() + corner_radius),
-            (rect.right(), rect.bottom() - corner_radius),
+            Point::new(rect.right(), rect.top() + corner_radius),
+            Point::new(rect.right(), rect.bottom() - corner_radius),
             color,
         );
         self.draw_arc(
@@ -392,100 +443,423 @@ impl<I: GenericImage> Renderer for BasicRenderer<I> {
 
         self.draw_line(
             img,
-            (rect.left() + corner_radius, rect.top()),
-            (rect.right() - corner_radius, rect.top()),
+            Point::new(rect.left() + corner_radius, rect.top()),
+            Point::new(rect.right() - corner_radius, rect.top()),
             color,
         );
         self.draw_line(
             img,
-            (rect.left() + corner_radius, rect.bottom()),
-            (rect.right() - corner_radius, rect.bottom()),
+            Point::new(rect.left() + corner_radius, rect.bottom()),
+            Point::new(rect.right() - corner_radius, rect.bottom()),
             color,
         );
     }
 
-    fn draw_filled_rounded_rect(
+    fn draw_filled_rounded_rect<'a>(
         &self,
         img: &mut Self::Image,
         rect: Rect,
-        corner_radius: u32,
-        color: Self::Pixel,
+        corner_radius: f64,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
     ) {
-        self.draw_filled_rect(
-            img,
-            Rect::new(
-                rect.left() + corner_radius,
-                rect.top(),
-                rect.width() - corner_radius * 2,
-                rect.height(),
-            ),
-            color,
-        );
+        // One outline, so no pixel is blended twice where sides meet corners.
+        let path = Path::rounded_rect(rect, corner_radius);
+        self.fill_path(img, &path, FillRule::NonZero, paint);
+    }
 
-        self.draw_filled_rect(
-            img,
-            Rect::new(
-                rect.left(),
-                rect.top() + corner_radius,
-                corner_radius,
-                rect.height() - corner_radius * 2,
-            ),
-            color,
-        );
+    fn draw_polyline(&self, img: &mut Self::Image, points: &[Point], color: Self::Pixel) {
+        self.stroke_path(img, &Path::polyline(points), color);
+    }
 
-        self.draw_filled_rect(
-            img,
-            Rect::new(
-                rect.right() - corner_radius,
-                rect.top() + corner_radius,
-                corner_radius,
-                rect.height() - corner_radius * 2,
-            ),
-            color,
-        );
+    fn draw_polygon(&self, img: &mut Self::Image, points: &[Point], color: Self::Pixel) {
+        self.stroke_path(img, &Path::polygon(points), color);
+    }
 
-        self.draw_filled_arc(
-            img,
-            Circle::new(
-                (rect.left() + corner_radius, rect.top() + corner_radius),
-                corner_radius,
-            ),
-            Angle::Degrees(180f64),
-            Angle::Degrees(270f64),
-            color,
-        );
+    fn draw_filled_polygon<'a>(
+        &self,
+        img: &mut Self::Image,
+        points: &[Point],
+        rule: FillRule,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = paint.into();
 
-        self.draw_filled_arc(
-            img,
-            Circle::new(
-                (rect.left() + corner_radius, rect.bottom() - corner_radius),
-                corner_radius,
-            ),
-            Angle::Degrees(90f64),
-            Angle::Degrees(180f64),
-            color,
-        );
+        raster::fill(&[points.to_vec()], rule, img.size(), |y, from, to| {
+            paint_span(img, y, from, to, &paint, self.blending(), |_, _| true)
+        });
+    }
 
-        self.draw_filled_arc(
-            img,
-            Circle::new(
-                (rect.right() - corner_radius, rect.top() + corner_radius),
-                corner_radius,
-            ),
-            Angle::Degrees(270f64),
-            Angle::Degrees(360f64),
-            color,
+    fn stroke_path(&self, img: &mut Self::Image, path: &Path, color: Self::Pixel) {
+        self.stroke_polylines(img, &path.flatten(TOLERANCE), color);
+    }
+
+    fn fill_path<'a>(
+        &self,
+        img: &mut Self::Image,
+        path: &Path,
+        rule: FillRule,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = paint.into();
+
+        let polygons: Vec<_> = path
+            .flatten(TOLERANCE)
+            .into_iter()
+            .map(|(p, _)| p)
+            .collect();
+
+        raster::fill(&polygons, rule, img.size(), |y, from, to| {
+            paint_span(img, y, from, to, &paint, self.blending(), |_, _| true)
+        });
+    }
+}
+
+/// Walks the outline of the circle around `center` with `radius` with the
+/// midpoint algorithm, calling `plot` once with each pixel inside an image of
+/// `dimensions`.
+///
+/// Only the steps reaching the image are taken: the walk restarts at each
+/// run of them. Radii past [`MAX_COORDINATE`] aren't drawn.
+fn midpoint_circle<F>(dimensions: (u32, u32), center: Point, radius: f64, mut plot: F)
+where
+    F: FnMut(i64, i64),
+{
+    let Some(((x0, y0), r, _)) = outline_extent(dimensions, center, radius, radius) else {
+        return;
+    };
+
+    // Decides the step after (x, y): whether x² + (y - ½)² is within r² at
+    // the next column, offset so the running value stays integral.
+    let decision = |x: i64, y: i64| (x + 1) * (x + 1) + y * y - y - r * r;
+    let row = |x: i64| {
+        let (x, r) = (x as i128, r as i128);
+        let inside = |y: i128| 4 * x * x + (2 * y - 1) * (2 * y - 1) <= 4 * r * r;
+        last_inside(inside, ((r * r - x * x) as f64).sqrt()) as i64
+    };
+
+    // The octant from the top to 45°, where x <= y.
+    let end = first_outside(0, r, |x| x <= row(x));
+    let (width, height) = (dimensions.0 as i64, dimensions.1 as i64);
+
+    for (from, to) in runs(&[(x0, width, end - 1), (y0, height, end - 1)]) {
+        let (mut x, mut y) = (from, row(from));
+        let mut p = decision(x, y);
+
+        while x <= to {
+            plot_symmetric(dimensions, (x0, y0), &[(x, y), (y, x)], &mut plot);
+
+            x += 1;
+            if p < 0 {
+                p += 2 * x + 1;
+            } else {
+                y -= 1;
+                p += 2 * (x - y) + 1;
+            }
+        }
+    }
+}
+
+/// Walks the outline of the axis-aligned `ellipse` with the midpoint
+/// algorithm, calling `plot` once with each pixel inside an image of
+/// `dimensions`.
+///
+/// Only the steps reaching the image are taken: the walk restarts at each
+/// run of them. Radii past [`MAX_COORDINATE`] aren't drawn.
+fn midpoint_ellipse<F: FnMut(i64, i64)>(dimensions: (u32, u32), ellipse: Ellipse, mut plot: F) {
+    let Some(((x0, y0), rx, ry)) = outline_extent(
+        dimensions,
+        ellipse.center(),
+        ellipse.radius_x(),
+        ellipse.radius_y(),
+    ) else {
+        return;
+    };
+
+    let (width, height) = (dimensions.0 as i64, dimensions.1 as i64);
+    let (rx, ry) = (rx as i128, ry as i128);
+    let (rx2, ry2) = (rx * rx, ry * ry);
+
+    // Decision variables are scaled by 4 to keep them integral, and wide
+    // enough for the squared radii to multiply.
+    let row = |x: i128| {
+        let inside = |y: i128| 4 * ry2 * x * x + rx2 * (2 * y - 1) * (2 * y - 1) < 4 * rx2 * ry2;
+        last_inside(
+            inside,
+            ry as f64 * (1f64 - (x as f64 / rx as f64).powi(2)).sqrt(),
+        )
+    };
+    let column = |y: i128| {
+        let inside = |x: i128| ry2 * (2 * x - 1) * (2 * x - 1) + 4 * rx2 * y * y <= 4 * rx2 * ry2;
+        last_inside(
+            inside,
+            rx as f64 * (1f64 - (y as f64 / ry as f64).powi(2)).sqrt(),
+        )
+    };
+
+    let mut plot4 = |x: i128, y: i128| {
+        plot_symmetric(dimensions, (x0, y0), &[(x as i64, y as i64)], &mut plot);
+    };
+
+    // Where the outline is flatter than 45°, step along x.
+    let end = first_outside(0, rx as i64, |x| {
+        let x = x as i128;
+        ry2 * x < rx2 * row(x)
+    }) as i128;
+
+    for (from, to) in runs(&[(x0, width, end as i64 - 1)]) {
+        let (mut x, mut y) = (from as i128, row(from as i128));
+        let (mut dx, mut dy) = (2 * ry2 * x, 2 * rx2 * y);
+        let mut p = 4 * ry2 * (x + 1) * (x + 1) + rx2 * (2 * y - 1) * (2 * y - 1) - 4 * rx2 * ry2;
+
+        while x <= to as i128 {
+            plot4(x, y);
+
+            x += 1;
+            dx += 2 * ry2;
+
+            if p < 0 {
+                p += 4 * (dx + ry2);
+            } else {
+                y -= 1;
+                dy -= 2 * rx2;
+                p += 4 * (dx - dy + ry2);
+            }
+        }
+    }
+
+    // Then step along y, from where the last step along x left off.
+    let last = row(end - 1);
+    let start = if 4 * ry2 * end * end + rx2 * (2 * last - 1) * (2 * last - 1) < 4 * rx2 * ry2 {
+        last
+    } else {
+        last - 1
+    };
+
+    for (to, from) in runs(&[(y0, height, start as i64)]) {
+        let mut y = from as i128;
+        let mut x = if y == start { end } else { end.max(column(y)) };
+        let (mut dx, mut dy) = (2 * ry2 * x, 2 * rx2 * y);
+        let mut p = ry2 * (2 * x + 1) * (2 * x + 1) + 4 * rx2 * (y - 1) * (y - 1) - 4 * rx2 * ry2;
+
+        while y >= to as i128 {
+            plot4(x, y);
+
+            y -= 1;
+            dy -= 2 * rx2;
+
+            if p > 0 {
+                p += 4 * (rx2 - dy);
+            } else {
+                x += 1;
+                dx += 2 * ry2;
+                p += 4 * (dx - dy + rx2);
+            }
+        }
+    }
+}
+
+/// The center pixel and radii, in whole pixels, of an outline that can reach
+/// an image of `dimensions`.
+fn outline_extent(
+    dimensions: (u32, u32),
+    center: Point,
+    rx: f64,
+    ry: f64,
+) -> Option<((i64, i64), i64, i64)> {
+    let (rx, ry) = (pixel(rx), pixel(ry));
+    if rx <= 0 || ry <= 0 || rx as f64 > MAX_COORDINATE || ry as f64 > MAX_COORDINATE {
+        return None;
+    }
+
+    let (x0, y0) = (pixel(center.x), pixel(center.y));
+    let (width, height) = (dimensions.0 as i64, dimensions.1 as i64);
+
+    if x0.saturating_add(rx) < 0
+        || x0.saturating_sub(rx) >= width
+        || y0.saturating_add(ry) < 0
+        || y0.saturating_sub(ry) >= height
+    {
+        return None;
+    }
+
+    Some(((x0, y0), rx, ry))
+}
+
+/// Plots the pixels mirrored around the center `(x0, y0)` from the offsets
+/// in `quadrant`, skipping repeats and the ones outside the image.
+fn plot_symmetric<F: FnMut(i64, i64)>(
+    dimensions: (u32, u32),
+    (x0, y0): (i64, i64),
+    quadrant: &[(i64, i64)],
+    plot: &mut F,
+) {
+    let mut plotted = [(0, 0); 8];
+    let mut count = 0;
+
+    for &(x, y) in quadrant {
+        for offset in [(x, y), (-x, y), (x, -y), (-x, -y)] {
+            if plotted[..count].contains(&offset) {
+                continue;
+            }
+
+            plotted[count] = offset;
+            count += 1;
+
+            let (x, y) = (x0 + offset.0, y0 + offset.1);
+            if x >= 0 && y >= 0 && x < dimensions.0 as i64 && y < dimensions.1 as i64 {
+                plot(x, y);
+            }
+        }
+    }
+}
+
+/// The largest `v` for which `inside(v)` holds, starting the search from
+/// `estimate`.
+fn last_inside<F: Fn(i128) -> bool>(inside: F, estimate: f64) -> i128 {
+    let mut v = (estimate + 0.5).floor().max(0f64) as i128;
+
+    while inside(v + 1) {
+        v += 1;
+    }
+
+    while v > 0 && !inside(v) {
+        v -= 1;
+    }
+
+    v
+}
+
+/// The first `v` in `from..=to` for which `inside(v)` doesn't hold, given it
+/// holds up to some point and not after, or `to + 1` when it always does.
+fn first_outside<F: Fn(i64) -> bool>(from: i64, to: i64, inside: F) -> i64 {
+    let (mut low, mut high) = (from, to + 1);
+
+    while low < high {
+        let middle = low + (high - low) / 2;
+
+        if inside(middle) {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+
+    low
+}
+
+/// The sorted, disjoint runs of offsets in `0..=limit` that put any of the
+/// given centers inside `0..length` when added to or subtracted from them.
+fn runs(axes: &[(i64, i64, i64)]) -> Vec<(i64, i64)> {
+    let mut runs: Vec<_> = axes
+        .iter()
+        .flat_map(|&(center, length, limit)| {
+            [
+                (center - length + 1, center),
+                (-center, length - 1 - center),
+            ]
+            .map(|(from, to)| (from.max(0), to.min(limit)))
+        })
+        .filter(|(from, to)| from <= to)
+        .collect();
+
+    runs.sort_unstable();
+
+    let mut merged: Vec<(i64, i64)> = vec![];
+    for (from, to) in runs {
+        match merged.last_mut() {
+            Some(last) if from <= last.1 + 1 => last.1 = last.1.max(to),
+            _ => merged.push((from, to)),
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Renderer;
+
+    use super::*;
+
+    #[test]
+    fn clipped_outlines_match_the_whole_walk() {
+        let offsets = [(3i64, 5i64), (-10, 7), (20, -30), (5, 45), (37, 2)];
+
+        for (rx, ry) in [(1f64, 2f64), (7f64, 7f64), (30f64, 12f64), (9f64, 41f64)] {
+            let mut whole = vec![];
+            let ellipse = Ellipse::new((50f64, 50f64), rx, ry);
+            midpoint_ellipse((100, 100), ellipse, |x, y| whole.push((x - 50, y - 50)));
+
+            let mut circle = vec![];
+            midpoint_circle((100, 100), ellipse.center(), rx, |x, y| {
+                circle.push((x - 50, y - 50))
+            });
+
+            for (cx, cy) in offsets {
+                let on_image = |&(x, y): &(i64, i64)| {
+                    (0..12).contains(&(x + cx)) && (0..9).contains(&(y + cy))
+                };
+                let center = Point::new(cx as f64, cy as f64);
+
+                let mut clipped = vec![];
+                let ellipse = Ellipse::new(center, rx, ry);
+                midpoint_ellipse((12, 9), ellipse, |x, y| clipped.push((x - cx, y - cy)));
+
+                let mut expected: Vec<_> = whole.iter().copied().filter(on_image).collect();
+                clipped.sort_unstable();
+                expected.sort_unstable();
+                assert_eq!(clipped, expected);
+
+                let mut clipped = vec![];
+                midpoint_circle((12, 9), center, rx, |x, y| clipped.push((x - cx, y - cy)));
+
+                let mut expected: Vec<_> = circle.iter().copied().filter(on_image).collect();
+                clipped.sort_unstable();
+                expected.sort_unstable();
+                assert_eq!(clipped, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn fractional_coordinates_snap_to_nearest_pixel() {
+        let mut img = image::RgbaImage::new(10, 10);
+        let red = image::Rgba([255, 0, 0, 255]);
+
+        BasicRenderer::default().draw_line(
+            &mut img,
+            Point::new(1.4f64, 2.6f64),
+            Point::new(7.6f64, 2.6f64),
+            red,
         );
 
-        self.draw_filled_arc(
-            img,
-            Circle::new(
-                (rect.right() - corner_radius, rect.bottom() - corner_radius),
-                corner_radius,
-            ),
+        assert_eq!(*img.get_pixel(1, 3), red);
+        assert_eq!(*img.get_pixel(8, 3), red);
+        assert_eq!(*img.get_pixel(0, 3), image::Rgba([0, 0, 0, 0]));
+        assert_eq!(*img.get_pixel(4, 2), image::Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn huge_ellipses_draw_only_what_is_on_the_image() {
+        let red = image::Rgba([255, 0, 0, 255]);
+        let clear = image::Rgba([0, 0, 0, 0]);
+
+        // The top of an ellipse far larger than the image runs along row 10.
+        let ellipse = Ellipse::new((10f64, 50010f64), 80000f64, 50000f64);
+
+        let mut img = image::RgbaImage::new(20, 20);
+        BasicRenderer::default().draw_ellipse(&mut img, ellipse, red);
+        assert_eq!(*img.get_pixel(10, 10), red);
+        assert_eq!(*img.get_pixel(10, 11), clear);
+
+        let mut img = image::RgbaImage::new(20, 20);
+        BasicRenderer::default().draw_elliptical_arc(
+            &mut img,
+            Ellipse::new((1e12f64, 1e12f64), 1e12f64, 1e12f64),
             Angle::Degrees(0f64),
-            Angle::Degrees(90f64),
-            color,
+            Angle::Degrees(360f64),
+            red,
         );
+        assert!(img.pixels().all(|p| *p == clear));
     }
 }
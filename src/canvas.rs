@@ -0,0 +1,412 @@
+use image::Pixel;
+
+use crate::{
+    clip::Clip,
+    color::Channel,
+    composite::{Composite, CompositeOp},
+    coverage::CoverageRenderer,
+    paint::Paint,
+    path::Path,
+    stroke::Stroke,
+    target::Target,
+    transform::{Transform, TransformedRenderer},
+    types::{Angle, Circle, Ellipse, FillRule, Point, Rect},
+    Renderer,
+};
+
+/// What [`Canvas::save`] keeps and [`Canvas::restore`] returns to.
+#[derive(Debug, Clone)]
+struct State<'a, P> {
+    fill: Paint<'a, P>,
+    stroke_color: P,
+    stroke: Stroke,
+    transform: Transform,
+    clip: Clip,
+    composite: Composite,
+}
+
+/// Drawing context over an image, in the manner of the HTML canvas: shapes
+/// are filled with the current fill paint and stroked with the current
+/// stroke color and style, through the current transform and clip.
+///
+/// Drawing is done by a [`CoverageRenderer`] behind a
+/// [`TransformedRenderer`]. Unlike with the bare renderers, stroke widths
+/// scale with the transform. Fill and stroke start out opaque black.
+pub struct Canvas<'a, I: Target> {
+    img: &'a mut I,
+    state: State<'a, I::Pixel>,
+    saved: Vec<State<'a, I::Pixel>>,
+}
+
+impl<'a, I: Target> Canvas<'a, I> {
+    pub fn new(img: &'a mut I) -> Self {
+        let black = black::<I::Pixel>();
+
+        Self {
+            img,
+            state: State {
+                fill: Paint::Solid(black),
+                stroke_color: black,
+                stroke: Stroke::default(),
+                transform: Transform::IDENTITY,
+                clip: Clip::new(),
+                composite: Composite::default(),
+            },
+            saved: vec![],
+        }
+    }
+
+    pub fn image(&self) -> &I {
+        self.img
+    }
+
+    pub fn image_mut(&mut self) -> &mut I {
+        self.img
+    }
+
+    pub fn set_fill(&mut self, paint: impl Into<Paint<'a, I::Pixel>>) -> &mut Self {
+        self.state.fill = paint.into();
+        self
+    }
+
+    pub fn fill(&self) -> &Paint<'a, I::Pixel> {
+        &self.state.fill
+    }
+
+    pub fn set_stroke_color(&mut self, color: I::Pixel) -> &mut Self {
+        self.state.stroke_color = color;
+        self
+    }
+
+    pub fn stroke_color(&self) -> I::Pixel {
+        self.state.stroke_color
+    }
+
+    pub fn set_stroke(&mut self, stroke: Stroke) -> &mut Self {
+        self.state.stroke = stroke;
+        self
+    }
+
+    pub fn stroke(&self) -> &Stroke {
+        &self.state.stroke
+    }
+
+    pub fn set_composite(&mut self, composite: impl Into<Composite>) -> &mut Self {
+        self.state.composite = composite.into();
+        self
+    }
+
+    pub fn composite(&self) -> Composite {
+        self.state.composite
+    }
+
+    pub fn transform(&self) -> Transform {
+        self.state.transform
+    }
+
+    pub fn set_transform(&mut self, transform: Transform) -> &mut Self {
+        self.state.transform = transform;
+        self
+    }
+
+    pub fn reset_transform(&mut self) -> &mut Self {
+        self.set_transform(Transform::IDENTITY)
+    }
+
+    /// Applies `transform` to shapes before the current transform, so later
+    /// coordinates are given in the space it sets up.
+    pub fn concat(&mut self, transform: Transform) -> &mut Self {
+        self.state.transform = transform.then(self.state.transform);
+        self
+    }
+
+    pub fn translate(&mut self, tx: f64, ty: f64) -> &mut Self {
+        self.concat(Transform::translate(tx, ty))
+    }
+
+    pub fn scale(&mut self, sx: f64, sy: f64) -> &mut Self {
+        self.concat(Transform::scale(sx, sy))
+    }
+
+    pub fn rotate(&mut self, angle: Angle) -> &mut Self {
+        self.concat(Transform::rotate(angle))
+    }
+
+    pub fn clip(&self) -> &Clip {
+        &self.state.clip
+    }
+
+    /// Restricts drawing to `rect` as well, until the state is restored.
+    pub fn clip_rect(&mut self, rect: Rect) -> &mut Self {
+        let t = self.state.transform;
+
+        match t.uniform_scale() {
+            Some(s) => {
+                let p = t.apply((rect.left(), rect.top()));
+                let rect = Rect::new(p.x, p.y, rect.width() * s, rect.height() * s);
+                self.state.clip.push_rect(rect);
+            }
+            None => {
+                let path = Path::rect(rect).transformed(t);
+                let size = self.img.size();
+                self.state.clip.push_path(&path, FillRule::NonZero, size);
+            }
+        }
+
+        self
+    }
+
+    /// Restricts drawing to the inside of `path` as well, until the state is
+    /// restored.
+    pub fn clip_path(&mut self, path: &Path, rule: FillRule) -> &mut Self {
+        let path = path.transformed(self.state.transform);
+        let size = self.img.size();
+        self.state.clip.push_path(&path, rule, size);
+        self
+    }
+
+    /// Pushes the current state onto the stack.
+    pub fn save(&mut self) -> &mut Self {
+        self.saved.push(self.state.clone());
+        self
+    }
+
+    /// Returns to the state saved last, if any.
+    pub fn restore(&mut self) -> &mut Self {
+        if let Some(state) = self.saved.pop() {
+            self.state = state;
+        }
+        self
+    }
+
+    pub fn fill_rect(&mut self, rect: Rect) {
+        let paint = self.state.fill.clone();
+        self.draw(self.state.composite, |r, img| {
+            r.draw_filled_rect(img, rect, paint)
+        });
+    }
+
+    pub fn stroke_rect(&mut self, rect: Rect) {
+        let color = self.state.stroke_color;
+        self.draw(self.state.composite, |r, img| r.draw_rect(img, rect, color));
+    }
+
+    /// Makes the pixels under `rect` transparent, or black for images without
+    /// alpha.
+    pub fn clear_rect(&mut self, rect: Rect) {
+        let color = self.state.stroke_color;
+        self.draw(CompositeOp::Clear.into(), |r, img| {
+            r.draw_filled_rect(img, rect, color)
+        });
+    }
+
+    pub fn fill_rounded_rect(&mut self, rect: Rect, corner_radius: f64) {
+        let paint = self.state.fill.clone();
+        self.draw(self.state.composite, |r, img| {
+            r.draw_filled_rounded_rect(img, rect, corner_radius, paint)
+        });
+    }
+
+    pub fn stroke_rounded_rect(&mut self, rect: Rect, corner_radius: f64) {
+        let color = self.state.stroke_color;
+        self.draw(self.state.composite, |r, img| {
+            r.draw_rounded_rect(img, rect, corner_radius, color)
+        });
+    }
+
+    pub fn fill_circle(&mut self, circle: Circle) {
+        let paint = self.state.fill.clone();
+        self.draw(self.state.composite, |r, img| {
+            r.draw_filled_circle(img, circle, paint)
+        });
+    }
+
+    pub fn stroke_circle(&mut self, circle: Circle) {
+        let color = self.state.stroke_color;
+        self.draw(self.state.composite, |r, img| {
+            r.draw_circle(img, circle, color)
+        });
+    }
+
+    pub fn fill_ellipse(&mut self, ellipse: Ellipse) {
+        let paint = self.state.fill.clone();
+        self.draw(self.state.composite, |r, img| {
+            r.draw_filled_ellipse(img, ellipse, paint)
+        });
+    }
+
+    pub fn stroke_ellipse(&mut self, ellipse: Ellipse) {
+        let color = self.state.stroke_color;
+        self.draw(self.state.composite, |r, img| {
+            r.draw_ellipse(img, ellipse, color)
+        });
+    }
+
+    /// Fills the sector of `circle` from `start` to `end`.
+    pub fn fill_arc(&mut self, circle: Circle, start: Angle, end: Angle) {
+        let paint = self.state.fill.clone();
+        self.draw(self.state.composite, |r, img| {
+            r.draw_filled_arc(img, circle, start, end, paint)
+        });
+    }
+
+    pub fn stroke_arc(&mut self, circle: Circle, start: Angle, end: Angle) {
+        let color = self.state.stroke_color;
+        self.draw(self.state.composite, |r, img| {
+            r.draw_arc(img, circle, start, end, color)
+        });
+    }
+
+    pub fn stroke_line(&mut self, from: Point, to: Point) {
+        let color = self.state.stroke_color;
+        self.draw(self.state.composite, |r, img| {
+            r.draw_line(img, from, to, color)
+        });
+    }
+
+    pub fn stroke_polyline(&mut self, points: &[Point]) {
+        let color = self.state.stroke_color;
+        self.draw(self.state.composite, |r, img| {
+            r.draw_polyline(img, points, color)
+        });
+    }
+
+    pub fn fill_polygon(&mut self, points: &[Point], rule: FillRule) {
+        let paint = self.state.fill.clone();
+        self.draw(self.state.composite, |r, img| {
+            r.draw_filled_polygon(img, points, rule, paint)
+        });
+    }
+
+    pub fn stroke_polygon(&mut self, points: &[Point]) {
+        let color = self.state.stroke_color;
+        self.draw(self.state.composite, |r, img| {
+            r.draw_polygon(img, points, color)
+        });
+    }
+
+    pub fn fill_path(&mut self, path: &Path, rule: FillRule) {
+        let paint = self.state.fill.clone();
+        self.draw(self.state.composite, |r, img| {
+            r.fill_path(img, path, rule, paint)
+        });
+    }
+
+    pub fn stroke_path(&mut self, path: &Path) {
+        let color = self.state.stroke_color;
+        self.draw(self.state.composite, |r, img| {
+            r.stroke_path(img, path, color)
+        });
+    }
+
+    /// Has `draw` draw with a renderer set up from the current state and
+    /// `composite`. The clip is lent to the renderer rather than copied.
+    fn draw<F>(&mut self, composite: Composite, draw: F)
+    where
+        F: FnOnce(&TransformedRenderer<CoverageRenderer<I>>, &mut I),
+    {
+        let state = &mut self.state;
+
+        // Stroke widths scale by the geometric mean of the transform's
+        // scales, which is exact for uniform scaling.
+        let scale = state.transform.determinant().abs().sqrt();
+        let stroke = if scale == 1f64 {
+            state.stroke.clone()
+        } else {
+            state.stroke.scaled(scale)
+        };
+
+        let mut renderer = TransformedRenderer::new(
+            CoverageRenderer::default()
+                .with_stroke(stroke)
+                .with_composite(composite)
+                .with_clip(std::mem::take(&mut state.clip)),
+        )
+        .with_transform(state.transform);
+
+        draw(&renderer, self.img);
+
+        state.clip = std::mem::take(renderer.inner_mut().clip_mut());
+    }
+}
+
+/// Opaque black: every color channel at zero and alpha, if any, at full.
+fn black<P: Pixel>() -> P
+where
+    P::Subpixel: Channel,
+{
+    let zero = [<P::Subpixel as Channel>::from_f64(0f64); 4];
+
+    P::from_slice(&zero[..P::CHANNEL_COUNT as usize]).map_with_alpha(
+        |c| c,
+        |_| <P::Subpixel as Channel>::from_f64(<P::Subpixel as Channel>::MAX),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Luma, Rgba, RgbaImage};
+
+    use crate::paint::Gradient;
+
+    use super::*;
+
+    #[test]
+    fn canvas_draws_with_its_state() {
+        let red = Rgba([255, 0, 0, 255]);
+        let blue = Rgba([0, 0, 255, 255]);
+        let mut img = RgbaImage::new(40, 40);
+        let mut canvas = Canvas::new(&mut img);
+
+        canvas.fill_rect(Rect::new(0f64, 0f64, 4f64, 4f64));
+        assert_eq!(*canvas.image().get_pixel(2, 2), Rgba([0, 0, 0, 255]));
+
+        canvas.save();
+        canvas
+            .set_fill(red)
+            .translate(10f64, 10f64)
+            .clip_rect(Rect::new(0f64, 0f64, 5f64, 20f64));
+        canvas.fill_rect(Rect::new(0f64, 0f64, 10f64, 10f64));
+        assert_eq!(*canvas.image().get_pixel(12, 12), red);
+        assert_eq!(*canvas.image().get_pixel(17, 12), Rgba([0, 0, 0, 0]));
+        canvas.restore();
+
+        assert!(canvas.transform().is_identity());
+        assert!(canvas.clip().is_empty());
+        canvas
+            .set_fill(blue)
+            .fill_rect(Rect::new(10f64, 30f64, 10f64, 5f64));
+        assert_eq!(*canvas.image().get_pixel(17, 32), blue);
+
+        canvas.clear_rect(Rect::new(10f64, 10f64, 2f64, 2f64));
+        assert_eq!(*canvas.image().get_pixel(11, 11), Rgba([0, 0, 0, 0]));
+        assert_eq!(*canvas.image().get_pixel(14, 14), red);
+    }
+
+    #[test]
+    fn strokes_and_fills_follow_the_transform() {
+        let white = Luma([255u8]);
+        let mut img = image::GrayImage::new(40, 40);
+        let mut canvas = Canvas::new(&mut img);
+
+        // A one pixel line drawn at twice the scale is two pixels wide.
+        canvas
+            .set_stroke_color(white)
+            .set_stroke(Stroke::new(1f64))
+            .scale(2f64, 2f64);
+        canvas.stroke_line(Point::new(1f64, 5.25f64), Point::new(19f64, 5.25f64));
+        let column: Vec<_> = (7..=12)
+            .map(|y| canvas.image().get_pixel(20, y)[0])
+            .collect();
+        assert_eq!(column, [0, 0, 0, 255, 255, 0]);
+
+        // Fills take the transformed gradient along.
+        canvas.set_fill(
+            Gradient::linear((0f64, 0f64), (10f64, 0f64))
+                .with_stop(0f64, Luma([0u8]))
+                .with_stop(1f64, white),
+        );
+        canvas.fill_rect(Rect::new(0f64, 10f64, 10f64, 5f64));
+        assert_eq!(canvas.image().get_pixel(10, 25)[0], 128);
+    }
+}
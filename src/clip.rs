@@ -0,0 +1,316 @@
+use image::{GenericImageView, Luma};
+
+use crate::{
+    geom::TOLERANCE,
+    path::Path,
+    raster,
+    types::{Circle, FillRule, Point, Rect},
+};
+
+/// Coverage of the pixels under a clip shape, relative to its top left
+/// pixel. Pixels outside it aren't covered.
+#[derive(Debug, Clone)]
+struct CoverageMask {
+    left: i64,
+    top: i64,
+    width: u32,
+    height: u32,
+    coverage: Vec<f32>,
+}
+
+impl CoverageMask {
+    fn coverage(&self, x: i64, y: i64) -> f64 {
+        let (x, y) = (x - self.left, y - self.top);
+
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return 0f64;
+        }
+
+        self.coverage[y as usize * self.width as usize + x as usize] as f64
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Region {
+    Rect(Rect),
+    Mask(CoverageMask),
+}
+
+impl Region {
+    fn coverage(&self, x: i64, y: i64) -> f64 {
+        match self {
+            Region::Rect(rect) => {
+                // The overlap of the pixel's square with the rectangle.
+                let overlap = |center: i64, from: f64, to: f64| {
+                    let (a, b) = (center as f64 - 0.5f64, center as f64 + 0.5f64);
+                    (b.min(to) - a.max(from)).clamp(0f64, 1f64)
+                };
+
+                overlap(x, rect.left(), rect.right()) * overlap(y, rect.top(), rect.bottom())
+            }
+            Region::Mask(mask) => mask.coverage(x, y),
+        }
+    }
+}
+
+/// A stack of regions drawing is restricted to; only what lies inside all
+/// of them is drawn.
+///
+/// Edges are anti-aliased: pixels a region partly covers get that fraction
+/// of whatever is drawn over them, even by the aliased renderers.
+/// Rectangles are checked directly, while other shapes are rasterized into
+/// a coverage mask when pushed, only over the image they're pushed for:
+/// nothing outside it is inside them.
+#[derive(Debug, Clone, Default)]
+pub struct Clip {
+    regions: Vec<Region>,
+}
+
+impl Clip {
+    pub const fn new() -> Self {
+        Self {
+            regions: Vec::new(),
+        }
+    }
+
+    pub fn push_rect(&mut self, rect: Rect) -> &mut Self {
+        self.regions.push(Region::Rect(rect));
+        self
+    }
+
+    /// Pushes `rect` with rounded corners, for an image of `size`.
+    pub fn push_rounded_rect(
+        &mut self,
+        rect: Rect,
+        corner_radius: f64,
+        size: (u32, u32),
+    ) -> &mut Self {
+        let path = Path::rounded_rect(rect, corner_radius);
+        self.push_path(&path, FillRule::NonZero, size)
+    }
+
+    /// Pushes `circle`, for an image of `size`.
+    pub fn push_circle(&mut self, circle: Circle, size: (u32, u32)) -> &mut Self {
+        self.push_path(&Path::circle(circle), FillRule::NonZero, size)
+    }
+
+    /// Pushes the inside of `path` following `rule`, rasterized only where
+    /// it overlaps an image of `size`.
+    pub fn push_path(&mut self, path: &Path, rule: FillRule, size: (u32, u32)) -> &mut Self {
+        let polygons: Vec<_> = path
+            .flatten(TOLERANCE)
+            .into_iter()
+            .map(|(p, _)| p)
+            .collect();
+
+        let (min, max) = polygons.iter().flatten().fold(
+            (
+                Point::new(f64::INFINITY, f64::INFINITY),
+                Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY),
+            ),
+            |(min, max), p| {
+                (
+                    Point::new(min.x.min(p.x), min.y.min(p.y)),
+                    Point::new(max.x.max(p.x), max.y.max(p.y)),
+                )
+            },
+        );
+
+        let mut mask = CoverageMask {
+            left: 0,
+            top: 0,
+            width: 0,
+            height: 0,
+            coverage: vec![],
+        };
+
+        if min.x.is_finite() && min.y.is_finite() && max.x.is_finite() && max.y.is_finite() {
+            // A pixel around the shape takes its anti-aliased edge.
+            let (width, height) = (size.0 as f64, size.1 as f64);
+            let left = (min.x.floor() - 1f64).clamp(0f64, width);
+            let top = (min.y.floor() - 1f64).clamp(0f64, height);
+            let right = (max.x.ceil() + 2f64).clamp(left, width);
+            let bottom = (max.y.ceil() + 2f64).clamp(top, height);
+
+            mask.left = left as i64;
+            mask.top = top as i64;
+            mask.width = (right - left) as u32;
+            mask.height = (bottom - top) as u32;
+            mask.coverage = vec![0f32; mask.width as usize * mask.height as usize];
+
+            let polygons: Vec<Vec<_>> = polygons
+                .iter()
+                .map(|p| {
+                    p.iter()
+                        .map(|p| Point::new(p.x - left, p.y - top))
+                        .collect()
+                })
+                .collect();
+
+            let width = mask.width as usize;
+            raster::accumulate(
+                &polygons,
+                rule,
+                (mask.width, mask.height),
+                |x, y, coverage| {
+                    mask.coverage[y as usize * width + x as usize] = coverage as f32;
+                },
+            );
+        }
+
+        self.regions.push(Region::Mask(mask));
+        self
+    }
+
+    /// Pushes a region covering each pixel as much as the gray level of the
+    /// `mask` pixel at the same position, none outside of it.
+    pub fn push_mask<M>(&mut self, mask: &M) -> &mut Self
+    where
+        M: GenericImageView<Pixel = Luma<u8>>,
+    {
+        let (width, height) = mask.dimensions();
+
+        self.regions.push(Region::Mask(CoverageMask {
+            left: 0,
+            top: 0,
+            width,
+            height,
+            coverage: mask
+                .pixels()
+                .map(|(_, _, Luma([v]))| v as f32 / 255f32)
+                .collect(),
+        }));
+        self
+    }
+
+    /// Removes the region pushed last, if any.
+    pub fn pop(&mut self) -> &mut Self {
+        self.regions.pop();
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    /// The number of regions on the stack.
+    pub fn depth(&self) -> usize {
+        self.regions.len()
+    }
+
+    /// The fraction of the pixel at `x`, `y` drawing reaches.
+    pub fn coverage(&self, x: i64, y: i64) -> f64 {
+        let mut coverage = 1f64;
+
+        for region in &self.regions {
+            coverage *= region.coverage(x, y);
+
+            if coverage <= 0f64 {
+                return 0f64;
+            }
+        }
+
+        coverage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::GrayImage;
+
+    use crate::{
+        antialiased::AntiAliasingRender, basic::BasicRenderer, coverage::CoverageRenderer,
+        supersample::SupersampledRenderer, Renderer,
+    };
+
+    use super::*;
+
+    #[test]
+    fn regions_intersect() {
+        let mut clip = Clip::new();
+        assert_eq!(clip.coverage(-100, 100), 1f64);
+
+        clip.push_rect(Rect::new(2f64, 2f64, 6f64, 6f64));
+        assert_eq!(clip.coverage(4, 4), 1f64);
+        assert_eq!(clip.coverage(2, 4), 0.5f64);
+        assert_eq!(clip.coverage(2, 2), 0.25f64);
+        assert_eq!(clip.coverage(9, 4), 0f64);
+
+        clip.push_circle(Circle::new((8f64, 8f64), 4f64), (20, 20));
+        assert_eq!(clip.depth(), 2);
+        assert_eq!(clip.coverage(7, 7), 1f64);
+        assert_eq!(clip.coverage(4, 4), 0f64);
+        assert_eq!(clip.coverage(6, 6), 1f64);
+
+        clip.pop();
+        assert_eq!(clip.coverage(4, 4), 1f64);
+
+        // Only the part over the image is rasterized, however large the
+        // shape.
+        let mut clip = Clip::new();
+        clip.push_circle(Circle::new((0f64, 0f64), 100_000f64), (20, 10));
+        assert_eq!(clip.coverage(19, 9), 1f64);
+        assert_eq!(clip.coverage(25, 5), 0f64);
+        assert_eq!(clip.coverage(-1, 5), 0f64);
+
+        let mut mask = GrayImage::new(4, 4);
+        mask.put_pixel(1, 1, Luma([51]));
+
+        let mut clip = Clip::new();
+        clip.push_mask(&mask);
+        assert!((clip.coverage(1, 1) - 0.2f64).abs() < 1e-6);
+        assert_eq!(clip.coverage(2, 1), 0f64);
+        assert_eq!(clip.coverage(5, 1), 0f64);
+    }
+
+    #[test]
+    fn renderers_draw_only_inside_the_clip() {
+        let white = image::Luma([255u8]);
+        let whole = Rect::new(0f64, 0f64, 20f64, 20f64);
+
+        let mut clip = Clip::new();
+        clip.push_rect(Rect::new(0f64, 0f64, 10f64, 20f64))
+            .push_circle(Circle::new((10f64, 10f64), 6f64), (20, 20));
+
+        let check = |img: &image::GrayImage| {
+            assert_eq!(img.get_pixel(7, 10)[0], 255);
+            assert_eq!(img.get_pixel(10, 10)[0], 128);
+            assert_eq!(img.get_pixel(13, 10)[0], 0);
+            assert_eq!(img.get_pixel(2, 10)[0], 0);
+            assert_eq!(img.get_pixel(8, 2)[0], 0);
+        };
+
+        let mut img = image::GrayImage::new(20, 20);
+        BasicRenderer::default()
+            .with_clip(clip.clone())
+            .draw_filled_rect(&mut img, whole, white);
+        check(&img);
+
+        let mut img = image::GrayImage::new(20, 20);
+        AntiAliasingRender::default()
+            .with_clip(clip.clone())
+            .draw_filled_rect(&mut img, whole, white);
+        check(&img);
+
+        let mut img = image::GrayImage::new(20, 20);
+        CoverageRenderer::default()
+            .with_clip(clip.clone())
+            .draw_filled_rect(&mut img, whole, white);
+        check(&img);
+
+        let mut img = image::GrayImage::new(20, 20);
+        SupersampledRenderer::new(BasicRenderer::default(), 4)
+            .with_clip(clip.clone())
+            .draw_filled_rect(&mut img, whole, white);
+        check(&img);
+
+        // Popping the circle leaves the rectangle's anti-aliased edge.
+        let mut renderer = BasicRenderer::default().with_clip(clip);
+        renderer.clip_mut().pop();
+        let mut img = image::GrayImage::new(20, 20);
+        renderer.draw_filled_rect(&mut img, whole, white);
+        assert_eq!(img.get_pixel(2, 2)[0], 255);
+        assert_eq!(img.get_pixel(10, 2)[0], 128);
+        assert_eq!(img.get_pixel(11, 2)[0], 0);
+    }
+}
@@ -0,0 +1,211 @@
+use std::sync::OnceLock;
+
+use image::Primitive;
+
+use crate::{clip::Clip, composite::Composite, target::Target};
+
+/// Subpixel types the anti-aliasing renderer can blend, along with the value
+/// standing for full intensity: the type's maximum for integers and `1.0`
+/// for floats.
+pub trait Channel: Primitive {
+    const MAX: f64;
+
+    fn from_f64(v: f64) -> Self;
+}
+
+macro_rules! integer_channel {
+    ($($t:ty),*) => {$(
+        impl Channel for $t {
+            const MAX: f64 = <$t>::MAX as f64;
+
+            fn from_f64(v: f64) -> Self {
+                v.round().clamp(0f64, <$t as Channel>::MAX) as $t
+            }
+        }
+    )*};
+}
+
+macro_rules! float_channel {
+    ($($t:ty),*) => {$(
+        impl Channel for $t {
+            const MAX: f64 = 1f64;
+
+            fn from_f64(v: f64) -> Self {
+                v as $t
+            }
+        }
+    )*};
+}
+
+integer_channel!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+float_channel!(f32, f64);
+
+/// How renderers composite what they draw: the operator and the clip
+/// restricting it.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Blending<'a> {
+    pub composite: Composite,
+    pub clip: &'a Clip,
+}
+
+impl Default for Blending<'_> {
+    fn default() -> Self {
+        static UNCLIPPED: Clip = Clip::new();
+
+        Self {
+            composite: Composite::default(),
+            clip: &UNCLIPPED,
+        }
+    }
+}
+
+/// Composites `color` onto the pixel at `x`, `y` as if it covered
+/// `coverage` of its area, ignoring pixels outside the image or the clip.
+pub(crate) fn blend_coverage<T: Target>(
+    img: &mut T,
+    x: i64,
+    y: i64,
+    color: T::Pixel,
+    coverage: f64,
+    blending: Blending,
+) {
+    let (width, height) = img.size();
+
+    if coverage <= 0f64 || x < 0 || y < 0 || x >= width as i64 || y >= height as i64 {
+        return;
+    }
+
+    let coverage = coverage.min(1f64) * blending.clip.coverage(x, y);
+    if coverage <= 0f64 {
+        return;
+    }
+
+    img.blend(x as u32, y as u32, color, coverage, blending.composite);
+}
+
+/// Entries in the transfer function tables, which are interpolated between.
+const CURVE_SIZE: usize = 4096;
+
+static DECODE: OnceLock<Vec<f64>> = OnceLock::new();
+static ENCODE: OnceLock<Vec<f64>> = OnceLock::new();
+
+fn curve(table: &OnceLock<Vec<f64>>, f: fn(f64) -> f64, v: f64) -> f64 {
+    let table = table.get_or_init(|| {
+        (0..=CURVE_SIZE)
+            .map(|i| f(i as f64 / CURVE_SIZE as f64))
+            .collect()
+    });
+
+    let x = v.clamp(0f64, 1f64) * CURVE_SIZE as f64;
+    let i = (x as usize).min(CURVE_SIZE - 1);
+
+    table[i] + (table[i + 1] - table[i]) * (x - i as f64)
+}
+
+/// Decodes the sRGB-encoded `v`, from 0 to 1, into linear light.
+pub(crate) fn srgb_to_linear(v: f64) -> f64 {
+    curve(
+        &DECODE,
+        |v| {
+            if v <= 0.04045f64 {
+                v / 12.92f64
+            } else {
+                ((v + 0.055f64) / 1.055f64).powf(2.4f64)
+            }
+        },
+        v,
+    )
+}
+
+/// Encodes the linear light `v`, from 0 to 1, into sRGB.
+pub(crate) fn linear_to_srgb(v: f64) -> f64 {
+    curve(
+        &ENCODE,
+        |v| {
+            if v <= 0.0031308f64 {
+                v * 12.92f64
+            } else {
+                1.055f64 * v.powf(1f64 / 2.4f64) - 0.055f64
+            }
+        },
+        v,
+    )
+}
+
+pub(crate) fn value<T: Channel>(channel: T) -> f64 {
+    channel.to_f64().unwrap_or(0f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{ImageBuffer, Luma, Rgb, Rgba};
+
+    use super::*;
+
+    #[test]
+    fn coverage_interpolates_pixels_without_alpha() {
+        let mut img = ImageBuffer::from_pixel(2, 1, Luma([0u8]));
+        blend_coverage(&mut img, 0, 0, Luma([200u8]), 0.5f64, Blending::default());
+        blend_coverage(&mut img, 5, 0, Luma([200u8]), 0.5f64, Blending::default());
+        assert_eq!(*img.get_pixel(0, 0), Luma([100u8]));
+        assert_eq!(*img.get_pixel(1, 0), Luma([0u8]));
+
+        let mut img = ImageBuffer::from_pixel(1, 1, Rgb([0u16, 65535, 0]));
+        blend_coverage(
+            &mut img,
+            0,
+            0,
+            Rgb([65535u16, 0, 0]),
+            0.25f64,
+            Blending::default(),
+        );
+        assert_eq!(*img.get_pixel(0, 0), Rgb([16384u16, 49151, 0]));
+    }
+
+    #[test]
+    fn coverage_scales_alpha() {
+        let mut img = ImageBuffer::from_pixel(1, 1, Rgba([0u16; 4]));
+        blend_coverage(
+            &mut img,
+            0,
+            0,
+            Rgba([65535u16, 0, 0, 65535]),
+            0.5f64,
+            Blending::default(),
+        );
+        assert_eq!(*img.get_pixel(0, 0), Rgba([65535u16, 0, 0, 32768]));
+
+        let mut img = ImageBuffer::from_pixel(1, 1, Rgba([0f32, 0f32, 1f32, 1f32]));
+        blend_coverage(
+            &mut img,
+            0,
+            0,
+            Rgba([1f32, 0f32, 0f32, 0.5f32]),
+            0.5f64,
+            Blending::default(),
+        );
+        assert_eq!(*img.get_pixel(0, 0), Rgba([0.25f32, 0f32, 0.75f32, 1f32]));
+
+        let mut img = ImageBuffer::from_pixel(1, 1, Rgba([0u8, 0, 255, 255]));
+        blend_coverage(
+            &mut img,
+            0,
+            0,
+            Rgba([255u8, 0, 0, 255]),
+            1f64,
+            Blending::default(),
+        );
+        assert_eq!(*img.get_pixel(0, 0), Rgba([255u8, 0, 0, 255]));
+    }
+
+    #[test]
+    fn srgb_tables_round_trip() {
+        assert!((srgb_to_linear(0.5f64) - 0.2140f64).abs() < 1e-4);
+        assert!((linear_to_srgb(0.5f64) - 0.7354f64).abs() < 1e-4);
+
+        for v in 0..=255u8 {
+            let linear = srgb_to_linear(v as f64 / 255f64);
+            assert_eq!(u8::from_f64(linear_to_srgb(linear) * 255f64), v);
+        }
+    }
+}
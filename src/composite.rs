@@ -0,0 +1,503 @@
+use crate::color::{linear_to_srgb, srgb_to_linear, value, Channel};
+
+/// Porter-Duff operators, deciding how much of the source and of the
+/// backdrop make it into the result.
+///
+/// Only the pixels a shape touches are composited: operators like
+/// [`CompositeOp::SourceIn`] leave the rest of the image alone rather than
+/// clearing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompositeOp {
+    Clear,
+    Source,
+    Destination,
+    #[default]
+    SourceOver,
+    DestinationOver,
+    SourceIn,
+    DestinationIn,
+    SourceOut,
+    DestinationOut,
+    SourceAtop,
+    DestinationAtop,
+    Xor,
+    /// Adds source and backdrop together, saturating.
+    Lighter,
+}
+
+impl CompositeOp {
+    /// The fractions of the source and of the backdrop kept, given their
+    /// alphas.
+    fn factors(&self, source_alpha: f64, backdrop_alpha: f64) -> (f64, f64) {
+        let (a_s, a_b) = (source_alpha, backdrop_alpha);
+
+        match self {
+            CompositeOp::Clear => (0f64, 0f64),
+            CompositeOp::Source => (1f64, 0f64),
+            CompositeOp::Destination => (0f64, 1f64),
+            CompositeOp::SourceOver => (1f64, 1f64 - a_s),
+            CompositeOp::DestinationOver => (1f64 - a_b, 1f64),
+            CompositeOp::SourceIn => (a_b, 0f64),
+            CompositeOp::DestinationIn => (0f64, a_s),
+            CompositeOp::SourceOut => (1f64 - a_b, 0f64),
+            CompositeOp::DestinationOut => (0f64, 1f64 - a_s),
+            CompositeOp::SourceAtop => (a_b, 1f64 - a_s),
+            CompositeOp::DestinationAtop => (1f64 - a_b, a_s),
+            CompositeOp::Xor => (1f64 - a_b, 1f64 - a_s),
+            CompositeOp::Lighter => (1f64, 1f64),
+        }
+    }
+}
+
+/// How the source color is mixed with the backdrop where both are present,
+/// before compositing.
+///
+/// Hue, saturation, color and luminosity only make sense for RGB pixels; on
+/// gray ones they keep the backdrop's gray, or the source's for luminosity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    #[default]
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
+}
+
+impl BlendMode {
+    fn is_separable(&self) -> bool {
+        !matches!(
+            self,
+            BlendMode::Hue | BlendMode::Saturation | BlendMode::Color | BlendMode::Luminosity
+        )
+    }
+
+    /// Mixes one channel of the backdrop `b` and the source `s`.
+    fn separable(&self, b: f64, s: f64) -> f64 {
+        match self {
+            BlendMode::Multiply => b * s,
+            BlendMode::Screen => b + s - b * s,
+            BlendMode::Overlay => BlendMode::HardLight.separable(s, b),
+            BlendMode::Darken => b.min(s),
+            BlendMode::Lighten => b.max(s),
+            BlendMode::ColorDodge => {
+                if b <= 0f64 {
+                    0f64
+                } else if s >= 1f64 {
+                    1f64
+                } else {
+                    (b / (1f64 - s)).min(1f64)
+                }
+            }
+            BlendMode::ColorBurn => {
+                if b >= 1f64 {
+                    1f64
+                } else if s <= 0f64 {
+                    0f64
+                } else {
+                    1f64 - ((1f64 - b) / s).min(1f64)
+                }
+            }
+            BlendMode::HardLight => {
+                if s <= 0.5f64 {
+                    b * 2f64 * s
+                } else {
+                    BlendMode::Screen.separable(b, 2f64 * s - 1f64)
+                }
+            }
+            BlendMode::SoftLight => {
+                if s <= 0.5f64 {
+                    b - (1f64 - 2f64 * s) * b * (1f64 - b)
+                } else {
+                    let d = if b <= 0.25f64 {
+                        ((16f64 * b - 12f64) * b + 4f64) * b
+                    } else {
+                        b.sqrt()
+                    };
+
+                    b + (2f64 * s - 1f64) * (d - b)
+                }
+            }
+            BlendMode::Difference => (b - s).abs(),
+            BlendMode::Exclusion => b + s - 2f64 * b * s,
+            _ => s,
+        }
+    }
+
+    /// Mixes the colors `b` and `s` as a whole.
+    fn non_separable(&self, b: [f64; 3], s: [f64; 3]) -> [f64; 3] {
+        match self {
+            BlendMode::Hue => set_lum(set_sat(s, sat(b)), lum(b)),
+            BlendMode::Saturation => set_lum(set_sat(b, sat(s)), lum(b)),
+            BlendMode::Color => set_lum(s, lum(b)),
+            BlendMode::Luminosity => set_lum(b, lum(s)),
+            _ => s,
+        }
+    }
+
+    /// Mixes the color channels of the backdrop and the source, filling as
+    /// many channels of the result as they have.
+    fn mix(&self, backdrop: &[f64], source: &[f64]) -> [f64; MAX_CHANNELS] {
+        let mut mixed = [0f64; MAX_CHANNELS];
+
+        if self.is_separable() {
+            for (m, (b, s)) in mixed.iter_mut().zip(backdrop.iter().zip(source)) {
+                *m = self.separable(*b, *s);
+            }
+
+            return mixed;
+        }
+
+        match (backdrop, source) {
+            (&[br, bg, bb], &[sr, sg, sb]) => {
+                mixed[..3].copy_from_slice(&self.non_separable([br, bg, bb], [sr, sg, sb]));
+            }
+            _ if *self == BlendMode::Luminosity => {
+                mixed[..source.len()].copy_from_slice(source);
+            }
+            _ => mixed[..backdrop.len()].copy_from_slice(backdrop),
+        }
+
+        mixed
+    }
+}
+
+fn lum([r, g, b]: [f64; 3]) -> f64 {
+    0.3f64 * r + 0.59f64 * g + 0.11f64 * b
+}
+
+fn clip_color(c: [f64; 3]) -> [f64; 3] {
+    let l = lum(c);
+    let n = c.iter().copied().fold(f64::INFINITY, f64::min);
+    let x = c.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+    c.map(|v| {
+        let v = if n < 0f64 {
+            l + (v - l) * l / (l - n)
+        } else {
+            v
+        };
+        if x > 1f64 {
+            l + (v - l) * (1f64 - l) / (x - l)
+        } else {
+            v
+        }
+    })
+}
+
+fn set_lum(c: [f64; 3], l: f64) -> [f64; 3] {
+    let d = l - lum(c);
+    clip_color(c.map(|v| v + d))
+}
+
+fn sat(c: [f64; 3]) -> f64 {
+    let n = c.iter().copied().fold(f64::INFINITY, f64::min);
+    let x = c.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    x - n
+}
+
+fn set_sat(c: [f64; 3], s: f64) -> [f64; 3] {
+    let mut order = [0, 1, 2];
+    order.sort_by(|a, b| c[*a].total_cmp(&c[*b]));
+    let [min, mid, max] = order;
+
+    let mut result = [0f64; 3];
+    if c[max] > c[min] {
+        result[mid] = (c[mid] - c[min]) * s / (c[max] - c[min]);
+        result[max] = s;
+    }
+
+    result
+}
+
+/// Most channels a pixel has, e.g. RGBA.
+const MAX_CHANNELS: usize = 4;
+
+/// A Porter-Duff operator together with the blend mode mixing colors
+/// beforehand. The default is plain source-over, in sRGB space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Composite {
+    op: CompositeOp,
+    blend_mode: BlendMode,
+    linear_light: bool,
+}
+
+impl Composite {
+    pub fn new(op: CompositeOp) -> Self {
+        Self {
+            op,
+            blend_mode: BlendMode::default(),
+            linear_light: false,
+        }
+    }
+
+    pub fn with_blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.blend_mode = blend_mode;
+        self
+    }
+
+    /// Blends colors in linear light rather than as they are stored, taking
+    /// them to be sRGB-encoded. Anti-aliased edges then keep the brightness
+    /// of the shape, instead of making thin shapes look too light or too
+    /// dark depending on the background.
+    pub fn with_linear_light(mut self, linear_light: bool) -> Self {
+        self.linear_light = linear_light;
+        self
+    }
+
+    pub fn op(&self) -> CompositeOp {
+        self.op
+    }
+
+    pub fn blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    pub fn linear_light(&self) -> bool {
+        self.linear_light
+    }
+}
+
+impl From<CompositeOp> for Composite {
+    fn from(op: CompositeOp) -> Self {
+        Composite::new(op)
+    }
+}
+
+impl From<BlendMode> for Composite {
+    fn from(blend_mode: BlendMode) -> Self {
+        Composite::default().with_blend_mode(blend_mode)
+    }
+}
+
+/// Composites `src` over `dst` as if it covered `coverage` of the pixel, by
+/// interpolating between the backdrop and the fully covered result.
+///
+/// Pixels without an alpha channel are opaque, and keep the result's color
+/// premultiplied by its alpha.
+pub(crate) fn composite_channels<T: Channel>(
+    dst: &mut [T],
+    src: &[T],
+    has_alpha: bool,
+    coverage: f64,
+    composite: Composite,
+) {
+    let coverage = coverage.min(1f64);
+    let colors = if has_alpha { dst.len() - 1 } else { dst.len() };
+
+    let decode = |c: &[T]| {
+        let mut decoded = [0f64; MAX_CHANNELS];
+
+        for (d, v) in decoded.iter_mut().zip(c) {
+            let v = value(*v) / T::MAX;
+            *d = if composite.linear_light {
+                srgb_to_linear(v)
+            } else {
+                v
+            };
+        }
+
+        decoded
+    };
+    let (backdrop, source) = (decode(&dst[..colors]), decode(&src[..colors]));
+
+    let (a_s, a_b) = if has_alpha {
+        (value(src[colors]) / T::MAX, value(dst[colors]) / T::MAX)
+    } else {
+        (1f64, 1f64)
+    };
+
+    let mixed = composite
+        .blend_mode
+        .mix(&backdrop[..colors], &source[..colors]);
+    let (f_s, f_b) = composite.op.factors(a_s, a_b);
+
+    let alpha = (f_s * a_s + f_b * a_b).min(1f64);
+    let alpha = a_b + (alpha - a_b) * coverage;
+
+    for (i, d) in dst[..colors].iter_mut().enumerate() {
+        let (b, s) = (backdrop[i], source[i]);
+        let s = (1f64 - a_b) * s + a_b * mixed[i];
+
+        let premultiplied = (f_s * a_s * s + f_b * a_b * b).min(1f64);
+        let premultiplied = a_b * b + (premultiplied - a_b * b) * coverage;
+
+        let v = if !has_alpha {
+            premultiplied
+        } else if alpha > 0f64 {
+            premultiplied / alpha
+        } else {
+            0f64
+        };
+
+        let v = if composite.linear_light {
+            linear_to_srgb(v)
+        } else {
+            v
+        };
+
+        *d = T::from_f64(v * T::MAX);
+    }
+
+    if has_alpha {
+        dst[colors] = T::from_f64(alpha * T::MAX);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        antialiased::AntiAliasingRender,
+        basic::BasicRenderer,
+        color,
+        coverage::CoverageRenderer,
+        types::{Circle, Point, Rect},
+        Renderer,
+    };
+
+    use super::*;
+
+    fn composite(dst: [u8; 4], src: [u8; 4], composite: impl Into<Composite>) -> [u8; 4] {
+        let mut dst = dst;
+        composite_channels(&mut dst, &src, true, 1f64, composite.into());
+        dst
+    }
+
+    #[test]
+    fn porter_duff_operators() {
+        let (red, blue) = ([255, 0, 0, 255], [0, 0, 255, 128]);
+        let clear = [0, 0, 0, 0];
+
+        assert_eq!(composite(blue, red, CompositeOp::Clear), clear);
+        assert_eq!(composite(blue, red, CompositeOp::Source), red);
+        assert_eq!(composite(blue, red, CompositeOp::Destination), blue);
+        assert_eq!(composite(blue, red, CompositeOp::SourceOver), red);
+        assert_eq!(
+            composite(blue, red, CompositeOp::DestinationOver),
+            [127, 0, 128, 255]
+        );
+        assert_eq!(
+            composite(blue, red, CompositeOp::SourceIn),
+            [255, 0, 0, 128]
+        );
+        assert_eq!(
+            composite(blue, red, CompositeOp::SourceOut),
+            [255, 0, 0, 127]
+        );
+        assert_eq!(
+            composite(red, blue, CompositeOp::DestinationOut),
+            [255, 0, 0, 127]
+        );
+        assert_eq!(
+            composite(red, blue, CompositeOp::SourceAtop),
+            [127, 0, 128, 255]
+        );
+        assert_eq!(composite(clear, red, CompositeOp::SourceAtop), clear);
+        assert_eq!(composite(red, blue, CompositeOp::Xor), [255, 0, 0, 127]);
+        assert_eq!(
+            composite([100, 0, 0, 255], [200, 0, 0, 255], CompositeOp::Lighter),
+            [255, 0, 0, 255]
+        );
+    }
+
+    #[test]
+    fn blend_modes() {
+        let gray = [128, 128, 128, 255];
+        let color = [255, 64, 0, 255];
+
+        assert_eq!(
+            composite(gray, color, BlendMode::Multiply),
+            [128, 32, 0, 255]
+        );
+        assert_eq!(
+            composite(gray, color, BlendMode::Screen),
+            [255, 160, 128, 255]
+        );
+        assert_eq!(composite(gray, color, BlendMode::Darken), [128, 64, 0, 255]);
+        assert_eq!(
+            composite(gray, color, BlendMode::Difference),
+            [127, 64, 128, 255]
+        );
+
+        // Gray has no saturation to give, so hue keeps the gray and the
+        // others end up gray with the luminosity of the color.
+        assert_eq!(composite(gray, color, BlendMode::Hue), gray);
+        assert_eq!(
+            composite(color, gray, BlendMode::Saturation),
+            [114, 114, 114, 255]
+        );
+        assert_eq!(
+            composite(gray, color, BlendMode::Luminosity),
+            [114, 114, 114, 255]
+        );
+
+        // Blending only applies where the backdrop is there.
+        assert_eq!(composite([0; 4], color, BlendMode::Multiply), color);
+    }
+
+    #[test]
+    fn renderers_composite_with_the_chosen_operator() {
+        let red = image::Rgba([255, 0, 0, 255]);
+        let gray = image::Rgba([128, 128, 128, 255]);
+        let rect = Rect::new(2f64, 2f64, 6f64, 6f64);
+
+        let mut img = image::RgbaImage::from_pixel(12, 12, red);
+        let punch = BasicRenderer::default().with_composite(CompositeOp::DestinationOut);
+        punch.draw_filled_rect(&mut img, rect, gray);
+        assert_eq!(*img.get_pixel(5, 5), image::Rgba([0, 0, 0, 0]));
+        assert_eq!(*img.get_pixel(10, 5), red);
+
+        let mut img = image::RgbaImage::from_pixel(12, 12, red);
+        let multiply = Composite::new(CompositeOp::SourceAtop).with_blend_mode(BlendMode::Multiply);
+        AntiAliasingRender::default()
+            .with_composite(multiply)
+            .draw_filled_circle(&mut img, Circle::new((6f64, 6f64), 4f64), gray);
+        assert_eq!(*img.get_pixel(6, 6), image::Rgba([128, 0, 0, 255]));
+        assert_eq!(*img.get_pixel(0, 0), red);
+
+        let mut img = image::RgbaImage::new(12, 12);
+        CoverageRenderer::default()
+            .with_composite(CompositeOp::SourceIn)
+            .draw_filled_rect(&mut img, rect, gray);
+        assert_eq!(*img.get_pixel(5, 5), image::Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn linear_light_keeps_line_weight() {
+        let white = image::Luma([255u8]);
+        let line = |composite: Composite| {
+            let mut img = image::GrayImage::new(20, 12);
+            AntiAliasingRender::default()
+                .with_composite(composite)
+                .draw_line(
+                    &mut img,
+                    Point::new(0f64, 5.5f64),
+                    Point::new(19f64, 5.5f64),
+                    white,
+                );
+
+            // The light the line gives off in a column, which is 1 for a
+            // line one pixel wide.
+            (0..12)
+                .map(|y| color::srgb_to_linear(img.get_pixel(10, y)[0] as f64 / 255f64))
+                .sum::<f64>()
+        };
+
+        let srgb = line(Composite::default());
+        let linear = line(Composite::default().with_linear_light(true));
+
+        assert!((linear - 1f64).abs() < 0.01, "{linear}");
+        assert!(srgb < 0.5f64, "{srgb}");
+    }
+}
@@ -0,0 +1,276 @@
+use std::marker::PhantomData;
+
+use crate::{
+    geom::{clockwise_arc, TOLERANCE},
+    paint::{blend_paint, Paint},
+    path::Path,
+    raster,
+    state::{render_state, RenderState},
+    target::Target,
+    types::{Angle, Circle, Ellipse, FillRule, Point, Rect},
+    Renderer,
+};
+
+/// Anti-aliasing renderer computing the exact area every shape covers in
+/// each pixel, font-rasterizer style.
+///
+/// Every primitive is turned into polygons whose coverage is accumulated
+/// before anything is drawn, so each pixel is composited once per call and
+/// shapes meeting along an edge leave no seam. Hairlines are drawn as
+/// outlines `width` wide, like any other stroke.
+///
+/// Accumulated coverage adds up: overlapping subpaths of a non-zero fill are
+/// slightly too dark along edges where they overlap each other.
+pub struct CoverageRenderer<I> {
+    state: RenderState,
+    _phantom_data: PhantomData<I>,
+}
+
+impl<I> Default for CoverageRenderer<I> {
+    fn default() -> Self {
+        Self {
+            state: RenderState::default(),
+            _phantom_data: PhantomData,
+        }
+    }
+}
+
+render_state!(CoverageRenderer<I>, stroke);
+
+impl<I> CoverageRenderer<I>
+where
+    I: Target,
+{
+    fn fill_polygons(
+        &self,
+        img: &mut I,
+        polygons: &[Vec<Point>],
+        rule: FillRule,
+        paint: &Paint<I::Pixel>,
+    ) {
+        raster::accumulate(polygons, rule, img.size(), |x, y, coverage| {
+            blend_paint(img, x, y, paint, coverage, self.blending())
+        });
+    }
+}
+
+impl<I> Renderer for CoverageRenderer<I>
+where
+    I: Target,
+{
+    type Image = I;
+    type Pixel = I::Pixel;
+
+    fn draw_line(&self, img: &mut Self::Image, from: Point, to: Point, color: Self::Pixel) {
+        self.stroke_path(img, &Path::polyline(&[from, to]), color);
+    }
+
+    fn draw_rect(&self, img: &mut Self::Image, rect: Rect, color: Self::Pixel) {
+        self.stroke_path(img, &Path::rect(rect), color);
+    }
+
+    fn draw_filled_rect<'a>(
+        &self,
+        img: &mut Self::Image,
+        rect: Rect,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        self.fill_path(img, &Path::rect(rect), FillRule::NonZero, paint);
+    }
+
+    fn draw_circle(&self, img: &mut Self::Image, circle: Circle, color: Self::Pixel) {
+        self.stroke_path(img, &Path::circle(circle), color);
+    }
+
+    fn draw_filled_circle<'a>(
+        &self,
+        img: &mut Self::Image,
+        circle: Circle,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        self.fill_path(img, &Path::circle(circle), FillRule::NonZero, paint);
+    }
+
+    fn draw_arc(
+        &self,
+        img: &mut Self::Image,
+        circle: Circle,
+        start: Angle,
+        end: Angle,
+        color: Self::Pixel,
+    ) {
+        let (start, end) = clockwise_arc(start, end);
+        self.stroke_path(img, &Path::arc(circle, start, end), color);
+    }
+
+    fn draw_filled_arc<'a>(
+        &self,
+        img: &mut Self::Image,
+        circle: Circle,
+        start: Angle,
+        end: Angle,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let (start, end) = clockwise_arc(start, end);
+        let path = Path::pie(circle, start, end);
+        self.fill_path(img, &path, FillRule::NonZero, paint);
+    }
+
+    fn draw_ellipse(&self, img: &mut Self::Image, ellipse: Ellipse, color: Self::Pixel) {
+        self.stroke_path(img, &Path::ellipse(ellipse), color);
+    }
+
+    fn draw_filled_ellipse<'a>(
+        &self,
+        img: &mut Self::Image,
+        ellipse: Ellipse,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        self.fill_path(img, &Path::ellipse(ellipse), FillRule::NonZero, paint);
+    }
+
+    fn draw_elliptical_arc(
+        &self,
+        img: &mut Self::Image,
+        ellipse: Ellipse,
+        start: Angle,
+        end: Angle,
+        color: Self::Pixel,
+    ) {
+        let (start, end) = clockwise_arc(start, end);
+        self.stroke_path(img, &Path::elliptical_arc(ellipse, start, end), color);
+    }
+
+    fn draw_filled_elliptical_arc<'a>(
+        &self,
+        img: &mut Self::Image,
+        ellipse: Ellipse,
+        start: Angle,
+        end: Angle,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let (start, end) = clockwise_arc(start, end);
+        let path = Path::elliptical_pie(ellipse, start, end);
+        self.fill_path(img, &path, FillRule::NonZero, paint);
+    }
+
+    fn draw_rounded_rect(
+        &self,
+        img: &mut Self::Image,
+        rect: Rect,
+        corner_radius: f64,
+        color: Self::Pixel,
+    ) {
+        self.stroke_path(img, &Path::rounded_rect(rect, corner_radius), color);
+    }
+
+    fn draw_filled_rounded_rect<'a>(
+        &self,
+        img: &mut Self::Image,
+        rect: Rect,
+        corner_radius: f64,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let path = Path::rounded_rect(rect, corner_radius);
+        self.fill_path(img, &path, FillRule::NonZero, paint);
+    }
+
+    fn draw_polyline(&self, img: &mut Self::Image, points: &[Point], color: Self::Pixel) {
+        self.stroke_path(img, &Path::polyline(points), color);
+    }
+
+    fn draw_polygon(&self, img: &mut Self::Image, points: &[Point], color: Self::Pixel) {
+        self.stroke_path(img, &Path::polygon(points), color);
+    }
+
+    fn draw_filled_polygon<'a>(
+        &self,
+        img: &mut Self::Image,
+        points: &[Point],
+        rule: FillRule,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = paint.into();
+
+        self.fill_polygons(img, &[points.to_vec()], rule, &paint);
+    }
+
+    fn stroke_path(&self, img: &mut Self::Image, path: &Path, color: Self::Pixel) {
+        let polygons: Vec<_> = path
+            .flatten(TOLERANCE)
+            .iter()
+            .flat_map(|(points, closed)| self.state.stroke.pieces(points, *closed, img.size()))
+            .flat_map(|(piece, closed)| self.state.stroke.outline(&piece, closed))
+            .collect();
+
+        // The pieces are all wound the same way, so the non-zero rule fills
+        // their union, and edges they share cancel out without a seam.
+        let color = Paint::Solid(color);
+        self.fill_polygons(img, &polygons, FillRule::NonZero, &color);
+    }
+
+    fn fill_path<'a>(
+        &self,
+        img: &mut Self::Image,
+        path: &Path,
+        rule: FillRule,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = paint.into();
+
+        let polygons: Vec<_> = path
+            .flatten(TOLERANCE)
+            .into_iter()
+            .map(|(p, _)| p)
+            .collect();
+
+        self.fill_polygons(img, &polygons, rule, &paint);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stroke::{LineJoin, Stroke};
+
+    use super::*;
+
+    #[test]
+    fn coverage_renderer_composites_once() {
+        let translucent = image::Rgba([255, 0, 0, 128]);
+
+        let mut halves = Path::polygon(&[
+            Point::new(2f64, 2f64),
+            Point::new(17.3f64, 2f64),
+            Point::new(2f64, 17.3f64),
+        ]);
+        halves
+            .move_to((17.3f64, 2f64))
+            .line_to((17.3f64, 17.3f64))
+            .line_to((2f64, 17.3f64))
+            .close();
+
+        let mut img = image::RgbaImage::new(20, 20);
+        CoverageRenderer::default().fill_path(&mut img, &halves, FillRule::NonZero, translucent);
+        for y in 3..17 {
+            for x in 3..17 {
+                assert_eq!(*img.get_pixel(x, y), translucent);
+            }
+        }
+        assert_eq!(img.get_pixel(17, 10)[3], 102);
+
+        let mut img = image::RgbaImage::new(20, 20);
+        CoverageRenderer::default()
+            .with_stroke(Stroke::new(4f64).with_join(LineJoin::Round))
+            .draw_polyline(
+                &mut img,
+                &[
+                    Point::new(2f64, 17f64),
+                    Point::new(10f64, 3f64),
+                    Point::new(18f64, 17f64),
+                ],
+                translucent,
+            );
+        assert!(img.pixels().all(|p| p[3] <= 128));
+        assert_eq!(*img.get_pixel(10, 4), translucent);
+    }
+}
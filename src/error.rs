@@ -0,0 +1,97 @@
+use std::{error::Error, fmt};
+
+use crate::{svg::ParseError, text::FontError, types::Rect};
+
+/// What can go wrong drawing: geometry that doesn't describe a shape, or
+/// fonts and path data that can't be read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GfxError {
+    /// A coordinate, size or radius is NaN or infinite, or adds up to
+    /// infinity.
+    NonFinite,
+    /// A width, height or radius is negative.
+    Negative,
+    /// Part of the shape lies further than [`MAX_COORDINATE`] from the
+    /// origin.
+    OutOfRange,
+    Font(FontError),
+    Svg(ParseError),
+}
+
+impl fmt::Display for GfxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GfxError::NonFinite => write!(f, "geometry isn't finite"),
+            GfxError::Negative => write!(f, "size or radius is negative"),
+            GfxError::OutOfRange => write!(f, "geometry is too far from the origin"),
+            GfxError::Font(error) => write!(f, "{}", error),
+            GfxError::Svg(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for GfxError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GfxError::Font(error) => Some(error),
+            GfxError::Svg(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<FontError> for GfxError {
+    fn from(error: FontError) -> Self {
+        GfxError::Font(error)
+    }
+}
+
+impl From<ParseError> for GfxError {
+    fn from(error: ParseError) -> Self {
+        GfxError::Svg(error)
+    }
+}
+
+/// How far from the origin, in pixels, the validating constructors accept
+/// coordinates. Curves much larger than this flatten into more segments than
+/// any image could use.
+pub const MAX_COORDINATE: f64 = 16_777_216f64;
+
+/// Checks that `values` are all finite.
+pub(crate) fn finite(values: &[f64]) -> Result<(), GfxError> {
+    if values.iter().all(|v| v.is_finite()) {
+        Ok(())
+    } else {
+        Err(GfxError::NonFinite)
+    }
+}
+
+/// Checks that `values` are all finite and not negative.
+pub(crate) fn non_negative(values: &[f64]) -> Result<(), GfxError> {
+    finite(values)?;
+
+    if values.iter().all(|&v| v >= 0f64) {
+        Ok(())
+    } else {
+        Err(GfxError::Negative)
+    }
+}
+
+/// Checks that `values` are all within [`MAX_COORDINATE`] of the origin.
+pub(crate) fn in_range(values: &[f64]) -> Result<(), GfxError> {
+    if values.iter().all(|v| v.abs() <= MAX_COORDINATE) {
+        Ok(())
+    } else {
+        Err(GfxError::OutOfRange)
+    }
+}
+
+/// Checks that `rect` has a finite position within range and a non-negative
+/// size.
+pub(crate) fn check_rect(rect: Rect) -> Result<(), GfxError> {
+    let edges = [rect.left(), rect.top(), rect.right(), rect.bottom()];
+
+    finite(&edges)?;
+    non_negative(&[rect.width(), rect.height()])?;
+    in_range(&edges)
+}
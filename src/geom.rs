@@ -1,11 +1,181 @@
-use crate::types::Angle;
+use std::f64::consts::PI;
 
+use crate::types::{Angle, Circle, Point, Rect};
+
+/// Whether the direction of `point` from the origin lies on the arc running
+/// clockwise from `start` to `end`, as drawn by the renderers.
 pub fn inside_arc(point: (f64, f64), start: Angle, end: Angle) -> bool {
     let (x, y) = point;
 
-    let d = Angle::Degrees(y.atan2(x).to_degrees())
-        .normalized()
-        .to_degrees();
+    let d = (y.atan2(x).to_degrees() - start.to_degrees()).rem_euclid(360f64);
+
+    d <= arc_sweep(start, end)
+}
+
+/// How many degrees the arc running clockwise (towards increasing angles)
+/// from `start` to `end` spans: it wraps around past 360°, and covers the
+/// full circle once `end` is a turn or more past `start`.
+pub fn arc_sweep(start: Angle, end: Angle) -> f64 {
+    let sweep = end.to_degrees() - start.to_degrees();
+
+    if sweep >= 360f64 {
+        360f64
+    } else {
+        sweep.rem_euclid(360f64)
+    }
+}
+
+/// The arc running clockwise from `start` to `end`, with `start` normalized
+/// and `end` past it by the sweep, as [`Path::arc`] and friends take it.
+///
+/// [`Path::arc`]: crate::path::Path::arc
+pub(crate) fn clockwise_arc(start: Angle, end: Angle) -> (Angle, Angle) {
+    let from = start.normalized().to_degrees();
+
+    (
+        Angle::Degrees(from),
+        Angle::Degrees(from + arc_sweep(start, end)),
+    )
+}
+
+/// Index of the pixel whose center is nearest to `v`.
+pub(crate) fn pixel(v: f64) -> i64 {
+    (v + 0.5).floor() as i64
+}
+
+/// Offsets from the center of an ellipse with radii `rx` and `ry` to the
+/// points where its outline runs at 45°.
+pub(crate) fn diagonal(rx: f64, ry: f64) -> (f64, f64) {
+    let hypot = rx.hypot(ry);
+    (rx * rx / hypot, ry * ry / hypot)
+}
+
+/// Clips the segment `from`-`to` against `bounds` (Liang-Barsky), returning
+/// `None` when no part of it lies inside.
+pub(crate) fn clip_line(from: Point, to: Point, bounds: Rect) -> Option<(Point, Point)> {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let (t0, t1) = clip_line_params(from, to, bounds)?;
+
+    Some((
+        Point::new(from.x + t0 * dx, from.y + t0 * dy),
+        Point::new(from.x + t1 * dx, from.y + t1 * dy),
+    ))
+}
+
+/// Like [`clip_line`], but returns where the part inside starts and ends as
+/// fractions of the way from `from` to `to`: exactly 0 and 1 when unclipped.
+pub(crate) fn clip_line_params(from: Point, to: Point, bounds: Rect) -> Option<(f64, f64)> {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+
+    let mut t0 = 0f64;
+    let mut t1 = 1f64;
+
+    for (p, q) in [
+        (-dx, from.x - bounds.left()),
+        (dx, bounds.right() - from.x),
+        (-dy, from.y - bounds.top()),
+        (dy, bounds.bottom() - from.y),
+    ] {
+        if p == 0f64 {
+            if q < 0f64 {
+                return None;
+            }
+        } else {
+            let t = q / p;
+
+            if p < 0f64 {
+                if t > t1 {
+                    return None;
+                }
+                t0 = t0.max(t);
+            } else {
+                if t < t0 {
+                    return None;
+                }
+                t1 = t1.min(t);
+            }
+        }
+    }
+
+    Some((t0, t1))
+}
+
+/// Maximum distance, in pixels, between a curve and the polyline used to
+/// approximate it.
+pub(crate) const TOLERANCE: f64 = 0.1;
+
+/// Points along the arc of `circle` from `start` to `end` radians, close
+/// enough together that the polyline through them stays within
+/// [`TOLERANCE`] of the curve.
+pub(crate) fn arc_points(circle: Circle, start: f64, end: f64) -> Vec<Point> {
+    let (center, radius) = (circle.center(), circle.radius());
+    let sweep = end - start;
+
+    let step = if radius > TOLERANCE {
+        2f64 * (1f64 - TOLERANCE / radius).acos()
+    } else {
+        PI / 2f64
+    };
+    let steps = (sweep.abs() / step).ceil().max(1f64) as usize;
+
+    (0..=steps)
+        .map(|i| {
+            let t = start + sweep * i as f64 / steps as f64;
+            Point::new(center.x + radius * t.cos(), center.y + radius * t.sin())
+        })
+        .collect()
+}
+
+/// Closed polygon approximating the circle around `center`.
+pub(crate) fn circle_points(center: Point, radius: f64) -> Vec<Point> {
+    let mut points = arc_points(Circle::new(center, radius), 0f64, 2f64 * PI);
+    points.pop();
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clip_line_works() {
+        let bounds = Rect::new(0f64, 0f64, 10f64, 10f64);
+
+        let (a, b) = clip_line(Point::new(-5f64, 5f64), Point::new(15f64, 5f64), bounds).unwrap();
+        assert_eq!((a, b), (Point::new(0f64, 5f64), Point::new(10f64, 5f64)));
+
+        let (a, b) = clip_line(Point::new(2f64, 2f64), Point::new(4f64, 6f64), bounds).unwrap();
+        assert_eq!((a, b), (Point::new(2f64, 2f64), Point::new(4f64, 6f64)));
+
+        assert!(clip_line(Point::new(-5f64, -1f64), Point::new(15f64, -1f64), bounds).is_none());
+        assert!(clip_line(Point::new(-5f64, 2f64), Point::new(2f64, -5f64), bounds).is_none());
+    }
+
+    #[test]
+    fn arcs_wrap_around() {
+        let d = Angle::Degrees;
+
+        assert_eq!(arc_sweep(d(300f64), d(30f64)), 90f64);
+        assert_eq!(arc_sweep(d(30f64), d(300f64)), 270f64);
+        assert_eq!(arc_sweep(d(-30f64), d(400f64)), 360f64);
+        assert_eq!(arc_sweep(d(0f64), d(360f64)), 360f64);
+
+        assert!(inside_arc((1f64, 0f64), d(300f64), d(30f64)));
+        assert!(inside_arc((1f64, -1f64), d(300f64), d(30f64)));
+        assert!(!inside_arc((-1f64, 0f64), d(300f64), d(30f64)));
+        assert!(inside_arc((-1f64, 0f64), d(30f64), d(300f64)));
+        assert!(inside_arc((0f64, -1f64), d(10f64), d(370f64)));
+
+        let (start, end) = clockwise_arc(d(-60f64), d(30f64));
+        assert_eq!((start.to_degrees(), end.to_degrees()), (300f64, 390f64));
+    }
 
-    d >= start.to_degrees() && d <= end.to_degrees()
+    #[test]
+    fn pixel_rounds_to_nearest_center() {
+        assert_eq!(pixel(3f64), 3);
+        assert_eq!(pixel(3.49f64), 3);
+        assert_eq!(pixel(3.5f64), 4);
+        assert_eq!(pixel(-0.2f64), 0);
+        assert_eq!(pixel(-0.7f64), -1);
+    }
 }
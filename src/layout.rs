@@ -0,0 +1,557 @@
+use crate::{
+    color::Blending,
+    target::Target,
+    text::{blend_text, Font, TextAlign},
+    types::{Point, Rect},
+};
+
+/// Vertical position of text in the box it's laid out in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VerticalAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// A piece of text drawn in one color.
+#[derive(Debug, Clone, Copy)]
+pub struct TextRun<'t, P> {
+    pub text: &'t str,
+    pub color: P,
+}
+
+impl<'t, P> TextRun<'t, P> {
+    pub fn new(text: &'t str, color: P) -> Self {
+        Self { text, color }
+    }
+}
+
+/// A character of the text being laid out and the run it comes from.
+type Char = (char, usize);
+
+/// How to fit text into a rectangle.
+///
+/// Lines break at `\n` and, when wrapping, between words where they would
+/// get wider than the rectangle; words too long for a line of their own are
+/// broken between characters. Lines that don't fit below each other are
+/// dropped, and the last one left ends in an ellipsis. Without wrapping,
+/// lines too wide are cut short with an ellipsis instead.
+#[derive(Clone)]
+pub struct TextBox<'f> {
+    font: &'f dyn Font,
+    align: TextAlign,
+    vertical_align: VerticalAlign,
+    line_height: Option<f64>,
+    wrap: bool,
+    ellipsis: Option<String>,
+}
+
+impl<'f> TextBox<'f> {
+    /// Wrapped text at the top left of the box, lines as far apart as the
+    /// font's line height and cut short with `...`.
+    pub fn new(font: &'f dyn Font) -> Self {
+        Self {
+            font,
+            align: TextAlign::default(),
+            vertical_align: VerticalAlign::default(),
+            line_height: None,
+            wrap: true,
+            ellipsis: Some("...".to_owned()),
+        }
+    }
+
+    pub fn with_align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn with_vertical_align(mut self, vertical_align: VerticalAlign) -> Self {
+        self.vertical_align = vertical_align;
+        self
+    }
+
+    /// Sets the distance between the baselines of consecutive lines.
+    pub fn with_line_height(mut self, line_height: f64) -> Self {
+        self.line_height = Some(line_height);
+        self
+    }
+
+    pub fn with_wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    /// Sets what text cut short ends in, `None` to just cut it.
+    pub fn with_ellipsis(mut self, ellipsis: Option<&str>) -> Self {
+        self.ellipsis = ellipsis.map(str::to_owned);
+        self
+    }
+
+    pub fn font(&self) -> &'f dyn Font {
+        self.font
+    }
+
+    pub fn align(&self) -> TextAlign {
+        self.align
+    }
+
+    pub fn vertical_align(&self) -> VerticalAlign {
+        self.vertical_align
+    }
+
+    pub fn line_height(&self) -> f64 {
+        self.line_height.unwrap_or_else(|| self.font.line_height())
+    }
+
+    pub fn wraps(&self) -> bool {
+        self.wrap
+    }
+
+    pub fn ellipsis(&self) -> Option<&str> {
+        self.ellipsis.as_deref()
+    }
+
+    /// Breaks `runs` into lines fitting `rect` and places them in it.
+    pub fn layout<P: Copy>(&self, rect: Rect, runs: &[TextRun<P>]) -> TextLayout<'f, P> {
+        let chars: Vec<Char> = runs
+            .iter()
+            .enumerate()
+            .flat_map(|(i, run)| run.text.chars().map(move |c| (c, i)))
+            .collect();
+
+        let mut truncated = false;
+        let mut lines = vec![];
+
+        if !runs.is_empty() {
+            for paragraph in chars.split(|&(c, _)| c == '\n') {
+                for line in self.wrap(paragraph, rect.width()) {
+                    if self.fits(&line, rect.width()) {
+                        lines.push(line);
+                    } else {
+                        truncated = true;
+                        lines.push(self.ellipsize(line, rect.width()));
+                    }
+                }
+            }
+        }
+
+        let (ascent, descent) = (self.font.ascent(), self.font.descent());
+        let line_height = self.line_height();
+        let block = |lines: usize| match lines {
+            0 => 0f64,
+            n => ascent + descent + (n - 1) as f64 * line_height,
+        };
+
+        let mut count = lines.len();
+        while count > 0 && block(count) > rect.height() + 1e-9 {
+            count -= 1;
+        }
+
+        if count < lines.len() {
+            truncated = true;
+
+            let more = lines[count].clone();
+            lines.truncate(count);
+
+            // The ellipsis says there's more, so it's added even if the last
+            // line left would fit. Without one, that line ends where it did.
+            if self.ellipsis.is_some() {
+                if let Some(mut last) = lines.pop() {
+                    if let Some(&(_, run)) = more.first() {
+                        last.push((' ', run));
+                        last.extend(more);
+                    }
+                    lines.push(self.ellipsize(last, rect.width()));
+                }
+            }
+        }
+
+        let top = rect.top()
+            + match self.vertical_align {
+                VerticalAlign::Top => 0f64,
+                VerticalAlign::Middle => (rect.height() - block(count)) / 2f64,
+                VerticalAlign::Bottom => rect.height() - block(count),
+            };
+
+        let lines = lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let width = self.width(line);
+                let x = rect.left()
+                    + match self.align {
+                        TextAlign::Left => 0f64,
+                        TextAlign::Center => (rect.width() - width) / 2f64,
+                        TextAlign::Right => rect.width() - width,
+                    };
+
+                // Pixel centers are at integers, so the pen starts half a
+                // pixel in from the corner of the line.
+                let origin = Point::new(x + 0.5f64, top + ascent + i as f64 * line_height + 0.5f64);
+
+                let mut pieces: Vec<(String, P)> = vec![];
+                let mut previous = None;
+                for &(c, run) in line {
+                    match pieces.last_mut() {
+                        Some((text, _)) if previous == Some(run) => text.push(c),
+                        _ => pieces.push((c.to_string(), runs[run].color)),
+                    }
+                    previous = Some(run);
+                }
+
+                TextLine {
+                    origin,
+                    width,
+                    runs: pieces,
+                }
+            })
+            .collect();
+
+        TextLayout {
+            font: self.font,
+            lines,
+            truncated,
+        }
+    }
+
+    /// Lays `runs` out in `rect` and composites them onto `img`.
+    pub fn draw<T: Target>(&self, img: &mut T, rect: Rect, runs: &[TextRun<T::Pixel>]) {
+        self.layout(rect, runs).draw(img);
+    }
+
+    /// Breaks `paragraph` into lines at most `width` wide, if wrapping.
+    fn wrap(&self, paragraph: &[Char], width: f64) -> Vec<Vec<Char>> {
+        if !self.wrap {
+            return vec![paragraph.to_vec()];
+        }
+
+        let mut lines = vec![];
+        let mut line: Vec<Char> = vec![];
+
+        for word in words(paragraph) {
+            let mut candidate = line.clone();
+            candidate.extend_from_slice(word);
+
+            if self.fits(&candidate, width) {
+                line = candidate;
+                continue;
+            }
+
+            let kept = trim_end(&line);
+            if !kept.is_empty() {
+                lines.push(kept.to_vec());
+            }
+            line.clear();
+
+            for &c in trim_start(word) {
+                line.push(c);
+
+                if line.len() > 1 && !self.fits(&line, width) {
+                    line.pop();
+                    lines.push(std::mem::replace(&mut line, vec![c]));
+                }
+            }
+        }
+
+        lines.push(trim_end(&line).to_vec());
+        lines
+    }
+
+    /// Cuts `line` short enough to fit `width` with the ellipsis after it,
+    /// in the color of the last character. Nothing is left if even the
+    /// ellipsis is too wide.
+    fn ellipsize(&self, mut line: Vec<Char>, width: f64) -> Vec<Char> {
+        let run = line.last().map_or(0, |c| c.1);
+        let ellipsis: Vec<Char> = self
+            .ellipsis
+            .iter()
+            .flat_map(|e| e.chars())
+            .map(|c| (c, run))
+            .collect();
+
+        loop {
+            let mut candidate = trim_end(&line).to_vec();
+            candidate.extend_from_slice(&ellipsis);
+
+            if self.fits(&candidate, width) {
+                return candidate;
+            }
+
+            if line.pop().is_none() {
+                return vec![];
+            }
+        }
+    }
+
+    /// Whether `line` is at most `width` wide, not counting trailing space.
+    fn fits(&self, line: &[Char], width: f64) -> bool {
+        self.width(trim_end(line)) <= width + 1e-9
+    }
+
+    fn width(&self, line: &[Char]) -> f64 {
+        self.font.measure(&text(line)).0
+    }
+}
+
+/// Text laid out in a rectangle, line by line.
+pub struct TextLayout<'f, P> {
+    font: &'f dyn Font,
+    lines: Vec<TextLine<P>>,
+    truncated: bool,
+}
+
+impl<P: Copy> TextLayout<'_, P> {
+    pub fn lines(&self) -> &[TextLine<P>] {
+        &self.lines
+    }
+
+    /// Whether some of the text didn't fit and was left out.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// The area the lines take up, from the top of the first one's ascent
+    /// to the bottom of the last one's descent, or `None` without lines.
+    pub fn bounds(&self) -> Option<Rect> {
+        let (first, last) = (self.lines.first()?, self.lines.last()?);
+
+        let left = self
+            .lines
+            .iter()
+            .map(|l| l.origin.x)
+            .fold(f64::MAX, f64::min);
+        let right = self
+            .lines
+            .iter()
+            .map(|l| l.origin.x + l.width)
+            .fold(f64::MIN, f64::max);
+        let top = first.origin.y - self.font.ascent();
+        let bottom = last.origin.y + self.font.descent();
+
+        Some(Rect::new(
+            left - 0.5f64,
+            top - 0.5f64,
+            right - left,
+            bottom - top,
+        ))
+    }
+
+    pub fn draw<T: Target<Pixel = P>>(&self, img: &mut T) {
+        self.blend(img, Blending::default());
+    }
+
+    pub(crate) fn blend<T: Target<Pixel = P>>(&self, img: &mut T, blending: Blending) {
+        for line in &self.lines {
+            let mut pen = 0f64;
+            let mut previous = None;
+
+            for (text, color) in &line.runs {
+                // Each run is kerned with the last character of the one
+                // before.
+                if let (Some(left), Some(right)) = (previous, text.chars().next()) {
+                    pen += self.font.kerning(left, right);
+                }
+
+                let origin = Point::new(line.origin.x + pen, line.origin.y);
+                blend_text(img, self.font, text, origin, *color, blending);
+
+                pen += self.font.measure(text).0;
+                previous = text.chars().last().or(previous);
+            }
+        }
+    }
+}
+
+/// One line of laid out text.
+#[derive(Debug, Clone)]
+pub struct TextLine<P> {
+    origin: Point,
+    width: f64,
+    runs: Vec<(String, P)>,
+}
+
+impl<P: Copy> TextLine<P> {
+    /// Where the pen starts, on the baseline, as `draw_text` takes it.
+    pub fn origin(&self) -> Point {
+        self.origin
+    }
+
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    /// The pieces of the line and their colors.
+    pub fn runs(&self) -> impl Iterator<Item = (&str, P)> {
+        self.runs
+            .iter()
+            .map(|(text, color)| (text.as_str(), *color))
+    }
+
+    pub fn text(&self) -> String {
+        self.runs.iter().map(|(text, _)| text.as_str()).collect()
+    }
+}
+
+/// Splits `chars` into words, each with the space before it.
+fn words(chars: &[Char]) -> impl Iterator<Item = &[Char]> {
+    let mut rest = chars;
+
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+
+        let spaces = rest.iter().take_while(|c| c.0.is_whitespace()).count();
+        let letters = rest[spaces..]
+            .iter()
+            .take_while(|c| !c.0.is_whitespace())
+            .count();
+
+        let (word, tail) = rest.split_at(spaces + letters);
+        rest = tail;
+        Some(word)
+    })
+}
+
+fn trim_start(chars: &[Char]) -> &[Char] {
+    let spaces = chars.iter().take_while(|c| c.0.is_whitespace()).count();
+    &chars[spaces..]
+}
+
+fn trim_end(chars: &[Char]) -> &[Char] {
+    let spaces = chars
+        .iter()
+        .rev()
+        .take_while(|c| c.0.is_whitespace())
+        .count();
+    &chars[..chars.len() - spaces]
+}
+
+fn text(chars: &[Char]) -> String {
+    chars.iter().map(|c| c.0).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{GrayImage, Luma, Rgba, RgbaImage};
+
+    use crate::{outline::OutlineFont, text::BitmapFont};
+
+    use super::*;
+
+    fn lines(layout: &TextLayout<()>) -> Vec<String> {
+        layout.lines().iter().map(|l| l.text()).collect()
+    }
+
+    #[test]
+    fn text_wraps_and_is_cut_short() {
+        // The bundled font is 8 pixels a character and 16 a line: 10
+        // characters to a line here.
+        let text_box = TextBox::new(BitmapFont::builtin());
+        let rect = Rect::new(0f64, 0f64, 80f64, 100f64);
+        let run = |text| [TextRun::new(text, ())];
+
+        let layout = text_box.layout(
+            rect,
+            &run("the quick brown fox  jumps\n\nSupercalifragilistic"),
+        );
+        assert_eq!(
+            lines(&layout),
+            [
+                "the quick",
+                "brown fox",
+                "jumps",
+                "",
+                "Supercalif",
+                "ragilistic"
+            ]
+        );
+        assert!(!layout.is_truncated());
+
+        // Two lines fit 40 pixels, and the second says there's more.
+        let short = Rect::new(0f64, 0f64, 80f64, 40f64);
+        let layout = text_box.layout(short, &run("the quick brown fox jumps"));
+        assert_eq!(lines(&layout), ["the quick", "brown f..."]);
+        assert!(layout.is_truncated());
+
+        let layout = text_box
+            .clone()
+            .with_wrap(false)
+            .with_ellipsis(Some("~"))
+            .layout(rect, &run("MERCHANT WITH A VERY LONG NAME\nOK"));
+        assert_eq!(lines(&layout), ["MERCHANT~", "OK"]);
+
+        let text_box = text_box.with_ellipsis(None);
+        let layout = text_box.layout(short, &run("the quick brown fox jumps"));
+        assert_eq!(lines(&layout), ["the quick", "brown fox"]);
+
+        // Dropped lines don't leave a piece of themselves behind.
+        let layout = text_box.layout(short, &run("the quick\nhello\nworld"));
+        assert_eq!(lines(&layout), ["the quick", "hello"]);
+        assert!(layout.is_truncated());
+
+        // An ellipsis too wide for the box is left out too.
+        let narrow = Rect::new(0f64, 0f64, 20f64, 100f64);
+        let layout = TextBox::new(BitmapFont::builtin())
+            .with_wrap(false)
+            .layout(narrow, &run("overflowing"));
+        assert_eq!(lines(&layout), [""]);
+        assert!(layout.is_truncated());
+    }
+
+    #[test]
+    fn lines_are_aligned_and_colored_by_run() {
+        let (red, blue) = (Rgba([255, 0, 0, 255]), Rgba([0, 0, 255, 255]));
+        let runs = [TextRun::new("ab", red), TextRun::new("c\nd", blue)];
+        let rect = Rect::new(0f64, 0f64, 100f64, 60f64);
+
+        let text_box = TextBox::new(BitmapFont::builtin())
+            .with_align(TextAlign::Right)
+            .with_vertical_align(VerticalAlign::Bottom)
+            .with_line_height(20f64);
+        let layout = text_box.layout(rect, &runs);
+
+        // Two lines make a block 16 + 20 pixels high.
+        let bounds = layout.bounds().unwrap();
+        assert_eq!((bounds.left(), bounds.top()), (76f64, 24f64));
+        assert_eq!((bounds.right(), bounds.bottom()), (100f64, 60f64));
+        assert_eq!(layout.lines()[1].origin(), Point::new(92.5f64, 56.5f64));
+
+        let line = &layout.lines()[0];
+        assert_eq!(
+            line.runs().map(|(text, _)| text).collect::<Vec<_>>(),
+            ["ab", "c"]
+        );
+
+        let mut img = RgbaImage::new(100, 60);
+        layout.draw(&mut img);
+
+        let painted: Vec<_> = img
+            .enumerate_pixels()
+            .filter(|(_, y, p)| *y < 40 && p[3] > 0)
+            .collect();
+        assert!(painted.iter().any(|(x, _, p)| *x < 92 && **p == red));
+        assert!(painted.iter().any(|(x, _, p)| *x >= 92 && **p == blue));
+        assert!(painted.iter().all(|(x, _, p)| (*x < 92) == (**p == red)));
+    }
+
+    #[test]
+    fn runs_are_kerned_with_each_other() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/DejaVuSerif.ttf"
+        );
+        let font = OutlineFont::from_file(path).unwrap().with_size(32f64);
+        let (white, rect) = (Luma([255u8]), Rect::new(0f64, 0f64, 100f64, 40f64));
+
+        let mut whole = GrayImage::new(100, 40);
+        TextBox::new(&font).draw(&mut whole, rect, &[TextRun::new("AVA", white)]);
+
+        let mut pieces = GrayImage::new(100, 40);
+        let runs = [TextRun::new("A", white), TextRun::new("VA", white)];
+        TextBox::new(&font).draw(&mut pieces, rect, &runs);
+
+        assert_eq!(whole, pieces);
+    }
+}
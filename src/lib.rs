@@ -1,28 +1,55 @@
 pub mod antialiased;
 pub mod basic;
+pub mod canvas;
+pub mod clip;
+pub mod color;
+pub mod composite;
+pub mod coverage;
+pub mod error;
 pub mod geom;
+pub mod layout;
+pub mod mask;
+pub mod outline;
+pub mod paint;
+pub mod path;
+pub mod premultiplied;
+mod raster;
+mod state;
+pub mod stroke;
+pub mod supersample;
+pub mod svg;
+pub mod target;
+pub mod text;
+pub mod transform;
 pub mod types;
 
-use image::{GenericImage, GenericImageView};
+use color::{blend_coverage, Blending};
+use paint::{blend_paint, Paint};
+use path::Path;
+use target::Target;
 use types::*;
 
 pub trait Renderer {
     type Image;
-    type Pixel;
+    type Pixel: 'static;
 
-    fn draw_line(
+    fn draw_line(&self, img: &mut Self::Image, from: Point, to: Point, color: Self::Pixel);
+
+    fn draw_rect(&self, img: &mut Self::Image, rect: Rect, color: Self::Pixel);
+    fn draw_filled_rect<'a>(
         &self,
         img: &mut Self::Image,
-        from: (u32, u32),
-        to: (u32, u32),
-        color: Self::Pixel,
+        rect: Rect,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
     );
 
-    fn draw_rect(&self, img: &mut Self::Image, rect: Rect, color: Self::Pixel);
-    fn draw_filled_rect(&self, img: &mut Self::Image, rect: Rect, color: Self::Pixel);
-
     fn draw_circle(&self, img: &mut Self::Image, circle: Circle, color: Self::Pixel);
-    fn draw_filled_circle(&self, img: &mut Self::Image, circle: Circle, color: Self::Pixel);
+    fn draw_filled_circle<'a>(
+        &self,
+        img: &mut Self::Image,
+        circle: Circle,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    );
 
     fn draw_arc(
         &self,
@@ -32,45 +59,232 @@ pub trait Renderer {
         end: Angle,
         color: Self::Pixel,
     );
-    fn draw_filled_arc(
+    fn draw_filled_arc<'a>(
+        &self,
+        img: &mut Self::Image,
+        circle: Circle,
+        start: Angle,
+        end: Angle,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    );
+
+    /// Like [`draw_arc`](Renderer::draw_arc), running `direction` from
+    /// `start` to `end`. Dashes start at `start` either way.
+    fn draw_directed_arc(
+        &self,
+        img: &mut Self::Image,
+        circle: Circle,
+        start: Angle,
+        end: Angle,
+        direction: ArcDirection,
+        color: Self::Pixel,
+    ) {
+        match direction {
+            ArcDirection::Clockwise => self.draw_arc(img, circle, start, end, color),
+            ArcDirection::CounterClockwise => {
+                let path = Path::directed_arc(circle, start, end, direction);
+                self.stroke_path(img, &path, color)
+            }
+        }
+    }
+
+    /// Like [`draw_filled_arc`](Renderer::draw_filled_arc), running
+    /// `direction` from `start` to `end`.
+    fn draw_filled_directed_arc<'a>(
         &self,
         img: &mut Self::Image,
         circle: Circle,
         start: Angle,
         end: Angle,
+        direction: ArcDirection,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let (start, end) = direction.clockwise(start, end);
+        self.draw_filled_arc(img, circle, start, end, paint);
+    }
+
+    fn draw_ellipse(&self, img: &mut Self::Image, ellipse: Ellipse, color: Self::Pixel);
+    fn draw_filled_ellipse<'a>(
+        &self,
+        img: &mut Self::Image,
+        ellipse: Ellipse,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    );
+
+    fn draw_elliptical_arc(
+        &self,
+        img: &mut Self::Image,
+        ellipse: Ellipse,
+        start: Angle,
+        end: Angle,
         color: Self::Pixel,
     );
+    fn draw_filled_elliptical_arc<'a>(
+        &self,
+        img: &mut Self::Image,
+        ellipse: Ellipse,
+        start: Angle,
+        end: Angle,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    );
+
+    /// Like [`draw_elliptical_arc`](Renderer::draw_elliptical_arc), running
+    /// `direction` from `start` to `end`. Dashes start at `start` either way.
+    fn draw_directed_elliptical_arc(
+        &self,
+        img: &mut Self::Image,
+        ellipse: Ellipse,
+        start: Angle,
+        end: Angle,
+        direction: ArcDirection,
+        color: Self::Pixel,
+    ) {
+        match direction {
+            ArcDirection::Clockwise => self.draw_elliptical_arc(img, ellipse, start, end, color),
+            ArcDirection::CounterClockwise => {
+                let path = Path::directed_elliptical_arc(ellipse, start, end, direction);
+                self.stroke_path(img, &path, color)
+            }
+        }
+    }
+
+    /// Like [`draw_filled_elliptical_arc`](Renderer::draw_filled_elliptical_arc),
+    /// running `direction` from `start` to `end`.
+    fn draw_filled_directed_elliptical_arc<'a>(
+        &self,
+        img: &mut Self::Image,
+        ellipse: Ellipse,
+        start: Angle,
+        end: Angle,
+        direction: ArcDirection,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let (start, end) = direction.clockwise(start, end);
+        self.draw_filled_elliptical_arc(img, ellipse, start, end, paint);
+    }
 
     fn draw_rounded_rect(
         &self,
         img: &mut Self::Image,
         rect: Rect,
-        corner_radius: u32,
+        corner_radius: f64,
         color: Self::Pixel,
     );
-    fn draw_filled_rounded_rect(
+    fn draw_filled_rounded_rect<'a>(
         &self,
         img: &mut Self::Image,
         rect: Rect,
-        corner_radius: u32,
-        color: Self::Pixel,
+        corner_radius: f64,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    );
+
+    /// Outlines `rounded`, which may have a different radius per corner.
+    fn stroke_rounded_rect(&self, img: &mut Self::Image, rounded: RoundedRect, color: Self::Pixel) {
+        match rounded.uniform_radius() {
+            Some(radius) => self.draw_rounded_rect(img, rounded.rect(), radius, color),
+            None => self.stroke_path(img, &rounded.into(), color),
+        }
+    }
+
+    /// Fills `rounded`, which may have a different radius per corner.
+    fn fill_rounded_rect<'a>(
+        &self,
+        img: &mut Self::Image,
+        rounded: RoundedRect,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        match rounded.uniform_radius() {
+            Some(radius) => self.draw_filled_rounded_rect(img, rounded.rect(), radius, paint),
+            None => self.fill_path(img, &rounded.into(), FillRule::NonZero, paint),
+        }
+    }
+
+    fn draw_polyline(&self, img: &mut Self::Image, points: &[Point], color: Self::Pixel);
+    fn draw_polygon(&self, img: &mut Self::Image, points: &[Point], color: Self::Pixel);
+    fn draw_filled_polygon<'a>(
+        &self,
+        img: &mut Self::Image,
+        points: &[Point],
+        rule: FillRule,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    );
+
+    fn stroke_path(&self, img: &mut Self::Image, path: &Path, color: Self::Pixel);
+    fn fill_path<'a>(
+        &self,
+        img: &mut Self::Image,
+        path: &Path,
+        rule: FillRule,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
     );
 }
 
-pub(crate) fn blend_pixel<I: GenericImage>(
+/// Composites `color` onto the pixel at `x`, `y`, ignoring pixels outside
+/// the image.
+pub(crate) fn blend_pixel<I>(img: &mut I, x: i64, y: i64, color: I::Pixel, blending: Blending)
+where
+    I: Target,
+{
+    blend_coverage(img, x, y, color, 1f64, blending);
+}
+
+/// Composites `color` onto the pixels `from..=to` of row `y`, skipping the
+/// ones outside the image or rejected by `filter`.
+pub(crate) fn blend_span<I, F>(
+    img: &mut I,
+    y: i64,
+    from: i64,
+    to: i64,
+    color: I::Pixel,
+    blending: Blending,
+    filter: F,
+) where
+    I: Target,
+    F: Fn(i64, i64) -> bool,
+{
+    paint_span(img, y, from, to, &Paint::Solid(color), blending, filter);
+}
+
+/// Like [`blend_span`], with the color of each pixel taken from `paint` at
+/// its center.
+pub(crate) fn paint_span<I, F>(
     img: &mut I,
-    x: u32,
-    y: u32,
-    color: <I as GenericImageView>::Pixel,
-) {
-    if img.in_bounds(x, y) {
-        img.blend_pixel(x, y, color);
+    y: i64,
+    from: i64,
+    to: i64,
+    paint: &Paint<I::Pixel>,
+    blending: Blending,
+    filter: F,
+) where
+    I: Target,
+    F: Fn(i64, i64) -> bool,
+{
+    let (width, height) = img.size();
+
+    if y < 0 || y >= height as i64 {
+        return;
+    }
+
+    for x in from.max(0)..=to.min(width as i64 - 1) {
+        if filter(x, y) {
+            blend_paint(img, x, y, paint, 1f64, blending);
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{antialiased::AntiAliasingRender, basic::BasicRenderer};
+    use std::f64::consts::FRAC_PI_2;
+
+    use crate::{
+        antialiased::AntiAliasingRender,
+        basic::BasicRenderer,
+        coverage::CoverageRenderer,
+        path::Path,
+        stroke::Stroke,
+        supersample::SupersampledRenderer,
+        transform::{Transform, TransformedRenderer},
+    };
 
     use super::*;
 
@@ -81,76 +295,91 @@ mod tests {
         let r = BasicRenderer::default();
         let aar = AntiAliasingRender::default();
 
-        r.draw_line(&mut img, (90, 70), (90, 80), image::Rgba([0, 0, 255, 255]));
-        r.draw_line(&mut img, (90, 70), (80, 60), image::Rgba([0, 0, 255, 255]));
+        r.draw_line(
+            &mut img,
+            Point::new(90f64, 70f64),
+            Point::new(90f64, 80f64),
+            image::Rgba([0, 0, 255, 255]),
+        );
+        r.draw_line(
+            &mut img,
+            Point::new(90f64, 70f64),
+            Point::new(80f64, 60f64),
+            image::Rgba([0, 0, 255, 255]),
+        );
 
-        aar.draw_line(&mut img, (97, 70), (80, 60), image::Rgba([255, 0, 0, 255]));
+        aar.draw_line(
+            &mut img,
+            Point::new(97f64, 70f64),
+            Point::new(80f64, 60f64),
+            image::Rgba([255, 0, 0, 255]),
+        );
 
         r.draw_rect(
             &mut img,
-            Rect::new(8, 8, 16, 16),
+            Rect::new(8f64, 8f64, 16f64, 16f64),
             image::Rgba([255, 0, 0, 255]),
         );
 
         r.draw_filled_rect(
             &mut img,
-            Rect::new(16, 16, 8, 8),
+            Rect::new(16f64, 16f64, 8f64, 8f64),
             image::Rgba([255, 0, 0, 255]),
         );
 
         aar.draw_filled_circle(
             &mut img,
-            Circle::new((50, 50), 30),
+            Circle::new((50f64, 50f64), 30f64),
             image::Rgba([0, 0, 255, 255]),
         );
         r.draw_circle(
             &mut img,
-            Circle::new((50, 50), 30),
+            Circle::new((50f64, 50f64), 30f64),
             image::Rgba([255, 0, 0, 255]),
         );
 
         r.draw_circle(
             &mut img,
-            Circle::new((50, 50), 20),
+            Circle::new((50f64, 50f64), 20f64),
             image::Rgba([0, 255, 0, 255]),
         );
 
         aar.draw_circle(
             &mut img,
-            Circle::new((50, 50), 15),
+            Circle::new((50f64, 50f64), 15f64),
             image::Rgba([255, 0, 255, 255]),
         );
 
         r.draw_filled_circle(
             &mut img,
-            Circle::new((50, 50), 10),
+            Circle::new((50f64, 50f64), 10f64),
             image::Rgba([0, 255, 0, 255]),
         );
 
         aar.draw_arc(
             &mut img,
-            Circle::new((10, 50), 5),
+            Circle::new((10f64, 50f64), 5f64),
             Angle::Degrees(0f64),
             Angle::Degrees(90f64),
             image::Rgba([0, 255, 0, 255]),
         );
         aar.draw_arc(
             &mut img,
-            Circle::new((10, 50), 5),
+            Circle::new((10f64, 50f64), 5f64),
             Angle::Degrees(90f64),
             Angle::Degrees(180f64),
             image::Rgba([0, 0, 255, 255]),
         );
         aar.draw_arc(
             &mut img,
-            Circle::new((10, 50), 5),
+            Circle::new((10f64, 50f64), 5f64),
             Angle::Degrees(180f64),
             Angle::Degrees(270f64),
             image::Rgba([255, 0, 0, 255]),
         );
         r.draw_arc(
             &mut img,
-            Circle::new((10, 50), 5),
+            Circle::new((10f64, 50f64), 5f64),
             Angle::Degrees(270f64),
             Angle::Degrees(359.9f64),
             image::Rgba([255, 0, 255, 255]),
@@ -158,28 +387,28 @@ mod tests {
 
         r.draw_filled_arc(
             &mut img,
-            Circle::new((10, 80), 10),
+            Circle::new((10f64, 80f64), 10f64),
             Angle::Degrees(0f64),
             Angle::Degrees(90f64),
             image::Rgba([0, 255, 0, 255]),
         );
         aar.draw_filled_arc(
             &mut img,
-            Circle::new((10, 80), 10),
+            Circle::new((10f64, 80f64), 10f64),
             Angle::Degrees(90f64),
             Angle::Degrees(180f64),
             image::Rgba([0, 0, 255, 255]),
         );
         aar.draw_filled_arc(
             &mut img,
-            Circle::new((10, 80), 10),
+            Circle::new((10f64, 80f64), 10f64),
             Angle::Degrees(180f64),
             Angle::Degrees(270f64),
             image::Rgba([255, 0, 0, 255]),
         );
         aar.draw_filled_arc(
             &mut img,
-            Circle::new((10, 80), 10),
+            Circle::new((10f64, 80f64), 10f64),
             Angle::Degrees(270f64),
             Angle::Degrees(359.9999f64),
             image::Rgba([255, 0, 255, 255]),
@@ -187,17 +416,372 @@ mod tests {
 
         aar.draw_rounded_rect(
             &mut img,
-            Rect::new(80, 10, 10, 10),
-            3,
+            Rect::new(80f64, 10f64, 10f64, 10f64),
+            3f64,
             image::Rgba([255, 0, 0, 255]),
         );
         aar.draw_filled_rounded_rect(
             &mut img,
-            Rect::new(80, 25, 10, 10),
-            3,
+            Rect::new(80f64, 25f64, 10f64, 10f64),
+            3f64,
             image::Rgba([255, 0, 0, 255]),
         );
 
         img.save("./test.png").unwrap();
     }
+
+    #[test]
+    fn shapes_partially_off_canvas_are_clipped() {
+        let mut img = image::RgbaImage::from_pixel(20, 20, image::Rgba([255, 255, 255, 255]));
+        let red = image::Rgba([255, 0, 0, 255]);
+
+        let r = BasicRenderer::default();
+        let aar = AntiAliasingRender::default();
+
+        r.draw_circle(&mut img, Circle::new((0f64, 10f64), 5f64), red);
+        r.draw_filled_circle(&mut img, Circle::new((-3.5f64, -3.5f64), 6f64), red);
+        aar.draw_circle(&mut img, Circle::new((19.5f64, 0.25f64), 4f64), red);
+        aar.draw_filled_circle(&mut img, Circle::new((-1e6f64, 10f64), 5f64), red);
+        r.draw_line(
+            &mut img,
+            Point::new(-50f64, 5f64),
+            Point::new(50f64, 5f64),
+            red,
+        );
+        aar.draw_line(
+            &mut img,
+            Point::new(-1e9f64, -1e9f64),
+            Point::new(1e9f64, 1e9f64),
+            red,
+        );
+        r.draw_filled_rect(&mut img, Rect::new(-10f64, 15f64, 15f64, 100f64), red);
+
+        assert_eq!(*img.get_pixel(0, 5), red);
+        assert_eq!(*img.get_pixel(19, 5), red);
+        assert_eq!(*img.get_pixel(0, 0), red);
+        assert_eq!(*img.get_pixel(5, 19), red);
+        assert_eq!(*img.get_pixel(10, 10), red);
+        assert_eq!(*img.get_pixel(12, 17), image::Rgba([255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn huge_circles_are_clipped() {
+        let red = image::Rgba([255, 0, 0, 255]);
+        let clear = image::Rgba([0, 0, 0, 0]);
+        let huge = Circle::new((25f64, 25f64), 1e19f64);
+
+        let r = BasicRenderer::default();
+        let aar = AntiAliasingRender::default();
+
+        let mut img = image::RgbaImage::new(20, 20);
+        r.draw_circle(&mut img, huge, red);
+        aar.draw_circle(&mut img, huge, red);
+        assert!(img.pixels().all(|p| *p == clear));
+
+        aar.draw_filled_circle(&mut img, huge, red);
+        assert!(img.pixels().all(|p| *p == red));
+
+        // The left side of a circle a million pixels across runs along
+        // column 5.
+        let edge = Circle::new((1e6f64 + 5f64, 10f64), 1e6f64);
+
+        let mut img = image::RgbaImage::new(20, 20);
+        r.draw_arc(
+            &mut img,
+            edge,
+            Angle::Degrees(90f64),
+            Angle::Degrees(270f64),
+            red,
+        );
+        assert_eq!(*img.get_pixel(5, 10), red);
+        assert_eq!(*img.get_pixel(6, 10), clear);
+
+        let mut img = image::RgbaImage::new(20, 20);
+        aar.draw_filled_circle(&mut img, edge, red);
+        assert_eq!(*img.get_pixel(5, 10), red);
+        assert_eq!(*img.get_pixel(4, 10), clear);
+    }
+
+    fn check_rect_outlines<R>(r: &R)
+    where
+        R: Renderer<Image = image::RgbaImage, Pixel = image::Rgba<u8>>,
+    {
+        let translucent = image::Rgba([255, 0, 0, 128]);
+        let tall = Rect::new(2f64, -1e15f64, 10f64, 2e15f64);
+
+        let mut img = image::RgbaImage::new(20, 20);
+        r.draw_rect(&mut img, tall, translucent);
+        assert_eq!(*img.get_pixel(2, 0), translucent);
+        assert_eq!(*img.get_pixel(12, 19), translucent);
+        assert_eq!(img.get_pixel(7, 10)[3], 0);
+
+        // Corners are blended once, by the top and bottom edges.
+        let mut img = image::RgbaImage::new(20, 20);
+        let rect = Rect::new(2f64, 3f64, 10f64, 10f64);
+        r.draw_rect(&mut img, rect, translucent);
+        assert_eq!(*img.get_pixel(2, 3), translucent);
+        assert_eq!(*img.get_pixel(12, 13), translucent);
+        assert_eq!(*img.get_pixel(2, 8), translucent);
+
+        // So are rects with no width or height, whose sides coincide.
+        let mut img = image::RgbaImage::new(20, 20);
+        let flat = Rect::new(2f64, 3f64, 10f64, 0f64);
+        let thin = Rect::new(2f64, 5f64, 0f64, 10f64);
+        r.draw_rect(&mut img, flat, translucent);
+        r.draw_rect(&mut img, thin, translucent);
+        assert_eq!(*img.get_pixel(7, 3), translucent);
+        assert_eq!(*img.get_pixel(2, 5), translucent);
+        assert_eq!(*img.get_pixel(2, 10), translucent);
+    }
+
+    #[test]
+    fn rect_outlines_blend_each_pixel_once() {
+        check_rect_outlines(&BasicRenderer::default());
+        check_rect_outlines(&AntiAliasingRender::default());
+    }
+
+    #[test]
+    fn rounded_rects_have_their_own_corners() {
+        let red = image::Rgba([255, 0, 0, 255]);
+        let clear = image::Rgba([0, 0, 0, 0]);
+        let rounded = RoundedRect::with_radii(
+            Rect::new(2f64, 2f64, 36f64, 26f64),
+            [
+                CornerRadius::from(10f64),
+                CornerRadius::from(0f64),
+                CornerRadius::new(20f64, 8f64),
+                CornerRadius::from(0f64),
+            ],
+        );
+
+        let mut img = image::RgbaImage::new(40, 30);
+        BasicRenderer::default().fill_rounded_rect(&mut img, rounded, red);
+        assert_eq!(*img.get_pixel(3, 3), clear);
+        assert_eq!(*img.get_pixel(37, 3), red);
+        assert_eq!(*img.get_pixel(36, 26), clear);
+        assert_eq!(*img.get_pixel(24, 26), red);
+        assert_eq!(*img.get_pixel(3, 26), red);
+
+        let mut img = image::RgbaImage::new(40, 30);
+        AntiAliasingRender::default().stroke_rounded_rect(&mut img, rounded, red);
+        assert_eq!(*img.get_pixel(38, 2), red);
+        assert_eq!(img.get_pixel(2, 2)[3], 0);
+        assert_eq!(img.get_pixel(20, 15)[3], 0);
+
+        // Radii too large for the rect make a pill instead of overlapping.
+        let mut img = image::RgbaImage::new(40, 30);
+        BasicRenderer::default().draw_filled_rounded_rect(
+            &mut img,
+            Rect::new(2f64, 10f64, 36f64, 10f64),
+            30f64,
+            red,
+        );
+        assert_eq!(*img.get_pixel(20, 10), red);
+        assert_eq!(*img.get_pixel(3, 10), clear);
+        assert_eq!(*img.get_pixel(2, 15), red);
+        assert_eq!(*img.get_pixel(20, 22), clear);
+
+        // Translucent paint is blended once, seams between sides and corners
+        // included.
+        let translucent = image::Rgba([255, 0, 0, 128]);
+        let rect = Rect::new(2f64, 2f64, 36f64, 26f64);
+
+        let mut img = image::RgbaImage::new(40, 30);
+        BasicRenderer::default().draw_filled_rounded_rect(&mut img, rect, 8f64, translucent);
+        assert!(img.pixels().all(|p| p[3] == 0 || *p == translucent));
+
+        let mut img = image::RgbaImage::new(40, 30);
+        AntiAliasingRender::default().draw_filled_rounded_rect(&mut img, rect, 8f64, translucent);
+        assert!(img.pixels().all(|p| p[3] <= 128));
+        assert_eq!(*img.get_pixel(10, 10), translucent);
+    }
+
+    #[test]
+    fn filled_polygons_follow_fill_rule() {
+        let red = image::Rgba([255, 0, 0, 255]);
+        let clear = image::Rgba([0, 0, 0, 0]);
+
+        let star: Vec<Point> = (0..5)
+            .map(|i| {
+                let a = (i as f64 * 144f64 - 90f64).to_radians();
+                Point::new(20f64 + 18f64 * a.cos(), 20f64 + 18f64 * a.sin())
+            })
+            .collect();
+
+        let mut img = image::RgbaImage::new(40, 40);
+        BasicRenderer::default().draw_filled_polygon(&mut img, &star, FillRule::NonZero, red);
+        assert_eq!(*img.get_pixel(20, 20), red);
+        assert_eq!(*img.get_pixel(20, 5), red);
+
+        let mut img = image::RgbaImage::new(40, 40);
+        BasicRenderer::default().draw_filled_polygon(&mut img, &star, FillRule::EvenOdd, red);
+        assert_eq!(*img.get_pixel(20, 20), clear);
+        assert_eq!(*img.get_pixel(20, 5), red);
+
+        let mut img = image::RgbaImage::new(40, 40);
+        AntiAliasingRender::default().draw_filled_polygon(&mut img, &star, FillRule::EvenOdd, red);
+        assert_eq!(*img.get_pixel(20, 20), clear);
+        assert_eq!(*img.get_pixel(20, 5), red);
+
+        let mut img = image::RgbaImage::new(40, 40);
+        AntiAliasingRender::default().draw_polygon(&mut img, &star, red);
+        assert_eq!(*img.get_pixel(20, 20), clear);
+        assert_ne!(*img.get_pixel(20, 2), clear);
+    }
+
+    #[test]
+    fn ellipses_are_drawn() {
+        let red = image::Rgba([255, 0, 0, 255]);
+        let clear = image::Rgba([0, 0, 0, 0]);
+        let ellipse = Ellipse::new((20f64, 20f64), 15f64, 8f64);
+
+        let mut img = image::RgbaImage::new(40, 40);
+        BasicRenderer::default().draw_ellipse(&mut img, ellipse, red);
+        for (x, y) in [(35, 20), (5, 20), (20, 12), (20, 28)] {
+            assert_eq!(*img.get_pixel(x, y), red);
+        }
+        assert_eq!(*img.get_pixel(20, 20), clear);
+        assert_eq!(*img.get_pixel(20, 9), clear);
+
+        let mut img = image::RgbaImage::new(40, 40);
+        BasicRenderer::default().draw_filled_ellipse(
+            &mut img,
+            ellipse.with_rotation(Angle::Degrees(90f64)),
+            red,
+        );
+        assert_eq!(*img.get_pixel(20, 34), red);
+        assert_eq!(*img.get_pixel(30, 20), clear);
+
+        let mut img = image::RgbaImage::new(40, 40);
+        BasicRenderer::default().draw_elliptical_arc(
+            &mut img,
+            ellipse,
+            Angle::Degrees(0f64),
+            Angle::Degrees(90f64),
+            red,
+        );
+        assert_eq!(*img.get_pixel(35, 20), red);
+        assert_eq!(*img.get_pixel(20, 28), red);
+        assert_eq!(*img.get_pixel(5, 20), clear);
+        assert_eq!(*img.get_pixel(20, 12), clear);
+
+        let mut img = image::RgbaImage::new(40, 40);
+        AntiAliasingRender::default().draw_filled_elliptical_arc(
+            &mut img,
+            ellipse,
+            Angle::Degrees(180f64),
+            Angle::Degrees(270f64),
+            red,
+        );
+        assert_eq!(*img.get_pixel(10, 16), red);
+        assert_eq!(*img.get_pixel(30, 24), clear);
+        let edge = img.get_pixel(15, 12)[3];
+        assert!(edge > 0 && edge < 255);
+
+        let mut img = image::RgbaImage::new(40, 40);
+        AntiAliasingRender::default().draw_ellipse(&mut img, ellipse, red);
+        assert_eq!(*img.get_pixel(35, 20), red);
+        assert_eq!(*img.get_pixel(20, 20), clear);
+    }
+
+    /// Whether anything was drawn in each quadrant around the center of
+    /// `img`, clockwise from the bottom right, away from the axes.
+    fn quadrants(img: &image::GrayImage) -> [bool; 4] {
+        let (cx, cy) = (img.width() as i64 / 2, img.height() as i64 / 2);
+
+        [(1, 1), (-1, 1), (-1, -1), (1, -1)].map(|(sx, sy)| {
+            img.enumerate_pixels().any(|(x, y, p)| {
+                let (dx, dy) = (x as i64 - cx, y as i64 - cy);
+                dx * sx >= 2 && dy * sy >= 2 && p[0] > 0
+            })
+        })
+    }
+
+    fn check_arcs<R>(r: &R)
+    where
+        R: Renderer<Image = image::GrayImage, Pixel = image::Luma<u8>>,
+    {
+        let circle = Circle::new((10f64, 10f64), 8f64);
+        let d = Angle::Degrees;
+        let (cw, ccw) = (ArcDirection::Clockwise, ArcDirection::CounterClockwise);
+        let white = image::Luma([255u8]);
+
+        for ((start, end, direction), expected) in [
+            ((d(300f64), d(30f64), cw), [true, false, false, true]),
+            ((d(270f64), d(90f64), cw), [true, false, false, true]),
+            ((d(90f64), d(270f64), cw), [false, true, true, false]),
+            ((d(270f64), d(90f64), ccw), [false, true, true, false]),
+            ((d(90f64), d(270f64), ccw), [true, false, false, true]),
+            ((d(-90f64), d(90f64), cw), [true, false, false, true]),
+            ((d(90f64), d(180f64), cw), [false, true, false, false]),
+            ((d(180f64), d(270f64), cw), [false, false, true, false]),
+            (
+                (Angle::Radians(-FRAC_PI_2), Angle::Radians(0f64), cw),
+                [false, false, false, true],
+            ),
+            ((d(45f64), d(500f64), cw), [true; 4]),
+            ((d(0f64), d(-360f64), ccw), [true; 4]),
+        ] {
+            let mut img = image::GrayImage::new(21, 21);
+            r.draw_filled_directed_arc(&mut img, circle, start, end, direction, white);
+            assert_eq!(quadrants(&img), expected, "filled {start:?} to {end:?}");
+
+            let mut img = image::GrayImage::new(21, 21);
+            r.draw_directed_arc(&mut img, circle, start, end, direction, white);
+            assert_eq!(quadrants(&img), expected, "{start:?} to {end:?}");
+
+            let ellipse = Ellipse::new((10f64, 10f64), 8f64, 6f64);
+
+            let mut img = image::GrayImage::new(21, 21);
+            r.draw_filled_directed_elliptical_arc(&mut img, ellipse, start, end, direction, white);
+            assert_eq!(quadrants(&img), expected, "elliptical {start:?} to {end:?}");
+
+            let mut img = image::GrayImage::new(21, 21);
+            r.draw_directed_elliptical_arc(&mut img, ellipse, start, end, direction, white);
+            assert_eq!(quadrants(&img), expected, "elliptical {start:?} to {end:?}");
+        }
+    }
+
+    #[test]
+    fn counter_clockwise_arcs_dash_from_their_start() {
+        let white = image::Luma([255u8]);
+        let circle = Circle::new((10f64, 10f64), 8f64);
+        let dashed = Stroke::new(1f64).with_dash(&[4f64, 100f64], 0f64);
+
+        // From the bottom of the circle back to its right side, so the only
+        // dash is at the bottom.
+        let mut img = image::GrayImage::new(21, 21);
+        BasicRenderer::default()
+            .with_stroke(dashed)
+            .draw_directed_arc(
+                &mut img,
+                circle,
+                Angle::Degrees(90f64),
+                Angle::Degrees(0f64),
+                ArcDirection::CounterClockwise,
+                white,
+            );
+        assert_eq!(img.get_pixel(10, 18)[0], 255);
+        assert_eq!(img.get_pixel(18, 10)[0], 0);
+
+        let path = Path::directed_arc(
+            circle,
+            Angle::Degrees(90f64),
+            Angle::Degrees(0f64),
+            ArcDirection::CounterClockwise,
+        );
+        assert_eq!(path.current_point().map(|p| p.y.round()), Some(10f64));
+    }
+
+    #[test]
+    fn arcs_run_clockwise_and_wrap_around() {
+        check_arcs(&BasicRenderer::default());
+        check_arcs(&AntiAliasingRender::default());
+        check_arcs(&CoverageRenderer::default());
+        check_arcs(&SupersampledRenderer::new(BasicRenderer::default(), 4));
+
+        // Scaled unevenly, arcs go through paths instead.
+        let uneven = Transform::scale(1f64, 1.0001f64);
+        check_arcs(&TransformedRenderer::new(BasicRenderer::default()).with_transform(uneven));
+    }
 }
@@ -0,0 +1,201 @@
+use image::{GrayImage, Luma};
+
+use crate::{
+    color::Blending,
+    composite::Composite,
+    paint::{blend_paint, Paint},
+    target::Target,
+};
+
+/// 8-bit coverage image: how much of each pixel a shape covers, from 0 for
+/// none to 255 for all of it.
+///
+/// Renderers draw into a mask like into any image, with `Luma([255])` for
+/// full coverage. Masks combine with each other and can then be painted
+/// through onto an image, pushed onto a [`Clip`] or tested for hits.
+///
+/// [`Clip`]: crate::clip::Clip
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mask {
+    image: GrayImage,
+}
+
+impl Mask {
+    /// An empty mask, covering nothing.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            image: GrayImage::new(width, height),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.image.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.image.height()
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.image.dimensions()
+    }
+
+    /// The fraction of the pixel at `x`, `y` the mask covers, 0 outside it.
+    pub fn coverage(&self, x: i64, y: i64) -> f64 {
+        let (width, height) = self.dimensions();
+
+        if x < 0 || y < 0 || x >= width as i64 || y >= height as i64 {
+            return 0f64;
+        }
+
+        self.image.get_pixel(x as u32, y as u32)[0] as f64 / 255f64
+    }
+
+    /// Whether the mask covers any of the pixel at `x`, `y`.
+    pub fn contains(&self, x: i64, y: i64) -> bool {
+        self.coverage(x, y) > 0f64
+    }
+
+    pub fn as_image(&self) -> &GrayImage {
+        &self.image
+    }
+
+    /// Keeps the coverage both masks share.
+    pub fn intersect(&mut self, other: &Mask) {
+        self.combine(other, |a, b| a * b);
+    }
+
+    /// Adds the coverage of `other`.
+    pub fn union(&mut self, other: &Mask) {
+        self.combine(other, |a, b| a + b - a * b);
+    }
+
+    /// Takes the coverage of `other` away.
+    pub fn subtract(&mut self, other: &Mask) {
+        self.combine(other, |a, b| a * (1f64 - b));
+    }
+
+    /// Covers what the mask doesn't and the other way around.
+    pub fn invert(&mut self) {
+        for Luma([v]) in self.image.pixels_mut() {
+            *v = 255 - *v;
+        }
+    }
+
+    /// Composites `paint` onto `img` as much as the mask covers each pixel,
+    /// lining the mask up with the top left of the image.
+    pub fn fill<'a, T: Target>(
+        &self,
+        img: &mut T,
+        paint: impl Into<Paint<'a, T::Pixel>>,
+        composite: impl Into<Composite>,
+    ) {
+        let paint = paint.into();
+        let blending = Blending {
+            composite: composite.into(),
+            ..Blending::default()
+        };
+
+        for (x, y, Luma([v])) in self.image.enumerate_pixels() {
+            let coverage = *v as f64 / 255f64;
+            blend_paint(img, x as i64, y as i64, &paint, coverage, blending);
+        }
+    }
+
+    /// Sets each pixel to `f` of its coverage and that of `other` at the
+    /// same position, `other` covering nothing outside of it.
+    fn combine(&mut self, other: &Mask, f: impl Fn(f64, f64) -> f64) {
+        for (x, y, Luma([v])) in self.image.enumerate_pixels_mut() {
+            let (a, b) = (*v as f64 / 255f64, other.coverage(x as i64, y as i64));
+            *v = (f(a, b) * 255f64).round().clamp(0f64, 255f64) as u8;
+        }
+    }
+}
+
+impl From<GrayImage> for Mask {
+    fn from(image: GrayImage) -> Self {
+        Self { image }
+    }
+}
+
+impl From<Mask> for GrayImage {
+    fn from(mask: Mask) -> Self {
+        mask.image
+    }
+}
+
+impl Target for Mask {
+    type Pixel = Luma<u8>;
+
+    fn size(&self) -> (u32, u32) {
+        self.dimensions()
+    }
+
+    fn blend(&mut self, x: u32, y: u32, color: Luma<u8>, coverage: f64, composite: Composite) {
+        self.image.blend(x, y, color, coverage, composite);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use crate::{antialiased::AntiAliasingRender, composite::CompositeOp, types::Circle, Renderer};
+
+    use super::*;
+
+    const FULL: Luma<u8> = Luma([255]);
+
+    #[test]
+    fn masks_combine() {
+        let columns = |from, to| {
+            Mask::from(GrayImage::from_fn(10, 4, |x, _| {
+                Luma([if (from..to).contains(&x) { 255 } else { 0 }])
+            }))
+        };
+        let (left, right) = (columns(0, 6), columns(4, 10));
+
+        let mut both = left.clone();
+        both.intersect(&right);
+        assert_eq!((0..10).filter(|&x| both.contains(x, 1)).count(), 2);
+
+        let mut either = left.clone();
+        either.union(&right);
+        assert_eq!((0..10).filter(|&x| either.contains(x, 1)).count(), 10);
+
+        let mut only = left.clone();
+        only.subtract(&right);
+        assert_eq!((0..10).filter(|&x| only.contains(x, 1)).count(), 4);
+
+        only.invert();
+        assert_eq!((0..10).filter(|&x| only.contains(x, 1)).count(), 6);
+        assert!(!only.contains(10, 1));
+
+        let mut half = Mask::from(GrayImage::from_pixel(10, 4, Luma([128])));
+        half.intersect(&left);
+        assert_eq!(half.as_image().get_pixel(2, 1)[0], 128);
+        assert_eq!(half.as_image().get_pixel(8, 1)[0], 0);
+    }
+
+    #[test]
+    fn paints_through_masks() {
+        let mut mask = Mask::new(12, 12);
+        AntiAliasingRender::default().draw_filled_circle(
+            &mut mask,
+            Circle::new((6f64, 6f64), 4.4f64),
+            FULL,
+        );
+
+        let red = Rgba([255, 0, 0, 255]);
+        let mut img = image::RgbaImage::from_pixel(12, 12, Rgba([0, 0, 255, 255]));
+        mask.fill(&mut img, red, CompositeOp::SourceOver);
+
+        assert_eq!(*img.get_pixel(6, 6), red);
+        assert_eq!(*img.get_pixel(0, 0), Rgba([0, 0, 255, 255]));
+
+        let edge = img.get_pixel(11, 6);
+        let coverage = mask.coverage(11, 6);
+        assert!(coverage > 0f64 && coverage < 1f64);
+        assert_eq!(edge[0], (255f64 * coverage).round() as u8);
+    }
+}
@@ -0,0 +1,385 @@
+use std::{fs, path::Path as FsPath, sync::Arc};
+
+use owned_ttf_parser::{
+    gpos::{PairAdjustment, PositioningSubtable},
+    AsFaceRef, Face, GlyphId, OutlineBuilder, OwnedFace, Tag,
+};
+
+use crate::{
+    error::MAX_COORDINATE,
+    geom::TOLERANCE,
+    path::Path,
+    raster,
+    text::{Font, FontError, FontErrorKind},
+    types::{FillRule, Point},
+};
+
+/// TrueType or OpenType font, drawn from its glyph outlines at any size.
+///
+/// Outlines made of quadratic (TrueType) and cubic (CFF) curves are both
+/// supported and drawn anti-aliased, with the advance widths and pair
+/// kerning the font defines. Characters it has no glyph for are drawn as
+/// its missing glyph, usually a box. There is no hinting or shaping beyond
+/// kerning.
+#[derive(Debug, Clone)]
+pub struct OutlineFont {
+    face: Arc<OwnedFace>,
+    size: f64,
+}
+
+impl OutlineFont {
+    /// Loads the font in `data`, at 16 pixels per em.
+    pub fn from_bytes(data: impl Into<Vec<u8>>) -> Result<Self, FontError> {
+        Self::from_collection(data, 0)
+    }
+
+    /// Loads font `index` of the font collection in `data`.
+    pub fn from_collection(data: impl Into<Vec<u8>>, index: u32) -> Result<Self, FontError> {
+        let face = OwnedFace::from_vec(data.into(), index)
+            .map_err(|_| FontError::new(0, FontErrorKind::Malformed))?;
+
+        Ok(Self {
+            face: Arc::new(face),
+            size: 16f64,
+        })
+    }
+
+    pub fn from_file(path: impl AsRef<FsPath>) -> Result<Self, FontError> {
+        Self::from_bytes(fs::read(path)?)
+    }
+
+    /// The same font drawn `size` pixels per em. Sizes that aren't positive
+    /// and within [`MAX_COORDINATE`] are ignored.
+    pub fn with_size(mut self, size: f64) -> Self {
+        if size > 0f64 && size <= MAX_COORDINATE {
+            self.size = size;
+        }
+        self
+    }
+
+    /// Pixels per em.
+    pub fn size(&self) -> f64 {
+        self.size
+    }
+
+    /// The outline of `c`, in pixels from the pen position on the baseline
+    /// with y growing downwards, or `None` if the font has no glyph for it.
+    pub fn outline(&self, c: char) -> Option<Path> {
+        let face = self.face();
+        let mut builder = PathBuilder::new(self.scale(face), Point::new(0f64, 0f64));
+
+        face.outline_glyph(face.glyph_index(c)?, &mut builder);
+        Some(builder.path)
+    }
+
+    fn face(&self) -> &Face<'_> {
+        self.face.as_face_ref()
+    }
+
+    /// Pixels per font unit.
+    fn scale(&self, face: &Face) -> f64 {
+        self.size / face.units_per_em() as f64
+    }
+}
+
+impl Font for OutlineFont {
+    fn ascent(&self) -> f64 {
+        let face = self.face();
+        face.ascender() as f64 * self.scale(face)
+    }
+
+    fn descent(&self) -> f64 {
+        let face = self.face();
+        -face.descender() as f64 * self.scale(face)
+    }
+
+    /// The ascent and descent, plus the line gap the font asks for.
+    fn line_height(&self) -> f64 {
+        let face = self.face();
+        let height = face.ascender() as f64 - face.descender() as f64 + face.line_gap() as f64;
+        height * self.scale(face)
+    }
+
+    fn has_glyph(&self, c: char) -> bool {
+        self.face().glyph_index(c).is_some()
+    }
+
+    fn advance(&self, c: char) -> f64 {
+        let face = self.face();
+        advance(face, glyph(face, c)) * self.scale(face)
+    }
+
+    fn kerning(&self, left: char, right: char) -> f64 {
+        let face = self.face();
+        kerning(face, glyph(face, left), glyph(face, right)) * self.scale(face)
+    }
+
+    fn measure(&self, text: &str) -> (f64, f64) {
+        let face = self.face();
+        let mut width = 0f64;
+        let mut previous = None;
+
+        for id in text.chars().map(|c| glyph(face, c)) {
+            if let Some(previous) = previous {
+                width += kerning(face, previous, id);
+            }
+
+            width += advance(face, id);
+            previous = Some(id);
+        }
+
+        (width * self.scale(face), self.line_height())
+    }
+
+    fn rasterize(
+        &self,
+        text: &str,
+        origin: Point,
+        size: (u32, u32),
+        plot: &mut dyn FnMut(i64, i64, f64),
+    ) {
+        let face = self.face();
+        let scale = self.scale(face);
+
+        // Glyphs start at the left edge of the pixel the origin is in, on
+        // its top edge.
+        let mut pen = Point::new(origin.x - 0.5f64, origin.y - 0.5f64);
+        let mut previous = None;
+        let mut builder = PathBuilder::new(scale, pen);
+
+        for id in text.chars().map(|c| glyph(face, c)) {
+            if let Some(previous) = previous {
+                pen.x += kerning(face, previous, id) * scale;
+            }
+
+            builder.origin = pen;
+            face.outline_glyph(id, &mut builder);
+
+            pen.x += advance(face, id) * scale;
+            previous = Some(id);
+        }
+
+        let polygons: Vec<_> = builder
+            .path
+            .flatten(TOLERANCE)
+            .into_iter()
+            .map(|(p, _)| p)
+            .collect();
+
+        raster::accumulate(&polygons, FillRule::NonZero, size, plot);
+    }
+}
+
+/// The glyph drawn for `c`, the missing glyph if there's none.
+fn glyph(face: &Face, c: char) -> GlyphId {
+    face.glyph_index(c).unwrap_or(GlyphId(0))
+}
+
+fn advance(face: &Face, id: GlyphId) -> f64 {
+    face.glyph_hor_advance(id).unwrap_or(0) as f64
+}
+
+/// The adjustment to the advance of `left` when `right` follows it, in font
+/// units, from the OpenType `kern` feature or else the legacy `kern` table.
+fn kerning(face: &Face, left: GlyphId, right: GlyphId) -> f64 {
+    if let Some(gpos) = face.tables().gpos {
+        let lookups = gpos
+            .features
+            .into_iter()
+            .filter(|feature| feature.tag == Tag::from_bytes(b"kern"))
+            .flat_map(|feature| feature.lookup_indices)
+            .filter_map(|index| gpos.lookups.get(index));
+
+        for lookup in lookups {
+            for subtable in lookup.subtables.into_iter::<PositioningSubtable>() {
+                let PositioningSubtable::Pair(pair) = subtable else {
+                    continue;
+                };
+
+                let values = match pair {
+                    PairAdjustment::Format1 { coverage, sets } => coverage
+                        .get(left)
+                        .and_then(|index| sets.get(index))
+                        .and_then(|set| set.get(right)),
+                    PairAdjustment::Format2 {
+                        coverage,
+                        classes,
+                        matrix,
+                    } if coverage.contains(left) => {
+                        matrix.get((classes.0.get(left), classes.1.get(right)))
+                    }
+                    PairAdjustment::Format2 { .. } => None,
+                };
+
+                // The first subtable with the pair decides.
+                if let Some((first, _)) = values {
+                    return first.x_advance as f64;
+                }
+            }
+        }
+    }
+
+    face.tables()
+        .kern
+        .into_iter()
+        .flat_map(|kern| kern.subtables)
+        .filter(|subtable| subtable.horizontal && !subtable.variable)
+        .filter_map(|subtable| subtable.glyphs_kerning(left, right))
+        .map(|kerning| kerning as f64)
+        .sum()
+}
+
+/// Collects glyph outlines into a path, scaled from font units and flipped
+/// to grow downwards from `origin`.
+struct PathBuilder {
+    scale: f64,
+    origin: Point,
+    path: Path,
+}
+
+impl PathBuilder {
+    fn new(scale: f64, origin: Point) -> Self {
+        Self {
+            scale,
+            origin,
+            path: Path::new(),
+        }
+    }
+
+    fn point(&self, x: f32, y: f32) -> Point {
+        Point::new(
+            self.origin.x + x as f64 * self.scale,
+            self.origin.y - y as f64 * self.scale,
+        )
+    }
+}
+
+impl OutlineBuilder for PathBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        let p = self.point(x, y);
+        self.path.move_to(p);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let p = self.point(x, y);
+        self.path.line_to(p);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (control, to) = (self.point(x1, y1), self.point(x, y));
+        self.path.quad_to(control, to);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (a, b, to) = (self.point(x1, y1), self.point(x2, y2), self.point(x, y));
+        self.path.cubic_to(a, b, to);
+    }
+
+    fn close(&mut self) {
+        self.path.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, io, path::PathBuf};
+
+    use image::{GrayImage, Luma};
+
+    use crate::text::{draw_text, TextAlign, TextStyle};
+
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    /// Compares `img` with the golden image `name`, rewriting it instead when
+    /// `BLESS` is set.
+    fn assert_golden(img: &GrayImage, name: &str) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden")
+            .join(name);
+
+        if env::var_os("BLESS").is_some() {
+            img.save(&path).unwrap();
+        }
+
+        let golden = image::open(&path).unwrap().to_luma8();
+        assert_eq!(golden.dimensions(), img.dimensions());
+
+        for (a, b) in golden.pixels().zip(img.pixels()) {
+            assert!((a[0] as i16 - b[0] as i16).abs() <= 1, "{} differs", name);
+        }
+    }
+
+    #[test]
+    fn glyphs_have_outlines_advances_and_kerning() {
+        let font = OutlineFont::from_file(fixture("DejaVuSerif.ttf"))
+            .unwrap()
+            .with_size(32f64);
+
+        assert!(font.has_glyph('A') && !font.has_glyph('\u{e000}'));
+        assert!(font.kerning('A', 'V') < 0f64);
+        assert_eq!(font.kerning('A', 'B'), 0f64);
+
+        let (width, height) = font.measure("AV");
+        let kerned = font.advance('A') + font.kerning('A', 'V') + font.advance('V');
+        assert!((width - kerned).abs() < 1e-9);
+        assert_eq!(height, font.line_height());
+
+        // The o has curves and sits on the baseline, under the ascent.
+        let o = font.outline('o').unwrap();
+        let (top, bottom) = o.points().fold((0f64, f64::MIN), |(top, bottom), p| {
+            (top.min(p.y), bottom.max(p.y))
+        });
+        assert!(top > -font.ascent() && top < 0f64);
+        assert!(bottom > 0f64 && bottom < 1f64);
+        assert!(font.outline(' ').unwrap().is_empty());
+
+        for size in [0f64, -1f64, f64::NAN, f64::INFINITY] {
+            assert_eq!(font.clone().with_size(size).size(), 32f64);
+        }
+    }
+
+    #[test]
+    fn text_matches_golden_images() {
+        let font = OutlineFont::from_file(fixture("DejaVuSerif.ttf"))
+            .unwrap()
+            .with_size(20f64);
+
+        let mut img = GrayImage::new(140, 28);
+        let style = TextStyle::new(&font);
+        draw_text(
+            &mut img,
+            "Hello, World!",
+            (4f64, 20f64),
+            &style,
+            Luma([255]),
+        );
+        assert_golden(&img, "hello-world.png");
+
+        let font = font.with_size(32f64);
+        let mut img = GrayImage::new(220, 44);
+        let style = TextStyle::new(&font).with_align(TextAlign::Center);
+        draw_text(
+            &mut img,
+            "AVATAR Typo",
+            (110f64, 32f64),
+            &style,
+            Luma([255]),
+        );
+        assert_golden(&img, "kerned-centered.png");
+    }
+
+    #[test]
+    fn bad_fonts_are_errors() {
+        let error = OutlineFont::from_bytes(&b"not a font"[..]).unwrap_err();
+        assert_eq!(error.kind(), &FontErrorKind::Malformed);
+
+        let error = OutlineFont::from_file(fixture("missing.ttf")).unwrap_err();
+        assert_eq!(error.kind(), &FontErrorKind::Io(io::ErrorKind::NotFound));
+    }
+}
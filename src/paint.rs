@@ -0,0 +1,636 @@
+use std::fmt;
+
+use image::{GenericImageView, Pixel};
+
+use crate::{
+    color::{blend_coverage, value, Blending, Channel},
+    geom::pixel,
+    target::Target,
+    transform::Transform,
+    types::{Angle, Point},
+};
+
+/// How a gradient continues before its first and past its last stop, or a
+/// pattern past the edges of its image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Spread {
+    /// Extends the first and last colors (or the edge pixels).
+    #[default]
+    Pad,
+    /// Starts over from the first stop (or the opposite edge).
+    Repeat,
+    /// Runs back and forth through the stops.
+    Reflect,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum GradientShape {
+    /// Stops run along the line from `from` (offset 0) to `to` (offset 1),
+    /// and are constant across it.
+    Linear { from: Point, to: Point },
+    /// Stops run from `center` out to the circle of `radius` around it.
+    Radial { center: Point, radius: f64 },
+    /// Stops run clockwise around `center` over a full turn, starting at the
+    /// angle `start`.
+    Conic { center: Point, start: Angle },
+}
+
+/// Colors interpolated between stops at offsets from 0 to 1, laid out by a
+/// [`GradientShape`] in a space its transform maps onto the image.
+#[derive(Debug, Clone)]
+pub struct Gradient<P> {
+    shape: GradientShape,
+    stops: Vec<(f64, P)>,
+    spread: Spread,
+    transform: Transform,
+    /// The inverse of `transform`, taking the image back to the gradient's
+    /// space, if it has one.
+    inverse: Option<Transform>,
+}
+
+impl<P> Gradient<P> {
+    pub fn new(shape: GradientShape) -> Self {
+        Self {
+            shape,
+            stops: vec![],
+            spread: Spread::default(),
+            transform: Transform::IDENTITY,
+            inverse: Some(Transform::IDENTITY),
+        }
+    }
+
+    pub fn linear(from: impl Into<Point>, to: impl Into<Point>) -> Self {
+        Self::new(GradientShape::Linear {
+            from: from.into(),
+            to: to.into(),
+        })
+    }
+
+    pub fn radial(center: impl Into<Point>, radius: f64) -> Self {
+        Self::new(GradientShape::Radial {
+            center: center.into(),
+            radius,
+        })
+    }
+
+    pub fn conic(center: impl Into<Point>, start: Angle) -> Self {
+        Self::new(GradientShape::Conic {
+            center: center.into(),
+            start,
+        })
+    }
+
+    /// Adds a stop at `offset`, clamped to `0..=1`. Stops sharing an offset
+    /// switch colors abruptly there, in the order they were added.
+    pub fn with_stop(mut self, offset: f64, color: P) -> Self {
+        let offset = if offset.is_nan() {
+            0f64
+        } else {
+            offset.clamp(0f64, 1f64)
+        };
+
+        let i = self.stops.partition_point(|(o, _)| *o <= offset);
+        self.stops.insert(i, (offset, color));
+        self
+    }
+
+    pub fn with_spread(mut self, spread: Spread) -> Self {
+        self.spread = spread;
+        self
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self.inverse = transform.invert();
+        self
+    }
+
+    pub fn shape(&self) -> GradientShape {
+        self.shape
+    }
+
+    pub fn stops(&self) -> &[(f64, P)] {
+        &self.stops
+    }
+
+    pub fn spread(&self) -> Spread {
+        self.spread
+    }
+
+    pub fn transform(&self) -> Transform {
+        self.transform
+    }
+
+    /// Where `p` falls along the gradient, before spreading.
+    fn offset(&self, p: Point) -> f64 {
+        match self.shape {
+            GradientShape::Linear { from, to } => {
+                let (dx, dy) = (to.x - from.x, to.y - from.y);
+                let length_squared = dx * dx + dy * dy;
+
+                if length_squared > 0f64 {
+                    ((p.x - from.x) * dx + (p.y - from.y) * dy) / length_squared
+                } else {
+                    1f64
+                }
+            }
+            GradientShape::Radial { center, radius } => {
+                if radius > 0f64 {
+                    (p.x - center.x).hypot(p.y - center.y) / radius
+                } else {
+                    1f64
+                }
+            }
+            GradientShape::Conic { center, start } => {
+                let angle = (p.y - center.y).atan2(p.x - center.x);
+                let turns = (angle - start.to_radians()) / std::f64::consts::TAU;
+
+                turns - turns.floor()
+            }
+        }
+    }
+}
+
+impl<P: Pixel> Gradient<P>
+where
+    P::Subpixel: Channel,
+{
+    /// The color at `p`, or `None` without any stops or where the transform
+    /// can't be undone.
+    pub fn color_at(&self, p: Point) -> Option<P> {
+        let t = self.offset(self.inverse?.apply(p));
+
+        let t = match self.spread {
+            Spread::Pad => t.clamp(0f64, 1f64),
+            Spread::Repeat => t - t.floor(),
+            Spread::Reflect => {
+                let t = t.rem_euclid(2f64);
+                if t > 1f64 {
+                    2f64 - t
+                } else {
+                    t
+                }
+            }
+        };
+
+        let i = self.stops.partition_point(|(o, _)| *o <= t);
+
+        match (i.checked_sub(1).map(|i| &self.stops[i]), self.stops.get(i)) {
+            (None, None) => None,
+            (Some((_, color)), None) | (None, Some((_, color))) => Some(*color),
+            (Some(&(from, a)), Some(&(to, b))) => Some(lerp(a, b, (t - from) / (to - from))),
+        }
+    }
+}
+
+/// How a [`Pattern`] picks colors between the pixels of its image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Sampling {
+    /// Takes the closest pixel.
+    #[default]
+    Nearest,
+    /// Interpolates between the four closest pixels.
+    Bilinear,
+}
+
+/// The pixels of an image a [`Pattern`] reads, without the rest of
+/// [`GenericImageView`], which can't be made into an object.
+trait Source<P> {
+    fn dimensions(&self) -> (u32, u32);
+    fn get_pixel(&self, x: u32, y: u32) -> P;
+}
+
+impl<V: GenericImageView> Source<V::Pixel> for V {
+    fn dimensions(&self) -> (u32, u32) {
+        GenericImageView::dimensions(self)
+    }
+
+    fn get_pixel(&self, x: u32, y: u32) -> V::Pixel {
+        GenericImageView::get_pixel(self, x, y)
+    }
+}
+
+/// An image laid out with its top left pixel centered on `offset` and every
+/// pixel `scale` pixels wide, in a space its transform maps onto the image,
+/// continued past its edges according to its [`Spread`], repeating by
+/// default.
+#[derive(Clone, Copy)]
+pub struct Pattern<'a, P> {
+    image: &'a dyn Source<P>,
+    offset: Point,
+    scale: f64,
+    spread: Spread,
+    sampling: Sampling,
+    transform: Transform,
+    /// The inverse of `transform`, taking the image back to the pattern's
+    /// space, if it has one.
+    inverse: Option<Transform>,
+}
+
+impl<'a, P> Pattern<'a, P> {
+    pub fn new<V: GenericImageView<Pixel = P>>(image: &'a V) -> Self {
+        Self {
+            image,
+            offset: Point::new(0f64, 0f64),
+            scale: 1f64,
+            spread: Spread::Repeat,
+            sampling: Sampling::default(),
+            transform: Transform::IDENTITY,
+            inverse: Some(Transform::IDENTITY),
+        }
+    }
+
+    pub fn with_offset(mut self, offset: impl Into<Point>) -> Self {
+        self.offset = offset.into();
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_spread(mut self, spread: Spread) -> Self {
+        self.spread = spread;
+        self
+    }
+
+    pub fn with_sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self.inverse = transform.invert();
+        self
+    }
+
+    pub fn offset(&self) -> Point {
+        self.offset
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    pub fn spread(&self) -> Spread {
+        self.spread
+    }
+
+    pub fn sampling(&self) -> Sampling {
+        self.sampling
+    }
+
+    pub fn transform(&self) -> Transform {
+        self.transform
+    }
+
+    /// The pixel at `x`, `y` of the image, continued past its edges.
+    fn texel(&self, x: i64, y: i64) -> P {
+        let (width, height) = self.image.dimensions();
+
+        let x = spread_index(self.spread, x, width as i64);
+        let y = spread_index(self.spread, y, height as i64);
+
+        self.image.get_pixel(x as u32, y as u32)
+    }
+}
+
+impl<P: Pixel> Pattern<'_, P>
+where
+    P::Subpixel: Channel,
+{
+    /// The color at `p`, or `None` for an empty image, a scale that isn't
+    /// positive or where the transform can't be undone.
+    pub fn color_at(&self, p: Point) -> Option<P> {
+        let (width, height) = self.image.dimensions();
+
+        if width == 0 || height == 0 || self.scale.is_nan() || self.scale <= 0f64 {
+            return None;
+        }
+
+        let p = self.inverse?.apply(p);
+
+        let u = (p.x - self.offset.x) / self.scale;
+        let v = (p.y - self.offset.y) / self.scale;
+
+        match self.sampling {
+            Sampling::Nearest => Some(self.texel(pixel(u), pixel(v))),
+            Sampling::Bilinear => {
+                let (x, y) = (u.floor(), v.floor());
+                let (fx, fy) = (u - x, v - y);
+                let (x, y) = (x as i64, y as i64);
+
+                let top = lerp(self.texel(x, y), self.texel(x + 1, y), fx);
+                let bottom = lerp(self.texel(x, y + 1), self.texel(x + 1, y + 1), fx);
+
+                Some(lerp(top, bottom, fy))
+            }
+        }
+    }
+}
+
+impl<P> fmt::Debug for Pattern<'_, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pattern")
+            .field("dimensions", &self.image.dimensions())
+            .field("offset", &self.offset)
+            .field("scale", &self.scale)
+            .field("spread", &self.spread)
+            .field("sampling", &self.sampling)
+            .field("transform", &self.transform)
+            .finish()
+    }
+}
+
+/// Maps the index `i` into `0..n` according to `spread`.
+fn spread_index(spread: Spread, i: i64, n: i64) -> i64 {
+    match spread {
+        Spread::Pad => i.clamp(0, n - 1),
+        Spread::Repeat => i.rem_euclid(n),
+        Spread::Reflect => {
+            let i = i.rem_euclid(2 * n);
+            if i >= n {
+                2 * n - 1 - i
+            } else {
+                i
+            }
+        }
+    }
+}
+
+fn lerp<P: Pixel>(a: P, b: P, f: f64) -> P
+where
+    P::Subpixel: Channel,
+{
+    a.map2(&b, |a, b| {
+        Channel::from_f64(value(a) + (value(b) - value(a)) * f)
+    })
+}
+
+/// What fills are painted with: a solid color, a gradient or a pattern.
+#[derive(Debug, Clone)]
+pub enum Paint<'a, P> {
+    Solid(P),
+    Gradient(Gradient<P>),
+    Pattern(Pattern<'a, P>),
+}
+
+impl<P> From<P> for Paint<'_, P> {
+    fn from(color: P) -> Self {
+        Paint::Solid(color)
+    }
+}
+
+impl<P> From<Gradient<P>> for Paint<'_, P> {
+    fn from(gradient: Gradient<P>) -> Self {
+        Paint::Gradient(gradient)
+    }
+}
+
+impl<'a, P> From<Pattern<'a, P>> for Paint<'a, P> {
+    fn from(pattern: Pattern<'a, P>) -> Self {
+        Paint::Pattern(pattern)
+    }
+}
+
+impl<P> Paint<'_, P> {
+    /// The same paint with `transform` applied after its own.
+    pub fn transformed(self, transform: Transform) -> Self {
+        match self {
+            Paint::Solid(color) => Paint::Solid(color),
+            Paint::Gradient(gradient) => {
+                let t = gradient.transform.then(transform);
+                Paint::Gradient(gradient.with_transform(t))
+            }
+            Paint::Pattern(pattern) => {
+                let t = pattern.transform.then(transform);
+                Paint::Pattern(pattern.with_transform(t))
+            }
+        }
+    }
+}
+
+impl<P: Pixel> Paint<'_, P>
+where
+    P::Subpixel: Channel,
+{
+    /// The color at `p`, or `None` where nothing is painted.
+    pub fn color_at(&self, p: Point) -> Option<P> {
+        match self {
+            Paint::Solid(color) => Some(*color),
+            Paint::Gradient(gradient) => gradient.color_at(p),
+            Paint::Pattern(pattern) => pattern.color_at(p),
+        }
+    }
+}
+
+/// Composites the color of `paint` at the center of the pixel `x`, `y` onto
+/// it as if it covered `coverage` of its area.
+pub(crate) fn blend_paint<I>(
+    img: &mut I,
+    x: i64,
+    y: i64,
+    paint: &Paint<I::Pixel>,
+    coverage: f64,
+    blending: Blending,
+) where
+    I: Target,
+{
+    if let Some(color) = paint.color_at(Point::new(x as f64, y as f64)) {
+        blend_coverage(img, x, y, color, coverage, blending);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{ImageBuffer, Luma, Rgba};
+
+    use crate::{
+        antialiased::AntiAliasingRender,
+        basic::BasicRenderer,
+        coverage::CoverageRenderer,
+        path::Path,
+        types::{Circle, FillRule, Rect},
+        Renderer,
+    };
+
+    use super::*;
+
+    #[test]
+    fn linear_gradients_interpolate_between_stops() {
+        let gradient = Gradient::linear((0f64, 0f64), (10f64, 0f64))
+            .with_stop(1f64, Luma([200u8]))
+            .with_stop(0f64, Luma([0u8]))
+            .with_stop(0.5f64, Luma([100u8]));
+
+        assert_eq!(
+            gradient.color_at(Point::new(2.5f64, 7f64)),
+            Some(Luma([50]))
+        );
+        assert_eq!(
+            gradient.color_at(Point::new(7.5f64, 0f64)),
+            Some(Luma([150]))
+        );
+        assert_eq!(gradient.color_at(Point::new(-5f64, 0f64)), Some(Luma([0])));
+        assert_eq!(
+            gradient.color_at(Point::new(15f64, 0f64)),
+            Some(Luma([200]))
+        );
+
+        let hard = Gradient::linear((0f64, 0f64), (10f64, 0f64))
+            .with_stop(0.5f64, Rgba([255u8, 0, 0, 255]))
+            .with_stop(0.5f64, Rgba([0u8, 0, 255, 255]));
+        assert_eq!(
+            hard.color_at(Point::new(4f64, 0f64)),
+            Some(Rgba([255, 0, 0, 255]))
+        );
+        assert_eq!(
+            hard.color_at(Point::new(6f64, 0f64)),
+            Some(Rgba([0, 0, 255, 255]))
+        );
+
+        let empty: Gradient<Luma<u8>> = Gradient::linear((0f64, 0f64), (1f64, 0f64));
+        assert_eq!(empty.color_at(Point::new(0f64, 0f64)), None);
+    }
+
+    #[test]
+    fn spread_modes_continue_the_gradient() {
+        let gradient = Gradient::radial((0f64, 0f64), 10f64)
+            .with_stop(0f64, Luma([0u8]))
+            .with_stop(1f64, Luma([100u8]));
+        let at = |g: &Gradient<Luma<u8>>, d: f64| g.color_at(Point::new(0f64, d)).unwrap()[0];
+
+        assert_eq!(at(&gradient, 13f64), 100);
+        assert_eq!(at(&gradient.clone().with_spread(Spread::Repeat), 13f64), 30);
+        assert_eq!(
+            at(&gradient.clone().with_spread(Spread::Reflect), 13f64),
+            70
+        );
+        assert_eq!(at(&gradient.with_spread(Spread::Reflect), 23f64), 30);
+    }
+
+    #[test]
+    fn conic_gradients_sweep_clockwise() {
+        let gradient = Gradient::conic((0f64, 0f64), Angle::Degrees(90f64))
+            .with_stop(0f64, Luma([0u8]))
+            .with_stop(1f64, Luma([240u8]));
+        let at = |x: f64, y: f64| gradient.color_at(Point::new(x, y)).unwrap()[0];
+
+        assert_eq!(at(0f64, 5f64), 0);
+        assert_eq!(at(-5f64, 0f64), 60);
+        assert_eq!(at(0f64, -5f64), 120);
+        assert_eq!(at(5f64, 0f64), 180);
+    }
+
+    #[test]
+    fn patterns_sample_their_image() {
+        let image = ImageBuffer::from_fn(2, 2, |x, y| Luma([(x * 100 + y * 10) as u8]));
+
+        let pattern = Pattern::new(&image)
+            .with_offset((10f64, 10f64))
+            .with_scale(2f64);
+        assert_eq!(pattern.color_at(Point::new(10f64, 10f64)), Some(Luma([0])));
+        assert_eq!(
+            pattern.color_at(Point::new(12f64, 10.9f64)),
+            Some(Luma([100]))
+        );
+        assert_eq!(pattern.color_at(Point::new(14f64, 12f64)), Some(Luma([10])));
+        assert_eq!(pattern.color_at(Point::new(8f64, 10f64)), Some(Luma([100])));
+
+        let pattern = pattern.with_spread(Spread::Pad);
+        assert_eq!(
+            pattern.color_at(Point::new(-20f64, 30f64)),
+            Some(Luma([10]))
+        );
+
+        let pattern = Pattern::new(&image).with_spread(Spread::Reflect);
+        assert_eq!(pattern.color_at(Point::new(2f64, 2f64)), Some(Luma([110])));
+        assert_eq!(pattern.color_at(Point::new(-1f64, -2f64)), Some(Luma([10])));
+
+        let pattern = pattern.with_sampling(Sampling::Bilinear);
+        assert_eq!(
+            pattern.color_at(Point::new(0.5f64, 0.5f64)),
+            Some(Luma([55]))
+        );
+        assert_eq!(
+            pattern.color_at(Point::new(0.25f64, 0f64)),
+            Some(Luma([25]))
+        );
+
+        let empty = ImageBuffer::<Luma<u8>, _>::new(0, 0);
+        assert_eq!(Pattern::new(&empty).color_at(Point::new(0f64, 0f64)), None);
+    }
+
+    #[test]
+    fn gradients_fill_shapes() {
+        let black = image::Luma([0u8]);
+        let white = image::Luma([200u8]);
+
+        let linear = Gradient::linear((0f64, 0f64), (10f64, 0f64))
+            .with_stop(0f64, black)
+            .with_stop(1f64, white);
+
+        let mut img = image::GrayImage::new(12, 4);
+        BasicRenderer::default().draw_filled_rect(
+            &mut img,
+            Rect::new(0f64, 0f64, 10f64, 3f64),
+            linear.clone(),
+        );
+        assert_eq!(*img.get_pixel(0, 1), black);
+        assert_eq!(*img.get_pixel(5, 1), image::Luma([100]));
+        assert_eq!(*img.get_pixel(10, 3), white);
+        assert_eq!(*img.get_pixel(11, 1), black);
+
+        let mut img = image::GrayImage::new(12, 4);
+        let path = Path::rect(Rect::new(-0.5f64, -0.5f64, 11f64, 4f64));
+        CoverageRenderer::default().fill_path(&mut img, &path, FillRule::NonZero, linear);
+        assert_eq!(*img.get_pixel(5, 2), image::Luma([100]));
+        assert_eq!(*img.get_pixel(8, 0), image::Luma([160]));
+
+        let radial = Gradient::radial((10f64, 10f64), 8f64)
+            .with_stop(0f64, image::Rgba([255, 0, 0, 255]))
+            .with_stop(1f64, image::Rgba([0, 0, 255, 255]));
+
+        let mut img = image::RgbaImage::new(20, 20);
+        AntiAliasingRender::default().draw_filled_circle(
+            &mut img,
+            Circle::new((10f64, 10f64), 8f64),
+            radial,
+        );
+        assert_eq!(*img.get_pixel(10, 10), image::Rgba([255, 0, 0, 255]));
+        assert_eq!(*img.get_pixel(14, 10), image::Rgba([128, 0, 128, 255]));
+        assert_eq!(*img.get_pixel(0, 0), image::Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn patterns_fill_shapes() {
+        let photo =
+            image::RgbImage::from_fn(8, 8, |x, y| image::Rgb([x as u8 * 30, y as u8 * 30, 0]));
+
+        let mut img = image::RgbImage::new(20, 20);
+        let avatar = Pattern::new(&photo)
+            .with_offset((6f64, 6f64))
+            .with_scale(1f64);
+        BasicRenderer::default().draw_filled_circle(
+            &mut img,
+            Circle::new((10f64, 10f64), 4f64),
+            avatar,
+        );
+        assert_eq!(*img.get_pixel(10, 10), image::Rgb([120, 120, 0]));
+        assert_eq!(*img.get_pixel(12, 9), image::Rgb([180, 90, 0]));
+        assert_eq!(*img.get_pixel(6, 6), image::Rgb([0, 0, 0]));
+
+        let mut img = image::RgbImage::new(20, 20);
+        let avatar = avatar
+            .with_sampling(Sampling::Bilinear)
+            .with_offset((6.5f64, 6f64));
+        CoverageRenderer::default().draw_filled_circle(
+            &mut img,
+            Circle::new((10f64, 10f64), 4f64),
+            avatar,
+        );
+        assert_eq!(*img.get_pixel(10, 10), image::Rgb([105, 120, 0]));
+    }
+}
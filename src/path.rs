@@ -0,0 +1,751 @@
+use std::f64::consts::{FRAC_PI_2, PI};
+
+use crate::{
+    transform::Transform,
+    types::{Angle, ArcDirection, Circle, Ellipse, Point, Rect, RoundedRect},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathEl {
+    MoveTo(Point),
+    LineTo(Point),
+    QuadTo(Point, Point),
+    CubicTo(Point, Point, Point),
+    Close,
+}
+
+/// A sequence of subpaths made of lines and Bézier curves.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Path {
+    elements: Vec<PathEl>,
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn move_to(&mut self, to: impl Into<Point>) -> &mut Self {
+        self.elements.push(PathEl::MoveTo(to.into()));
+        self
+    }
+
+    pub fn line_to(&mut self, to: impl Into<Point>) -> &mut Self {
+        self.elements.push(PathEl::LineTo(to.into()));
+        self
+    }
+
+    pub fn quad_to(&mut self, control: impl Into<Point>, to: impl Into<Point>) -> &mut Self {
+        self.elements
+            .push(PathEl::QuadTo(control.into(), to.into()));
+        self
+    }
+
+    pub fn cubic_to(
+        &mut self,
+        control1: impl Into<Point>,
+        control2: impl Into<Point>,
+        to: impl Into<Point>,
+    ) -> &mut Self {
+        self.elements
+            .push(PathEl::CubicTo(control1.into(), control2.into(), to.into()));
+        self
+    }
+
+    /// Adds the arc of the circle around `center` from `start` to `end`,
+    /// connected to the current point with a line if there is one.
+    ///
+    /// Unlike the renderers' `draw_arc`, the angles aren't normalized: the arc
+    /// sweeps by `end - start`, so it can go either way and wrap around.
+    pub fn arc_to(
+        &mut self,
+        center: impl Into<Point>,
+        radius: f64,
+        start: Angle,
+        end: Angle,
+    ) -> &mut Self {
+        let center = center.into();
+        let (start, end) = (start.to_radians(), end.to_radians());
+        let at = |t: f64| Point::new(center.x + radius * t.cos(), center.y + radius * t.sin());
+
+        if self.current_point().is_some() {
+            self.line_to(at(start));
+        } else {
+            self.move_to(at(start));
+        }
+
+        self.push_arc(center, (radius, radius), 0f64, start, end - start);
+        self
+    }
+
+    /// Like [`Path::arc_to`], for the arc of `ellipse`. The angles are polar
+    /// angles measured from the ellipse's own x axis.
+    pub fn ellipse_arc_to(&mut self, ellipse: Ellipse, start: Angle, end: Angle) -> &mut Self {
+        let (rx, ry) = (ellipse.radius_x(), ellipse.radius_y());
+        let start = parametric_angle(start.to_radians(), rx, ry);
+        let end = parametric_angle(end.to_radians(), rx, ry);
+
+        let center = ellipse.center();
+        let rotation = ellipse.rotation().to_radians();
+        let (sin, cos) = rotation.sin_cos();
+        let (x, y) = (rx * start.cos(), ry * start.sin());
+        let at = Point::new(center.x + cos * x - sin * y, center.y + sin * x + cos * y);
+
+        if self.current_point().is_some() {
+            self.line_to(at);
+        } else {
+            self.move_to(at);
+        }
+
+        self.push_arc(center, (rx, ry), rotation, start, end - start);
+        self
+    }
+
+    /// Adds an elliptical arc from the current point to `to`, parameterized
+    /// like the SVG `A` command: the ellipse has `radii` and is rotated by
+    /// `x_rotation`, `large_arc` picks the arc spanning more than 180° and
+    /// `sweep` the one drawn in the direction of increasing angles.
+    ///
+    /// Radii too small to reach `to` are scaled up, and zero radii degrade to
+    /// a line.
+    pub fn svg_arc_to(
+        &mut self,
+        radii: (f64, f64),
+        x_rotation: Angle,
+        large_arc: bool,
+        sweep: bool,
+        to: impl Into<Point>,
+    ) -> &mut Self {
+        let to = to.into();
+        let from = self.pen();
+
+        if from == to {
+            return self;
+        }
+
+        let (mut rx, mut ry) = (radii.0.abs(), radii.1.abs());
+        if rx == 0f64 || ry == 0f64 {
+            return self.line_to(to);
+        }
+
+        let phi = x_rotation.to_radians();
+        let (sin, cos) = phi.sin_cos();
+
+        let (dx, dy) = ((from.x - to.x) / 2f64, (from.y - to.y) / 2f64);
+        let (x1, y1) = (cos * dx + sin * dy, -sin * dx + cos * dy);
+
+        let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+        if lambda > 1f64 {
+            rx *= lambda.sqrt();
+            ry *= lambda.sqrt();
+        }
+
+        let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+        let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+        let sign = if large_arc == sweep { -1f64 } else { 1f64 };
+        let coef = sign * (numerator / denominator).max(0f64).sqrt();
+
+        let (cx1, cy1) = (coef * rx * y1 / ry, -coef * ry * x1 / rx);
+        let center = Point::new(
+            cos * cx1 - sin * cy1 + (from.x + to.x) / 2f64,
+            sin * cx1 + cos * cy1 + (from.y + to.y) / 2f64,
+        );
+
+        let start = ((y1 - cy1) / ry).atan2((x1 - cx1) / rx);
+        let end = ((-y1 - cy1) / ry).atan2((-x1 - cx1) / rx);
+
+        let mut delta = end - start;
+        if sweep && delta < 0f64 {
+            delta += 2f64 * PI;
+        } else if !sweep && delta > 0f64 {
+            delta -= 2f64 * PI;
+        }
+
+        self.push_arc(center, (rx, ry), phi, start, delta);
+
+        if let Some(PathEl::CubicTo(_, _, end)) = self.elements.last_mut() {
+            *end = to;
+        }
+
+        self
+    }
+
+    pub fn close(&mut self) -> &mut Self {
+        self.elements.push(PathEl::Close);
+        self
+    }
+
+    pub fn elements(&self) -> &[PathEl] {
+        &self.elements
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// The end of the last element, if the last subpath hasn't been closed.
+    pub fn current_point(&self) -> Option<Point> {
+        match self.elements.last()? {
+            PathEl::MoveTo(p) | PathEl::LineTo(p) | PathEl::QuadTo(_, p) => Some(*p),
+            PathEl::CubicTo(_, _, p) => Some(*p),
+            PathEl::Close => None,
+        }
+    }
+
+    /// Where the next element starts: the end of the last one, or the start
+    /// of the subpath it closed.
+    fn pen(&self) -> Point {
+        match self.current_point() {
+            Some(p) => p,
+            None => self
+                .elements
+                .iter()
+                .rev()
+                .find_map(|el| match el {
+                    PathEl::MoveTo(p) => Some(*p),
+                    _ => None,
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Appends cubics approximating the arc of the ellipse with `radii`
+    /// around `center`, rotated by `rotation` radians, from the parametric
+    /// angle `start` sweeping by `sweep` radians.
+    fn push_arc(
+        &mut self,
+        center: Point,
+        (rx, ry): (f64, f64),
+        rotation: f64,
+        start: f64,
+        sweep: f64,
+    ) {
+        let (sin, cos) = rotation.sin_cos();
+        let map =
+            |x: f64, y: f64| Point::new(center.x + cos * x - sin * y, center.y + sin * x + cos * y);
+
+        let segments = (sweep.abs() / FRAC_PI_2).ceil().max(1f64) as usize;
+        let step = sweep / segments as f64;
+        let k = 4f64 / 3f64 * (step / 4f64).tan();
+
+        for i in 0..segments {
+            let (a, b) = (start + step * i as f64, start + step * (i + 1) as f64);
+            let ((sa, ca), (sb, cb)) = (a.sin_cos(), b.sin_cos());
+
+            self.cubic_to(
+                map(rx * (ca - k * sa), ry * (sa + k * ca)),
+                map(rx * (cb + k * sb), ry * (sb - k * cb)),
+                map(rx * cb, ry * sb),
+            );
+        }
+    }
+
+    pub fn rect(rect: Rect) -> Self {
+        let mut path = Self::new();
+
+        path.move_to((rect.left(), rect.top()))
+            .line_to((rect.right(), rect.top()))
+            .line_to((rect.right(), rect.bottom()))
+            .line_to((rect.left(), rect.bottom()))
+            .close();
+
+        path
+    }
+
+    /// Rounded rectangle starting at the left end of its top side, going
+    /// clockwise. The radius is clamped to half the shorter side.
+    pub fn rounded_rect(rect: Rect, corner_radius: f64) -> Self {
+        RoundedRect::new(rect, corner_radius).into()
+    }
+
+    pub fn circle(circle: Circle) -> Self {
+        let mut path = Self::new();
+
+        path.arc_to(
+            circle.center(),
+            circle.radius(),
+            Angle::Radians(0f64),
+            Angle::Radians(2f64 * PI),
+        )
+        .close();
+
+        path
+    }
+
+    /// Open arc of `circle` sweeping by `end - start` like [`Path::arc_to`].
+    /// Renderers read the angles as a clockwise arc, see [`ArcDirection`].
+    pub fn arc(circle: Circle, start: Angle, end: Angle) -> Self {
+        let mut path = Self::new();
+        path.arc_to(circle.center(), circle.radius(), start, end);
+        path
+    }
+
+    /// Open arc of `circle` running `direction` from `start` to `end`, the
+    /// way renderers read the angles: it wraps around, and is the full circle
+    /// once `end` is a turn or more past `start`. The path starts at `start`
+    /// either way, so dashes do too.
+    pub fn directed_arc(circle: Circle, start: Angle, end: Angle, direction: ArcDirection) -> Self {
+        let end = Angle::Degrees(start.to_degrees() + direction.sweep(start, end));
+        Self::arc(circle, start, end)
+    }
+
+    /// Circle sector closed through the center, sweeping like [`Path::arc`].
+    pub fn pie(circle: Circle, start: Angle, end: Angle) -> Self {
+        let mut path = Self::new();
+
+        path.move_to(circle.center())
+            .arc_to(circle.center(), circle.radius(), start, end)
+            .close();
+
+        path
+    }
+
+    pub fn ellipse(ellipse: Ellipse) -> Self {
+        let mut path = Self::new();
+
+        path.ellipse_arc_to(ellipse, Angle::Radians(0f64), Angle::Radians(2f64 * PI))
+            .close();
+
+        path
+    }
+
+    /// Open arc of `ellipse` sweeping by `end - start`, like [`Path::arc`].
+    pub fn elliptical_arc(ellipse: Ellipse, start: Angle, end: Angle) -> Self {
+        let mut path = Self::new();
+        path.ellipse_arc_to(ellipse, start, end);
+        path
+    }
+
+    /// Like [`Path::directed_arc`], for the arc of `ellipse`.
+    pub fn directed_elliptical_arc(
+        ellipse: Ellipse,
+        start: Angle,
+        end: Angle,
+        direction: ArcDirection,
+    ) -> Self {
+        let end = Angle::Degrees(start.to_degrees() + direction.sweep(start, end));
+        Self::elliptical_arc(ellipse, start, end)
+    }
+
+    /// Ellipse sector closed through the center, sweeping like [`Path::arc`].
+    pub fn elliptical_pie(ellipse: Ellipse, start: Angle, end: Angle) -> Self {
+        let mut path = Self::new();
+
+        path.move_to(ellipse.center())
+            .ellipse_arc_to(ellipse, start, end)
+            .close();
+
+        path
+    }
+
+    pub fn polygon(points: &[Point]) -> Self {
+        let mut path = Self::polyline(points);
+        path.close();
+        path
+    }
+
+    pub fn polyline(points: &[Point]) -> Self {
+        let mut path = Self::new();
+
+        for (i, p) in points.iter().enumerate() {
+            if i == 0 {
+                path.move_to(*p);
+            } else {
+                path.line_to(*p);
+            }
+        }
+
+        path
+    }
+
+    /// The same path with `transform` applied, which maps curves exactly.
+    pub fn transformed(&self, transform: Transform) -> Path {
+        self.map_points(|p| transform.apply(p))
+    }
+
+    /// Every point of the path, control points included. Curves stay within
+    /// the hull of these.
+    pub(crate) fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.elements.iter().flat_map(|el| match *el {
+            PathEl::MoveTo(p) | PathEl::LineTo(p) => vec![p],
+            PathEl::QuadTo(c, p) => vec![c, p],
+            PathEl::CubicTo(c1, c2, p) => vec![c1, c2, p],
+            PathEl::Close => vec![],
+        })
+    }
+
+    /// The same path with every point, control points included, passed
+    /// through `f`.
+    pub(crate) fn map_points<F: Fn(Point) -> Point>(&self, f: F) -> Path {
+        let elements = self
+            .elements
+            .iter()
+            .map(|el| match *el {
+                PathEl::MoveTo(p) => PathEl::MoveTo(f(p)),
+                PathEl::LineTo(p) => PathEl::LineTo(f(p)),
+                PathEl::QuadTo(c, p) => PathEl::QuadTo(f(c), f(p)),
+                PathEl::CubicTo(c1, c2, p) => PathEl::CubicTo(f(c1), f(c2), f(p)),
+                PathEl::Close => PathEl::Close,
+            })
+            .collect();
+
+        Path { elements }
+    }
+
+    /// Approximates every subpath with a polyline that stays within
+    /// `tolerance` of its curves, along with whether the subpath is closed.
+    pub(crate) fn flatten(&self, tolerance: f64) -> Vec<(Vec<Point>, bool)> {
+        let mut subpaths = vec![];
+        let mut current: Vec<Point> = vec![];
+        let mut start = Point::default();
+
+        let mut finish = |current: &mut Vec<Point>, closed: bool| {
+            if !current.is_empty() {
+                subpaths.push((std::mem::take(current), closed));
+            }
+        };
+
+        for el in &self.elements {
+            let last = current.last().copied().unwrap_or(start);
+
+            match *el {
+                PathEl::MoveTo(p) => {
+                    finish(&mut current, false);
+                    start = p;
+                    current.push(p);
+                }
+                PathEl::LineTo(p) => {
+                    if current.is_empty() {
+                        current.push(last);
+                    }
+                    current.push(p);
+                }
+                PathEl::QuadTo(c, p) => {
+                    if current.is_empty() {
+                        current.push(last);
+                    }
+
+                    let dd = (last.x - 2f64 * c.x + p.x).hypot(last.y - 2f64 * c.y + p.y);
+                    let n = segments(dd / 4f64, tolerance);
+
+                    current.extend((1..=n).map(|i| {
+                        let t = i as f64 / n as f64;
+                        let mt = 1f64 - t;
+
+                        Point::new(
+                            mt * mt * last.x + 2f64 * mt * t * c.x + t * t * p.x,
+                            mt * mt * last.y + 2f64 * mt * t * c.y + t * t * p.y,
+                        )
+                    }));
+                }
+                PathEl::CubicTo(c1, c2, p) => {
+                    if current.is_empty() {
+                        current.push(last);
+                    }
+
+                    let dd = (last.x - 2f64 * c1.x + c2.x)
+                        .hypot(last.y - 2f64 * c1.y + c2.y)
+                        .max((c1.x - 2f64 * c2.x + p.x).hypot(c1.y - 2f64 * c2.y + p.y));
+                    let n = segments(dd * 3f64 / 4f64, tolerance);
+
+                    current.extend((1..=n).map(|i| {
+                        let t = i as f64 / n as f64;
+                        let mt = 1f64 - t;
+                        let (a, b, c, d) = (
+                            mt * mt * mt,
+                            3f64 * mt * mt * t,
+                            3f64 * mt * t * t,
+                            t * t * t,
+                        );
+
+                        Point::new(
+                            a * last.x + b * c1.x + c * c2.x + d * p.x,
+                            a * last.y + b * c1.y + c * c2.y + d * p.y,
+                        )
+                    }));
+                }
+                PathEl::Close => {
+                    finish(&mut current, true);
+                }
+            }
+        }
+
+        finish(&mut current, false);
+        subpaths
+    }
+}
+
+impl From<RoundedRect> for Path {
+    /// Starts at the left end of the top side and goes clockwise.
+    fn from(rounded: RoundedRect) -> Self {
+        let rect = rounded.rect();
+        let [top_left, top_right, bottom_right, bottom_left] = rounded.radii();
+        let mut path = Self::new();
+
+        let corner = |path: &mut Self, i: usize, at: Point, center: Point, start: f64| {
+            let r = rounded.radii()[i];
+
+            if rounded.is_square(i) {
+                path.line_to(at);
+            } else {
+                path.ellipse_arc_to(
+                    Ellipse::new(center, r.x, r.y),
+                    Angle::Radians(start),
+                    Angle::Radians(start + FRAC_PI_2),
+                );
+            }
+        };
+
+        let left = if rounded.is_square(0) {
+            0f64
+        } else {
+            top_left.x
+        };
+        path.move_to((rect.left() + left, rect.top()));
+
+        corner(
+            &mut path,
+            1,
+            Point::new(rect.right(), rect.top()),
+            Point::new(rect.right() - top_right.x, rect.top() + top_right.y),
+            1.5f64 * PI,
+        );
+        corner(
+            &mut path,
+            2,
+            Point::new(rect.right(), rect.bottom()),
+            Point::new(
+                rect.right() - bottom_right.x,
+                rect.bottom() - bottom_right.y,
+            ),
+            0f64,
+        );
+        corner(
+            &mut path,
+            3,
+            Point::new(rect.left(), rect.bottom()),
+            Point::new(rect.left() + bottom_left.x, rect.bottom() - bottom_left.y),
+            FRAC_PI_2,
+        );
+
+        // A square top left corner is where the path started.
+        if !rounded.is_square(0) {
+            corner(
+                &mut path,
+                0,
+                Point::new(rect.left(), rect.top()),
+                Point::new(rect.left() + top_left.x, rect.top() + top_left.y),
+                PI,
+            );
+        }
+
+        path.close();
+        path
+    }
+}
+
+/// Parametric angle of the point of an ellipse with radii `rx` and `ry` at
+/// the polar angle `theta`, keeping whole turns so sweeps are preserved.
+fn parametric_angle(theta: f64, rx: f64, ry: f64) -> f64 {
+    let t = (rx * theta.sin()).atan2(ry * theta.cos());
+    let d = t - theta;
+
+    theta + d - 2f64 * PI * (d / (2f64 * PI)).round()
+}
+
+/// Number of uniform steps keeping a curve whose second derivative is
+/// bounded by `8 * deviation` within `tolerance` of its chords (Wang's
+/// formula).
+fn segments(deviation: f64, tolerance: f64) -> usize {
+    (deviation / tolerance).sqrt().ceil().clamp(1f64, 1000f64) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        antialiased::AntiAliasingRender, basic::BasicRenderer, stroke::Stroke, types::FillRule,
+        Renderer,
+    };
+
+    use super::*;
+
+    #[test]
+    fn flatten_splits_subpaths() {
+        let mut path = Path::new();
+        path.move_to((0f64, 0f64))
+            .line_to((10f64, 0f64))
+            .line_to((10f64, 10f64))
+            .close()
+            .move_to((20f64, 20f64))
+            .line_to((30f64, 20f64));
+
+        assert_eq!(
+            path.flatten(0.1f64),
+            vec![
+                (
+                    vec![
+                        Point::new(0f64, 0f64),
+                        Point::new(10f64, 0f64),
+                        Point::new(10f64, 10f64)
+                    ],
+                    true
+                ),
+                (
+                    vec![Point::new(20f64, 20f64), Point::new(30f64, 20f64)],
+                    false
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn flattened_curves_stay_within_tolerance() {
+        let circle = Circle::new((50f64, 50f64), 40f64);
+
+        for tolerance in [1f64, 0.1f64, 0.01f64] {
+            let subpaths = Path::circle(circle).flatten(tolerance);
+            assert_eq!(subpaths.len(), 1);
+
+            let (points, closed) = &subpaths[0];
+            assert!(closed);
+
+            for pair in points.windows(2) {
+                let mid = Point::new(
+                    (pair[0].x + pair[1].x) / 2f64,
+                    (pair[0].y + pair[1].y) / 2f64,
+                );
+                let distance = (mid.x - 50f64).hypot(mid.y - 50f64);
+
+                assert!(40f64 - distance <= tolerance);
+            }
+        }
+
+        let coarse = Path::circle(circle).flatten(1f64)[0].0.len();
+        let fine = Path::circle(circle).flatten(0.01f64)[0].0.len();
+        assert!(fine > coarse);
+    }
+
+    #[test]
+    fn svg_arc_to_finds_the_center() {
+        let mut path = Path::new();
+        path.move_to((0f64, 10f64)).svg_arc_to(
+            (10f64, 10f64),
+            Angle::Degrees(0f64),
+            false,
+            true,
+            (10f64, 0f64),
+        );
+
+        let (points, _) = &path.flatten(0.01f64)[0];
+        for p in points {
+            assert!((p.x - 10f64).hypot(p.y - 10f64) - 10f64 <= 0.01f64);
+            assert!(p.x <= 10f64 + 1e-9 && p.y <= 10f64 + 1e-9);
+        }
+
+        let mut path = Path::new();
+        path.move_to((0f64, 10f64)).svg_arc_to(
+            (10f64, 10f64),
+            Angle::Degrees(0f64),
+            true,
+            true,
+            (10f64, 0f64),
+        );
+
+        let (points, _) = &path.flatten(0.01f64)[0];
+        assert!(points.iter().all(|p| p.x.hypot(p.y) - 10f64 <= 0.01f64));
+        assert!(points.iter().any(|p| p.x < -9.9f64));
+
+        let mut path = Path::new();
+        path.move_to((0f64, 0f64)).svg_arc_to(
+            (1f64, 1f64),
+            Angle::Degrees(30f64),
+            false,
+            false,
+            (20f64, 0f64),
+        );
+
+        let (points, _) = &path.flatten(0.01f64)[0];
+        assert!(points
+            .iter()
+            .all(|p| (p.x - 10f64).hypot(p.y) - 10f64 <= 0.01f64));
+        assert!(points.iter().any(|p| p.y > 9.9f64));
+        assert_eq!(*points.last().unwrap(), Point::new(20f64, 0f64));
+    }
+
+    #[test]
+    fn arc_to_connects_to_current_point() {
+        let mut path = Path::new();
+        path.move_to((0f64, 0f64)).arc_to(
+            (10f64, 10f64),
+            5f64,
+            Angle::Degrees(180f64),
+            Angle::Degrees(90f64),
+        );
+
+        let (points, _) = &path.flatten(0.1f64)[0];
+        assert_eq!(points[1], Point::new(5f64, 10f64));
+
+        let end = *points.last().unwrap();
+        assert!((end.x - 10f64).abs() < 1e-9 && (end.y - 15f64).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ellipse_arcs_use_polar_angles() {
+        let ellipse = Ellipse::new((0f64, 0f64), 20f64, 10f64);
+        let path = Path::elliptical_arc(ellipse, Angle::Degrees(0f64), Angle::Degrees(45f64));
+
+        let end = path.current_point().unwrap();
+        assert!((end.x - end.y).abs() < 1e-9);
+        assert!((end.x / 20f64).powi(2) + (end.y / 10f64).powi(2) - 1f64 < 1e-9);
+
+        let rotated = ellipse.with_rotation(Angle::Degrees(90f64));
+        let path = Path::elliptical_arc(rotated, Angle::Degrees(-90f64), Angle::Degrees(270f64));
+
+        let (points, _) = &path.flatten(0.1f64)[0];
+        let bottom = points.iter().fold(f64::NEG_INFINITY, |m, p| m.max(p.y));
+        let right = points.iter().fold(f64::NEG_INFINITY, |m, p| m.max(p.x));
+        assert!((bottom - 20f64).abs() < 0.1f64);
+        assert!((right - 10f64).abs() < 0.1f64);
+        assert!((points[0].x - 10f64).abs() < 1e-9 && points[0].y.abs() < 1e-9);
+    }
+
+    #[test]
+    fn paths_fill_and_stroke_curves() {
+        let red = image::Rgba([255, 0, 0, 255]);
+        let clear = image::Rgba([0, 0, 0, 0]);
+
+        let mut donut = Path::circle(Circle::new((20f64, 20f64), 15f64));
+        donut
+            .move_to((28f64, 20f64))
+            .arc_to(
+                (20f64, 20f64),
+                8f64,
+                Angle::Degrees(0f64),
+                Angle::Degrees(-360f64),
+            )
+            .close();
+
+        let mut img = image::RgbaImage::new(40, 40);
+        BasicRenderer::default().fill_path(&mut img, &donut, FillRule::NonZero, red);
+        assert_eq!(*img.get_pixel(20, 20), clear);
+        assert_eq!(*img.get_pixel(20, 8), red);
+        assert_eq!(*img.get_pixel(20, 3), clear);
+
+        let mut img = image::RgbaImage::new(40, 40);
+        AntiAliasingRender::default().fill_path(&mut img, &donut, FillRule::EvenOdd, red);
+        assert_eq!(*img.get_pixel(20, 20), clear);
+        assert_eq!(*img.get_pixel(8, 20), red);
+
+        let mut wave = Path::new();
+        wave.move_to((0f64, 20f64))
+            .quad_to((10f64, 0f64), (20f64, 20f64))
+            .cubic_to((25f64, 40f64), (35f64, 40f64), (40f64, 20f64));
+
+        let mut img = image::RgbaImage::new(40, 40);
+        BasicRenderer::default()
+            .with_stroke(Stroke::new(3f64))
+            .stroke_path(&mut img, &wave, red);
+        assert_eq!(*img.get_pixel(10, 10), red);
+        assert_eq!(*img.get_pixel(10, 20), clear);
+    }
+}
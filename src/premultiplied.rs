@@ -0,0 +1,264 @@
+use image::{Pixel, Rgba, RgbaImage};
+
+use crate::{
+    composite::{composite_channels, Composite},
+    target::Target,
+};
+
+/// RGBA image with 16-bit channels premultiplied by alpha, for renderers
+/// to draw into directly.
+///
+/// Plain source-over compositing is done in integer arithmetic on the
+/// premultiplied values, and the extra bits keep the color of nearly
+/// transparent pixels, so drawing many translucent layers over the same
+/// pixels stays fast and accurate. Pixels are read and written as 8-bit
+/// straight alpha, and the whole image converts from and to [`RgbaImage`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PremultipliedImage {
+    width: u32,
+    height: u32,
+    data: Vec<u16>,
+}
+
+impl PremultipliedImage {
+    /// A transparent image.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// The pixel at `x`, `y` in straight alpha.
+    ///
+    /// # Panics
+    ///
+    /// If `x`, `y` is outside the image.
+    pub fn get_pixel(&self, x: u32, y: u32) -> Rgba<u8> {
+        let i = self.index(x, y);
+        unpremultiply(&self.data[i..i + 4])
+    }
+
+    /// Sets the pixel at `x`, `y` to the straight alpha `pixel`.
+    ///
+    /// # Panics
+    ///
+    /// If `x`, `y` is outside the image.
+    pub fn put_pixel(&mut self, x: u32, y: u32, pixel: Rgba<u8>) {
+        let i = self.index(x, y);
+        self.data[i..i + 4].copy_from_slice(&premultiply(pixel));
+    }
+
+    /// The premultiplied RGBA values, row by row.
+    pub fn as_raw(&self) -> &[u16] {
+        &self.data
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        assert!(
+            x < self.width && y < self.height,
+            "pixel {x}, {y} is outside the {}x{} image",
+            self.width,
+            self.height
+        );
+
+        (y as usize * self.width as usize + x as usize) * 4
+    }
+}
+
+impl From<&RgbaImage> for PremultipliedImage {
+    fn from(img: &RgbaImage) -> Self {
+        Self {
+            width: img.width(),
+            height: img.height(),
+            data: img.pixels().flat_map(|p| premultiply(*p)).collect(),
+        }
+    }
+}
+
+impl From<&PremultipliedImage> for RgbaImage {
+    fn from(img: &PremultipliedImage) -> Self {
+        let data = img
+            .data
+            .chunks_exact(4)
+            .flat_map(|p| unpremultiply(p).0)
+            .collect();
+        RgbaImage::from_raw(img.width, img.height, data).expect("buffer matches dimensions")
+    }
+}
+
+impl Target for PremultipliedImage {
+    type Pixel = Rgba<u8>;
+
+    fn size(&self) -> (u32, u32) {
+        self.dimensions()
+    }
+
+    fn blend(&mut self, x: u32, y: u32, color: Rgba<u8>, coverage: f64, composite: Composite) {
+        let i = self.index(x, y);
+        let dst = &mut self.data[i..i + 4];
+
+        if composite != Composite::default() {
+            let mut pixel = Rgba(unpremultiply_wide(dst));
+            let color = Rgba(color.0.map(widen));
+
+            composite_channels(
+                pixel.channels_mut(),
+                color.channels(),
+                true,
+                coverage,
+                composite,
+            );
+            dst.copy_from_slice(&premultiply_wide(pixel.0));
+            return;
+        }
+
+        let alpha = (widen(color[3]) as f64 * coverage).round() as u32;
+        if alpha == 0 {
+            return;
+        }
+
+        let [r, g, b, _] = color.0.map(|c| div_max(widen(c) as u32 * alpha));
+
+        for (d, s) in dst.iter_mut().zip([r, g, b, alpha]) {
+            *d = (s + div_max(*d as u32 * (MAX - alpha))) as u16;
+        }
+    }
+}
+
+const MAX: u32 = u16::MAX as u32;
+
+/// `v / 65535`, rounded, for `v` up to `65535 * 65535`.
+fn div_max(v: u32) -> u32 {
+    let v = v as u64 + 32768;
+    ((v + (v >> 16)) >> 16) as u32
+}
+
+fn widen(c: u8) -> u16 {
+    c as u16 * 257
+}
+
+fn premultiply_wide([r, g, b, a]: [u16; 4]) -> [u16; 4] {
+    let premultiplied = |c: u16| div_max(c as u32 * a as u32) as u16;
+    [premultiplied(r), premultiplied(g), premultiplied(b), a]
+}
+
+fn unpremultiply_wide(pixel: &[u16]) -> [u16; 4] {
+    let a = pixel[3] as u64;
+    if a == 0 {
+        return [0; 4];
+    }
+
+    let channel = |c: u16| ((c as u64 * MAX as u64 + a / 2) / a).min(MAX as u64) as u16;
+    [
+        channel(pixel[0]),
+        channel(pixel[1]),
+        channel(pixel[2]),
+        pixel[3],
+    ]
+}
+
+fn premultiply(pixel: Rgba<u8>) -> [u16; 4] {
+    premultiply_wide(pixel.0.map(widen))
+}
+
+fn unpremultiply(pixel: &[u16]) -> Rgba<u8> {
+    let narrow = |c: u16| ((c as u32 + 128) / 257) as u8;
+    Rgba(unpremultiply_wide(pixel).map(narrow))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        antialiased::AntiAliasingRender,
+        basic::BasicRenderer,
+        types::{Circle, Rect},
+        Renderer,
+    };
+
+    use super::*;
+
+    #[test]
+    fn converts_from_and_to_straight_alpha() {
+        let img = RgbaImage::from_fn(4, 1, |x, _| match x {
+            0 => Rgba([10, 20, 30, 255]),
+            1 => Rgba([255, 128, 0, 128]),
+            2 => Rgba([90, 90, 90, 0]),
+            _ => Rgba([200, 100, 50, 51]),
+        });
+
+        let premultiplied = PremultipliedImage::from(&img);
+        assert_eq!(
+            &premultiplied.as_raw()[4..12],
+            &[32896, 16513, 0, 32896, 0, 0, 0, 0]
+        );
+
+        let back = RgbaImage::from(&premultiplied);
+        assert_eq!(back.get_pixel(0, 0), img.get_pixel(0, 0));
+        assert_eq!(back.get_pixel(1, 0), img.get_pixel(1, 0));
+        assert_eq!(*back.get_pixel(2, 0), Rgba([0, 0, 0, 0]));
+        assert_eq!(*back.get_pixel(3, 0), Rgba([200, 100, 50, 51]));
+    }
+
+    #[test]
+    fn integer_source_over_matches_straight_compositing() {
+        let backdrop = Rgba([40, 160, 220, 200]);
+        let color = Rgba([250, 30, 90, 170]);
+
+        for coverage in [0.1f64, 0.5f64, 0.8f64, 1f64] {
+            let mut premultiplied = PremultipliedImage::new(1, 1);
+            premultiplied.put_pixel(0, 0, backdrop);
+            premultiplied.blend(0, 0, color, coverage, Composite::default());
+
+            let mut straight = RgbaImage::from_pixel(1, 1, backdrop);
+            straight.blend(0, 0, color, coverage, Composite::default());
+
+            let (a, b) = (premultiplied.get_pixel(0, 0), straight.get_pixel(0, 0));
+            for (a, b) in a.0.iter().zip(b.0) {
+                assert!(a.abs_diff(b) <= 1, "{a} != {b} at coverage {coverage}");
+            }
+        }
+    }
+
+    #[test]
+    fn renderers_draw_into_premultiplied_images() {
+        let shade = image::Rgba([20, 90, 200, 60]);
+        let circle = Circle::new((10f64, 10f64), 6f64);
+        let rect = Rect::new(3f64, 3f64, 10f64, 10f64);
+
+        let mut straight = image::RgbaImage::new(20, 20);
+        let mut premultiplied = PremultipliedImage::new(20, 20);
+
+        // Layer the same translucent shapes a few times over.
+        for _ in 0..5 {
+            AntiAliasingRender::default().draw_filled_circle(&mut straight, circle, shade);
+            AntiAliasingRender::default().draw_filled_circle(&mut premultiplied, circle, shade);
+            BasicRenderer::default().draw_filled_rect(&mut straight, rect, shade);
+            BasicRenderer::default().draw_filled_rect(&mut premultiplied, rect, shade);
+        }
+
+        let converted = image::RgbaImage::from(&premultiplied);
+        for (a, b) in converted.pixels().zip(straight.pixels()) {
+            for (a, b) in a.0.iter().zip(b.0) {
+                assert!(a.abs_diff(b) <= 1, "{a:?} != {b:?}");
+            }
+        }
+
+        // Ten layers of 60/255 leave 1 - (195/255)^10 of the light.
+        assert_eq!(premultiplied.get_pixel(10, 10)[3], 238);
+        assert_eq!(premultiplied.get_pixel(10, 10).0[..3], [20, 90, 200]);
+    }
+}
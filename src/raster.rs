@@ -0,0 +1,580 @@
+use crate::types::{FillRule, Point};
+
+/// Vertical sub-scanlines sampled per pixel row by [`fill_aa`].
+const SUBSAMPLES: usize = 16;
+
+#[derive(Debug, Clone, Copy)]
+struct Edge {
+    top: f64,
+    bottom: f64,
+    x: f64,
+    dxdy: f64,
+    winding: i32,
+}
+
+impl Edge {
+    fn x_at(&self, y: f64) -> f64 {
+        self.x + (y - self.top) * self.dxdy
+    }
+}
+
+/// Edge table for a set of closed polygons, sorted by the edges' top.
+struct EdgeTable {
+    edges: Vec<Edge>,
+    top: f64,
+    bottom: f64,
+}
+
+impl EdgeTable {
+    fn new(polygons: &[Vec<Point>]) -> Self {
+        let mut edges = vec![];
+
+        for polygon in polygons {
+            for (i, &a) in polygon.iter().enumerate() {
+                let b = polygon[(i + 1) % polygon.len()];
+
+                if a.y == b.y || !(a.y.is_finite() && b.y.is_finite()) {
+                    continue;
+                }
+
+                let (upper, lower, winding) = if a.y < b.y { (a, b, 1) } else { (b, a, -1) };
+
+                edges.push(Edge {
+                    top: upper.y,
+                    bottom: lower.y,
+                    x: upper.x,
+                    dxdy: (lower.x - upper.x) / (lower.y - upper.y),
+                    winding,
+                });
+            }
+        }
+
+        edges.sort_by(|a, b| a.top.total_cmp(&b.top));
+
+        let top = edges.first().map_or(0f64, |e| e.top);
+        let bottom = edges.iter().fold(f64::NEG_INFINITY, |m, e| m.max(e.bottom));
+
+        Self { edges, top, bottom }
+    }
+
+    /// Calls `span` with every interval of the scanline at `y` the polygons
+    /// cover, keeping `active` up to date. Scanlines must be visited top to
+    /// bottom.
+    fn scan<F: FnMut(f64, f64)>(
+        &self,
+        y: f64,
+        rule: FillRule,
+        next: &mut usize,
+        active: &mut Vec<Edge>,
+        crossings: &mut Vec<(f64, i32)>,
+        mut span: F,
+    ) {
+        while *next < self.edges.len() && self.edges[*next].top <= y {
+            active.push(self.edges[*next]);
+            *next += 1;
+        }
+
+        active.retain(|e| e.bottom > y);
+
+        crossings.clear();
+        crossings.extend(active.iter().map(|e| (e.x_at(y), e.winding)));
+        crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut winding = 0;
+        let mut from = 0f64;
+
+        for &(x, w) in crossings.iter() {
+            let was_inside = rule.is_inside(winding);
+            winding += w;
+
+            match (was_inside, rule.is_inside(winding)) {
+                (false, true) => from = x,
+                (true, false) => span(from, x),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Fills `polygons` following `rule`, calling `span` with each row and first
+/// and last column whose pixel centers are inside.
+pub(crate) fn fill<F: FnMut(i64, i64, i64)>(
+    polygons: &[Vec<Point>],
+    rule: FillRule,
+    (width, height): (u32, u32),
+    mut span: F,
+) {
+    let table = EdgeTable::new(polygons);
+    if table.edges.is_empty() {
+        return;
+    }
+
+    let top = table.top.ceil().max(0f64) as i64;
+    let bottom = table.bottom.ceil().min(height as f64) as i64;
+
+    let (mut next, mut active, mut crossings) = (0, vec![], vec![]);
+
+    for y in top..bottom {
+        table.scan(
+            y as f64,
+            rule,
+            &mut next,
+            &mut active,
+            &mut crossings,
+            |x0, x1| {
+                let from = x0.ceil().max(0f64) as i64;
+                let to = x1.ceil().min(width as f64) as i64 - 1;
+
+                if from <= to {
+                    span(y, from, to);
+                }
+            },
+        );
+    }
+}
+
+/// Fills `polygons` following `rule`, calling `plot` with each touched pixel
+/// and the fraction of its area inside.
+///
+/// Each pixel row is sampled with [`SUBSAMPLES`] scanlines whose spans add
+/// their exact horizontal coverage, so overlapping polygons never blend a
+/// pixel twice.
+pub(crate) fn fill_aa<F: FnMut(i64, i64, f64)>(
+    polygons: &[Vec<Point>],
+    rule: FillRule,
+    (width, height): (u32, u32),
+    mut plot: F,
+) {
+    let table = EdgeTable::new(polygons);
+    if table.edges.is_empty() || width == 0 {
+        return;
+    }
+
+    let top = (table.top + 0.5f64).floor().max(0f64) as i64;
+    let bottom = (table.bottom + 0.5f64).ceil().min(height as f64) as i64;
+
+    let (mut next, mut active, mut crossings) = (0, vec![], vec![]);
+    let mut coverage = vec![0f64; width as usize];
+    let weight = 1f64 / SUBSAMPLES as f64;
+    let right = width as f64 - 0.5f64;
+
+    for y in top..bottom {
+        let (mut touched_from, mut touched_to) = (usize::MAX, 0);
+
+        for s in 0..SUBSAMPLES {
+            let sample_y = y as f64 - 0.5f64 + (s as f64 + 0.5f64) * weight;
+
+            table.scan(
+                sample_y,
+                rule,
+                &mut next,
+                &mut active,
+                &mut crossings,
+                |x0, x1| {
+                    let (x0, x1) = (x0.max(-0.5f64), x1.min(right));
+                    if x0 >= x1 {
+                        return;
+                    }
+
+                    let from = (x0 + 0.5f64).floor() as usize;
+                    let to = ((x1 + 0.5f64).floor() as usize).min(width as usize - 1);
+
+                    touched_from = touched_from.min(from);
+                    touched_to = touched_to.max(to);
+
+                    if from == to {
+                        coverage[from] += (x1 - x0) * weight;
+                        return;
+                    }
+
+                    coverage[from] += (from as f64 + 0.5f64 - x0) * weight;
+                    for c in &mut coverage[from + 1..to] {
+                        *c += weight;
+                    }
+                    coverage[to] += (x1 - (to as f64 - 0.5f64)) * weight;
+                },
+            );
+        }
+
+        if touched_from > touched_to {
+            continue;
+        }
+
+        for (x, c) in coverage[touched_from..=touched_to].iter_mut().enumerate() {
+            if *c > 1e-9 {
+                plot(
+                    (touched_from + x) as i64,
+                    y,
+                    if *c > 1f64 - 1e-9 { 1f64 } else { *c },
+                );
+            }
+            *c = 0f64;
+        }
+    }
+}
+
+/// Fills `polygons` following `rule`, calling `plot` with each pixel and the
+/// fraction of its area inside.
+///
+/// Unlike [`fill_aa`], coverage is exact: every edge adds the signed area it
+/// sweeps in each pixel to an accumulation buffer, whose running sums along a
+/// row give the winding-weighted area of its pixels, so each pixel is
+/// plotted once with its final coverage.
+pub(crate) fn accumulate<F: FnMut(i64, i64, f64)>(
+    polygons: &[Vec<Point>],
+    rule: FillRule,
+    (width, height): (u32, u32),
+    mut plot: F,
+) {
+    // Only the pixels under the polygons' bounding box are accumulated.
+    let (left, top, right, bottom) = match bounds(polygons, width, height) {
+        Some(bounds) => bounds,
+        None => return,
+    };
+    let (width, height) = (right - left, bottom - top);
+
+    // One extra column takes what edges past the right border add, another
+    // what a cell on that border spills into its neighbour.
+    let stride = width as usize + 2;
+    let mut cells = vec![0f64; stride * height as usize];
+
+    for polygon in polygons {
+        for (i, &a) in polygon.iter().enumerate() {
+            let b = polygon[(i + 1) % polygon.len()];
+
+            if !(a.x.is_finite() && a.y.is_finite() && b.x.is_finite() && b.y.is_finite()) {
+                continue;
+            }
+
+            // Pixel centers are at integers, so shift pixel edges onto them.
+            let shift =
+                |p: Point| Point::new(p.x + 0.5f64 - left as f64, p.y + 0.5f64 - top as f64);
+
+            clip_horizontally(shift(a), shift(b), width as f64, |p, q| {
+                accumulate_line(&mut cells, stride, height as f64, p, q)
+            });
+        }
+    }
+
+    for (y, row) in cells.chunks(stride).enumerate() {
+        let mut winding = 0f64;
+
+        for (x, cell) in row[..width as usize].iter().enumerate() {
+            winding += cell;
+
+            let coverage = match rule {
+                FillRule::NonZero => winding.abs().min(1f64),
+                FillRule::EvenOdd => {
+                    let folded = winding.abs() % 2f64;
+                    if folded > 1f64 {
+                        2f64 - folded
+                    } else {
+                        folded
+                    }
+                }
+            };
+
+            if coverage > 1e-9 {
+                plot(
+                    left + x as i64,
+                    top + y as i64,
+                    if coverage > 1f64 - 1e-9 {
+                        1f64
+                    } else {
+                        coverage
+                    },
+                );
+            }
+        }
+    }
+}
+
+/// Pixel columns `left..right` and rows `top..bottom` of an image with
+/// `width` and `height` touched by `polygons`, if any.
+fn bounds(polygons: &[Vec<Point>], width: u32, height: u32) -> Option<(i64, i64, i64, i64)> {
+    let points = polygons
+        .iter()
+        .flatten()
+        .filter(|p| p.x.is_finite() && p.y.is_finite());
+
+    let (min, max) = points.fold(
+        (
+            Point::new(f64::INFINITY, f64::INFINITY),
+            Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY),
+        ),
+        |(min, max), p| {
+            (
+                Point::new(min.x.min(p.x), min.y.min(p.y)),
+                Point::new(max.x.max(p.x), max.y.max(p.y)),
+            )
+        },
+    );
+
+    let left = (min.x + 0.5f64).floor().max(0f64);
+    let top = (min.y + 0.5f64).floor().max(0f64);
+    let right = (max.x + 0.5f64).ceil().min(width as f64);
+    let bottom = (max.y + 0.5f64).ceil().min(height as f64);
+
+    if left < right && top < bottom {
+        Some((left as i64, top as i64, right as i64, bottom as i64))
+    } else {
+        None
+    }
+}
+
+/// Splits `a`-`b` where it crosses the left and right borders and calls
+/// `line` with each piece, moving the ones outside onto the border. A piece
+/// left of the image still covers whole rows to its right, and one right of
+/// it covers nothing.
+fn clip_horizontally<F: FnMut(Point, Point)>(a: Point, b: Point, width: f64, mut line: F) {
+    let mut ts = [0f64, 1f64, 1f64, 1f64];
+
+    for (i, border) in [0f64, width].into_iter().enumerate() {
+        if (a.x - border) * (b.x - border) < 0f64 {
+            ts[i + 1] = (border - a.x) / (b.x - a.x);
+        }
+    }
+
+    ts.sort_by(f64::total_cmp);
+
+    let at = |t: f64| {
+        Point::new(
+            (a.x + (b.x - a.x) * t).clamp(0f64, width),
+            a.y + (b.y - a.y) * t,
+        )
+    };
+
+    for pair in ts.windows(2) {
+        if pair[0] < pair[1] {
+            line(at(pair[0]), at(pair[1]));
+        }
+    }
+}
+
+/// Adds the signed area the edge `p0`-`p1` sweeps in each cell to `cells`,
+/// for an edge within `0..=width` horizontally. Coordinates are in units of
+/// pixels with pixel edges on integers.
+fn accumulate_line(cells: &mut [f64], stride: usize, height: f64, p0: Point, p1: Point) {
+    if p0.y == p1.y {
+        return;
+    }
+
+    let (direction, p0, p1) = if p0.y < p1.y {
+        (1f64, p0, p1)
+    } else {
+        (-1f64, p1, p0)
+    };
+
+    if p1.y <= 0f64 || p0.y >= height {
+        return;
+    }
+
+    let width = (stride - 2) as f64;
+    let dxdy = (p1.x - p0.x) / (p1.y - p0.y);
+    let top = p0.y.max(0f64);
+    let mut x = p0.x + (top - p0.y) * dxdy;
+
+    for y in top.floor() as usize..p1.y.ceil().min(height) as usize {
+        let row = &mut cells[y * stride..(y + 1) * stride];
+
+        let dy = ((y + 1) as f64).min(p1.y) - (y as f64).max(top);
+        let next_x = (x + dxdy * dy).clamp(0f64, width);
+        let d = dy * direction;
+
+        let (x0, x1) = if x < next_x { (x, next_x) } else { (next_x, x) };
+        let (x0_floor, x1_ceil) = (x0.floor(), x1.ceil());
+        let (x0i, x1i) = (x0_floor as usize, x1_ceil as usize);
+
+        if x1i <= x0i + 1 {
+            // The edge stays within one cell: it covers the part of the cell
+            // right of its midpoint, and the rest spills into the next cell.
+            let mid = 0.5f64 * (x + next_x) - x0_floor;
+
+            row[x0i] += d - d * mid;
+            row[x0i + 1] += d * mid;
+        } else {
+            let s = (x1 - x0).recip();
+            let x0_fract = x0 - x0_floor;
+            let a0 = 0.5f64 * s * (1f64 - x0_fract) * (1f64 - x0_fract);
+            let x1_fract = x1 - x1_ceil + 1f64;
+            let am = 0.5f64 * s * x1_fract * x1_fract;
+
+            row[x0i] += d * a0;
+
+            if x1i == x0i + 2 {
+                row[x0i + 1] += d * (1f64 - a0 - am);
+            } else {
+                let a1 = s * (1.5f64 - x0_fract);
+                row[x0i + 1] += d * (a1 - a0);
+
+                for cell in &mut row[x0i + 2..x1i - 1] {
+                    *cell += d * s;
+                }
+
+                let a2 = a1 + (x1i - x0i - 3) as f64 * s;
+                row[x1i - 1] += d * (1f64 - a2 - am);
+            }
+
+            row[x1i] += d * am;
+        }
+
+        x = next_x;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(left: f64, top: f64, size: f64) -> Vec<Point> {
+        vec![
+            Point::new(left, top),
+            Point::new(left + size, top),
+            Point::new(left + size, top + size),
+            Point::new(left, top + size),
+        ]
+    }
+
+    #[test]
+    fn fill_covers_pixel_centers_inside() {
+        let mut spans = vec![];
+        fill(
+            &[square(0.5f64, 0.5f64, 3f64)],
+            FillRule::NonZero,
+            (10, 10),
+            |y, from, to| spans.push((y, from, to)),
+        );
+
+        assert_eq!(spans, vec![(1, 1, 3), (2, 1, 3), (3, 1, 3)]);
+    }
+
+    #[test]
+    fn fill_unions_overlapping_polygons() {
+        let mut spans = vec![];
+        fill(
+            &[square(0.5f64, 0.5f64, 2f64), square(1.5f64, 0.5f64, 2f64)],
+            FillRule::NonZero,
+            (10, 10),
+            |y, from, to| spans.push((y, from, to)),
+        );
+
+        assert_eq!(spans, vec![(1, 1, 3), (2, 1, 3)]);
+    }
+
+    #[test]
+    fn even_odd_leaves_holes_where_polygons_overlap() {
+        let mut spans = vec![];
+        fill(
+            &[square(0.5f64, 0.5f64, 2f64), square(1.5f64, 0.5f64, 2f64)],
+            FillRule::EvenOdd,
+            (10, 10),
+            |y, from, to| spans.push((y, from, to)),
+        );
+
+        assert_eq!(spans, vec![(1, 1, 1), (1, 3, 3), (2, 1, 1), (2, 3, 3)]);
+
+        let mut outer = square(0.5f64, 0.5f64, 5f64);
+        outer.reverse();
+
+        for rule in [FillRule::NonZero, FillRule::EvenOdd] {
+            let mut spans = vec![];
+            fill(
+                &[outer.clone(), square(1.5f64, 1.5f64, 3f64)],
+                rule,
+                (10, 10),
+                |y, from, to| spans.push((y, from, to)),
+            );
+
+            assert_eq!(spans[1], (2, 1, 1));
+            assert_eq!(spans[2], (2, 5, 5));
+        }
+    }
+
+    #[test]
+    fn fill_aa_measures_area() {
+        let mut total = 0f64;
+        let mut pixels = vec![];
+
+        fill_aa(
+            &[square(1f64, 1f64, 2f64), square(2f64, 1f64, 2f64)],
+            FillRule::NonZero,
+            (10, 10),
+            |x, y, c| {
+                total += c;
+                pixels.push((x, y, c));
+            },
+        );
+
+        assert!((total - 6f64).abs() < 1e-9);
+        assert!(pixels.iter().all(|&(_, _, c)| c <= 1f64));
+        assert!(pixels.contains(&(2, 2, 1f64)));
+        assert!(pixels.contains(&(1, 1, 0.25f64)));
+    }
+
+    #[test]
+    fn accumulate_measures_exact_area() {
+        let mut total = 0f64;
+        let mut pixels = vec![];
+
+        accumulate(
+            &[square(1f64, 1f64, 2f64), square(3f64, 1f64, 2f64)],
+            FillRule::NonZero,
+            (10, 10),
+            |x, y, c| {
+                total += c;
+                pixels.push((x, y, c));
+            },
+        );
+
+        assert!((total - 8f64).abs() < 1e-9);
+        assert!(pixels.contains(&(3, 2, 1f64)));
+        assert!(pixels.contains(&(3, 1, 0.5f64)));
+        assert!(pixels.contains(&(1, 1, 0.25f64)));
+
+        // Overlapping polygons wound the same way cover their union once.
+        let mut total = 0f64;
+        accumulate(
+            &[square(0.5f64, 0.5f64, 2f64), square(1.5f64, 0.5f64, 2f64)],
+            FillRule::NonZero,
+            (10, 10),
+            |_, _, c| total += c,
+        );
+        assert!((total - 6f64).abs() < 1e-9);
+
+        let triangle = vec![
+            Point::new(-0.5f64, -0.5f64),
+            Point::new(3.5f64, -0.5f64),
+            Point::new(-0.5f64, 3.5f64),
+        ];
+        let mut total = 0f64;
+        accumulate(&[triangle], FillRule::NonZero, (10, 10), |x, y, c| {
+            total += c;
+            if x + y == 3 {
+                assert!((c - 0.5f64).abs() < 1e-9);
+            }
+        });
+        assert!((total - 8f64).abs() < 1e-9);
+    }
+
+    #[test]
+    fn accumulate_clips_to_the_image() {
+        let mut total = 0f64;
+        accumulate(
+            &[square(-5.5f64, -5.5f64, 20f64)],
+            FillRule::NonZero,
+            (4, 3),
+            |_, _, c| total += c,
+        );
+        assert!((total - 12f64).abs() < 1e-9);
+
+        let mut holes = vec![];
+        accumulate(
+            &[square(0.5f64, 0.5f64, 5f64), square(1.5f64, 1.5f64, 3f64)],
+            FillRule::EvenOdd,
+            (10, 10),
+            |x, y, c| holes.push((x, y, c)),
+        );
+        assert!(holes.contains(&(1, 1, 1f64)));
+        assert!(!holes.iter().any(|&(x, y, _)| (x, y) == (3, 3)));
+    }
+}
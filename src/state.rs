@@ -0,0 +1,89 @@
+use crate::{clip::Clip, color::Blending, composite::Composite, stroke::Stroke};
+
+/// What every renderer draws with: the stroke, how it composites and where
+/// it may draw.
+#[derive(Default)]
+pub(crate) struct RenderState {
+    pub stroke: Stroke,
+    pub composite: Composite,
+    pub clip: Clip,
+}
+
+impl RenderState {
+    pub fn blending(&self) -> Blending<'_> {
+        Blending {
+            composite: self.composite,
+            clip: &self.clip,
+        }
+    }
+}
+
+/// Implements the composite and clip builders and accessors of a renderer
+/// keeping its [`RenderState`] in `state`.
+///
+/// With `stroke`, also implements the stroke's, along with [`Stroked`], for
+/// renderers that take the stroke as it is.
+///
+/// [`Stroked`]: crate::stroke::Stroked
+macro_rules! render_state {
+    ($renderer:ident<$($param:ident),*>, stroke) => {
+        impl<$($param),*> $renderer<$($param),*> {
+            pub fn with_stroke(mut self, stroke: $crate::stroke::Stroke) -> Self {
+                self.state.stroke = stroke;
+                self
+            }
+        }
+
+        impl<$($param),*> $crate::stroke::Stroked for $renderer<$($param),*> {
+            fn stroke(&self) -> &$crate::stroke::Stroke {
+                &self.state.stroke
+            }
+
+            fn with_stroke(self, stroke: $crate::stroke::Stroke) -> Self {
+                $renderer::with_stroke(self, stroke)
+            }
+        }
+
+        $crate::state::render_state!($renderer<$($param),*>);
+    };
+    ($renderer:ident<$($param:ident),*>) => {
+        impl<$($param),*> $renderer<$($param),*> {
+            pub fn with_composite(
+                mut self,
+                composite: impl Into<$crate::composite::Composite>,
+            ) -> Self {
+                self.state.composite = composite.into();
+                self
+            }
+
+            /// Restricts drawing to `clip`.
+            pub fn with_clip(mut self, clip: $crate::clip::Clip) -> Self {
+                self.state.clip = clip;
+                self
+            }
+
+            pub fn stroke(&self) -> &$crate::stroke::Stroke {
+                &self.state.stroke
+            }
+
+            pub fn composite(&self) -> $crate::composite::Composite {
+                self.state.composite
+            }
+
+            pub fn clip(&self) -> &$crate::clip::Clip {
+                &self.state.clip
+            }
+
+            /// The clip, for pushing and popping regions between calls.
+            pub fn clip_mut(&mut self) -> &mut $crate::clip::Clip {
+                &mut self.state.clip
+            }
+
+            fn blending(&self) -> $crate::color::Blending<'_> {
+                self.state.blending()
+            }
+        }
+    };
+}
+
+pub(crate) use render_state;
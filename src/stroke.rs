@@ -0,0 +1,770 @@
+use std::f64::consts::SQRT_2;
+
+use crate::{
+    error::MAX_COORDINATE,
+    geom::{circle_points, clip_line_params},
+    types::{Point, Rect},
+};
+
+/// Shortest non-zero dash or gap drawn; finer patterns couldn't be seen.
+const MIN_DASH: f64 = 1e-3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineCap {
+    Butt,
+    Round,
+    Square,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineJoin {
+    Miter,
+    Round,
+    Bevel,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stroke {
+    width: f64,
+    cap: LineCap,
+    join: LineJoin,
+    miter_limit: f64,
+    dash_array: Vec<f64>,
+    dash_offset: f64,
+}
+
+/// Renderers drawing outlines with a configurable [`Stroke`].
+pub trait Stroked {
+    fn stroke(&self) -> &Stroke;
+    fn with_stroke(self, stroke: Stroke) -> Self;
+}
+
+impl Default for Stroke {
+    fn default() -> Self {
+        Self::new(1f64)
+    }
+}
+
+impl Stroke {
+    pub fn new(width: f64) -> Self {
+        Self {
+            width,
+            cap: LineCap::Butt,
+            join: LineJoin::Miter,
+            miter_limit: 4f64,
+            dash_array: vec![],
+            dash_offset: 0f64,
+        }
+    }
+
+    pub fn with_cap(mut self, cap: LineCap) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    pub fn with_join(mut self, join: LineJoin) -> Self {
+        self.join = join;
+        self
+    }
+
+    /// Limits the ratio between a miter's length and the stroke width; joins
+    /// that would exceed it are beveled instead.
+    pub fn with_miter_limit(mut self, miter_limit: f64) -> Self {
+        self.miter_limit = miter_limit;
+        self
+    }
+
+    /// Alternates dashes and gaps with the lengths in `dash_array`, starting
+    /// `dash_offset` into the pattern. An odd number of lengths is repeated to
+    /// make it even, and an empty or all-zero array draws a solid line.
+    ///
+    /// The pattern runs continuously along a whole shape, e.g. through the
+    /// sides and corners of a rounded rectangle.
+    pub fn with_dash(mut self, dash_array: &[f64], dash_offset: f64) -> Self {
+        self.dash_array = dash_array.to_vec();
+        self.dash_offset = dash_offset;
+        self
+    }
+
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    pub fn cap(&self) -> LineCap {
+        self.cap
+    }
+
+    pub fn join(&self) -> LineJoin {
+        self.join
+    }
+
+    pub fn miter_limit(&self) -> f64 {
+        self.miter_limit
+    }
+
+    pub fn dash_array(&self) -> &[f64] {
+        &self.dash_array
+    }
+
+    pub fn dash_offset(&self) -> f64 {
+        self.dash_offset
+    }
+
+    /// Strokes at most one pixel wide are drawn as one-pixel lines; caps and
+    /// joins don't apply to them.
+    pub fn is_hairline(&self) -> bool {
+        self.width <= 1f64
+    }
+
+    pub fn is_dashed(&self) -> bool {
+        self.dash_array.iter().all(|d| d.is_finite() && *d >= 0f64)
+            && self.dash_array.iter().sum::<f64>() > 0f64
+    }
+
+    /// Solid hairlines are drawn with each shape's own one-pixel algorithm,
+    /// everything else goes through its outline polyline.
+    pub(crate) fn is_solid_hairline(&self) -> bool {
+        self.is_hairline() && !self.is_dashed()
+    }
+
+    /// The same stroke for geometry scaled up by `factor`. Hairlines become
+    /// `factor` wide, as they're drawn one pixel wide whatever their width.
+    pub(crate) fn scaled(&self, factor: f64) -> Stroke {
+        Stroke {
+            width: self.width.max(1f64) * factor,
+            dash_array: self.dash_array.iter().map(|d| d * factor).collect(),
+            dash_offset: self.dash_offset * factor,
+            ..self.clone()
+        }
+    }
+
+    /// Splits the polyline `points` into the pieces this stroke draws on an
+    /// image of `size`: the dashes when dashed, or the whole polyline
+    /// otherwise. Each piece comes with whether it's closed.
+    ///
+    /// Dashes are only cut where the stroke could reach the image; the pattern
+    /// just runs on over the rest of the polyline.
+    pub(crate) fn pieces(
+        &self,
+        points: &[Point],
+        closed: bool,
+        size: (u32, u32),
+    ) -> Vec<(Vec<Point>, bool)> {
+        if !self.is_dashed() || points.is_empty() {
+            return vec![(points.to_vec(), closed)];
+        }
+
+        // Caps and joins reach at most this far from the line.
+        let half = self.width.max(1f64) / 2f64;
+        let margin = (half * self.miter_limit.max(SQRT_2) + 1f64).min(MAX_COORDINATE);
+        let (width, height) = (size.0 as f64, size.1 as f64);
+        let bounds = Rect::new(
+            -margin,
+            -margin,
+            width + 2f64 * margin,
+            height + 2f64 * margin,
+        );
+
+        let mut phase = DashPhase::new(&self.dash_array, bounds);
+        phase.advance(self.dash_offset.rem_euclid(phase.total));
+
+        if points.len() == 1 {
+            if phase.is_on() {
+                return vec![(points.to_vec(), false)];
+            }
+
+            return vec![];
+        }
+
+        let mut dashes = vec![];
+        let mut current = vec![];
+
+        // A closed polyline's last dash carries on into its first, unless the
+        // first doesn't start right at the first point.
+        let mut joins_start = closed && phase.is_on();
+
+        let mut vertices = points.to_vec();
+        if closed {
+            vertices.push(points[0]);
+        }
+
+        for (i, pair) in vertices.windows(2).enumerate() {
+            let (a, b) = (pair[0], pair[1]);
+            let length = (b.x - a.x).hypot(b.y - a.y);
+            let at = |position: f64| {
+                let t = if length > 0f64 {
+                    position / length
+                } else {
+                    0f64
+                };
+                Point::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t)
+            };
+
+            let Some((t0, t1)) = clip_line_params(a, b, bounds) else {
+                joins_start &= i > 0;
+                phase.advance(length);
+                continue;
+            };
+
+            let (enter, exit) = (t0 * length, t1 * length);
+
+            if enter > 0f64 {
+                joins_start &= i > 0;
+
+                if !current.is_empty() {
+                    dashes.push(std::mem::take(&mut current));
+                }
+
+                phase.advance(enter);
+            }
+
+            if phase.is_on() && current.is_empty() {
+                current.push(at(enter));
+            }
+
+            let mut position = enter;
+
+            while exit - position > phase.remaining {
+                position += phase.remaining;
+                let p = at(position);
+
+                if phase.is_on() {
+                    current.push(p);
+                    dashes.push(std::mem::take(&mut current));
+                } else {
+                    current = vec![p];
+                }
+
+                phase.next();
+            }
+
+            phase.remaining -= exit - position;
+
+            if exit < length {
+                if phase.is_on() {
+                    current.push(at(exit));
+                    dashes.push(std::mem::take(&mut current));
+                }
+
+                phase.advance(length - exit);
+            } else if phase.is_on() {
+                current.push(b);
+            }
+        }
+
+        if phase.is_on() && !current.is_empty() {
+            if joins_start && !dashes.is_empty() {
+                let first = dashes.remove(0);
+                current.extend_from_slice(&first[1..]);
+            }
+
+            dashes.push(current);
+        }
+
+        dashes.into_iter().map(|dash| (dash, false)).collect()
+    }
+
+    /// Builds the polygons covering the stroke of the polyline `points`.
+    ///
+    /// Every polygon is wound the same way, so their union is what a non-zero
+    /// fill of all of them produces.
+    pub(crate) fn outline(&self, points: &[Point], closed: bool) -> Vec<Vec<Point>> {
+        let mut points = points.to_vec();
+        points.dedup();
+
+        if closed && points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+
+        let half = self.width / 2f64;
+        let mut polygons = vec![];
+
+        if points.len() == 1 {
+            let p = points[0];
+
+            match self.cap {
+                LineCap::Butt => {}
+                LineCap::Round => polygons.push(circle_points(p, half)),
+                LineCap::Square => polygons.push(vec![
+                    Point::new(p.x - half, p.y - half),
+                    Point::new(p.x + half, p.y - half),
+                    Point::new(p.x + half, p.y + half),
+                    Point::new(p.x - half, p.y + half),
+                ]),
+            }
+
+            return polygons;
+        }
+
+        let segments = if closed && points.len() > 2 {
+            points.len()
+        } else {
+            points.len() - 1
+        };
+
+        let segment = |i: usize| (points[i], points[(i + 1) % points.len()]);
+
+        for i in 0..segments {
+            let (a, b) = segment(i);
+            let n = scale(direction(a, b).1, half);
+
+            polygons.push(vec![add(a, n), add(b, n), sub(b, n), sub(a, n)]);
+        }
+
+        let joins = if closed && points.len() > 2 {
+            0..segments
+        } else {
+            1..segments
+        };
+
+        for i in joins {
+            let (a, p) = segment((i + segments - 1) % segments);
+            let (_, b) = segment(i);
+
+            self.join_polygon(&mut polygons, p, direction(a, p), direction(p, b));
+        }
+
+        if !closed || points.len() == 2 {
+            let (a, b) = segment(0);
+            self.cap_polygon(&mut polygons, a, direction(b, a));
+
+            let (a, b) = segment(segments - 1);
+            self.cap_polygon(&mut polygons, b, direction(a, b));
+        }
+
+        for polygon in polygons.iter_mut() {
+            if signed_area(polygon) < 0f64 {
+                polygon.reverse();
+            }
+        }
+
+        polygons
+    }
+
+    fn join_polygon(
+        &self,
+        polygons: &mut Vec<Vec<Point>>,
+        p: Point,
+        (d0, n0): (Point, Point),
+        (d1, n1): (Point, Point),
+    ) {
+        let half = self.width / 2f64;
+        let cross = d0.x * d1.y - d0.y * d1.x;
+        let dot = d0.x * d1.x + d0.y * d1.y;
+
+        if cross.abs() < 1e-9 && dot > 0f64 {
+            return;
+        }
+
+        let side = if cross > 0f64 { -half } else { half };
+        let (a, b) = (add(p, scale(n0, side)), add(p, scale(n1, side)));
+
+        match self.join {
+            LineJoin::Round => polygons.push(circle_points(p, half)),
+            LineJoin::Bevel => polygons.push(vec![p, a, b]),
+            LineJoin::Miter => {
+                let cos_half = ((1f64 + dot) / 2f64).sqrt();
+                let ratio = if cos_half > 0f64 {
+                    1f64 / cos_half
+                } else {
+                    f64::INFINITY
+                };
+
+                if ratio <= self.miter_limit {
+                    let (bisector, _) = direction(Point::default(), add(n0, n1));
+                    let tip = add(p, scale(bisector, side * ratio));
+
+                    polygons.push(vec![p, a, tip, b]);
+                } else {
+                    polygons.push(vec![p, a, b]);
+                }
+            }
+        }
+    }
+
+    fn cap_polygon(&self, polygons: &mut Vec<Vec<Point>>, p: Point, (d, n): (Point, Point)) {
+        let half = self.width / 2f64;
+
+        match self.cap {
+            LineCap::Butt => {}
+            LineCap::Round => polygons.push(circle_points(p, half)),
+            LineCap::Square => {
+                let (n, d) = (scale(n, half), scale(d, half));
+
+                polygons.push(vec![
+                    add(p, n),
+                    add(add(p, n), d),
+                    add(sub(p, n), d),
+                    sub(p, n),
+                ]);
+            }
+        }
+    }
+}
+
+/// Where along its dash pattern a stroke is.
+struct DashPhase {
+    pattern: Vec<f64>,
+    total: f64,
+    index: usize,
+    remaining: f64,
+}
+
+impl DashPhase {
+    /// Starts at the beginning of `dash_array`, repeated to an even length.
+    ///
+    /// Lengths too short to advance along a segment within `bounds`, or to
+    /// see at all, are stretched to [`MIN_DASH`] or more; zero-length dashes
+    /// are kept, as dots.
+    fn new(dash_array: &[f64], bounds: Rect) -> Self {
+        let min = MIN_DASH.max(bounds.width().hypot(bounds.height()) * f64::EPSILON);
+
+        let mut pattern: Vec<_> = dash_array
+            .iter()
+            .map(|&d| if d > 0f64 { d.max(min) } else { d })
+            .collect();
+
+        if pattern.len() % 2 == 1 {
+            pattern.extend_from_within(..);
+        }
+
+        Self {
+            total: pattern.iter().sum(),
+            remaining: pattern[0],
+            pattern,
+            index: 0,
+        }
+    }
+
+    fn is_on(&self) -> bool {
+        self.index.is_multiple_of(2)
+    }
+
+    fn next(&mut self) {
+        self.index = (self.index + 1) % self.pattern.len();
+        self.remaining = self.pattern[self.index];
+    }
+
+    /// Moves `by` along the pattern, skipping whole repeats of it at once.
+    fn advance(&mut self, by: f64) {
+        if by <= 0f64 {
+            return;
+        }
+
+        if by < self.remaining {
+            self.remaining -= by;
+            return;
+        }
+
+        let mut offset = (by - self.remaining).rem_euclid(self.total);
+        self.next();
+
+        while offset > self.remaining || (offset == self.remaining && offset > 0f64) {
+            offset -= self.remaining;
+            self.next();
+        }
+
+        self.remaining -= offset;
+    }
+}
+
+/// Unit direction from `a` to `b` and its left-hand normal.
+fn direction(a: Point, b: Point) -> (Point, Point) {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len = dx.hypot(dy);
+
+    if len == 0f64 {
+        return (Point::default(), Point::default());
+    }
+
+    let d = Point::new(dx / len, dy / len);
+    (d, Point::new(-d.y, d.x))
+}
+
+fn add(a: Point, b: Point) -> Point {
+    Point::new(a.x + b.x, a.y + b.y)
+}
+
+fn sub(a: Point, b: Point) -> Point {
+    Point::new(a.x - b.x, a.y - b.y)
+}
+
+fn scale(a: Point, k: f64) -> Point {
+    Point::new(a.x * k, a.y * k)
+}
+
+fn signed_area(polygon: &[Point]) -> f64 {
+    let mut area = 0f64;
+
+    for (i, a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        area += a.x * b.y - b.x * a.y;
+    }
+
+    area / 2f64
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{antialiased::AntiAliasingRender, basic::BasicRenderer, types::Circle, Renderer};
+
+    use super::*;
+
+    #[test]
+    fn outline_of_open_polyline_has_caps_and_joins() {
+        let points = [
+            Point::new(0f64, 0f64),
+            Point::new(10f64, 0f64),
+            Point::new(10f64, 10f64),
+        ];
+
+        let butt = Stroke::new(2f64).outline(&points, false);
+        assert_eq!(butt.len(), 3);
+        assert!(butt.iter().all(|p| signed_area(p) > 0f64));
+
+        let miter = &butt[2];
+        assert!(miter
+            .iter()
+            .any(|p| (p.x - 11f64).abs() < 1e-9 && (p.y + 1f64).abs() < 1e-9));
+
+        let square = Stroke::new(2f64)
+            .with_cap(LineCap::Square)
+            .with_join(LineJoin::Bevel)
+            .outline(&points, false);
+        assert_eq!(square.len(), 5);
+        assert_eq!(square[2].len(), 3);
+    }
+
+    #[test]
+    fn sharp_miters_fall_back_to_bevels() {
+        let points = [
+            Point::new(0f64, 0f64),
+            Point::new(10f64, 0f64),
+            Point::new(0f64, 1f64),
+        ];
+
+        let polygons = Stroke::new(2f64).outline(&points, false);
+        assert_eq!(polygons[2].len(), 3);
+
+        let polygons = Stroke::new(2f64)
+            .with_miter_limit(100f64)
+            .outline(&points, false);
+        assert_eq!(polygons[2].len(), 4);
+    }
+
+    #[test]
+    fn dashes_continue_across_segments() {
+        let points = [
+            Point::new(0f64, 0f64),
+            Point::new(5f64, 0f64),
+            Point::new(5f64, 5f64),
+        ];
+
+        let pieces =
+            Stroke::new(1f64)
+                .with_dash(&[4f64, 2f64], 0f64)
+                .pieces(&points, false, (10, 10));
+
+        assert_eq!(
+            pieces,
+            vec![
+                (vec![Point::new(0f64, 0f64), Point::new(4f64, 0f64)], false),
+                (vec![Point::new(5f64, 1f64), Point::new(5f64, 5f64),], false),
+            ]
+        );
+
+        let pieces = Stroke::new(1f64)
+            .with_dash(&[3f64], 2f64)
+            .pieces(&points, false, (10, 10));
+
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(
+            pieces[0].0,
+            vec![Point::new(0f64, 0f64), Point::new(1f64, 0f64)]
+        );
+        assert_eq!(
+            pieces[1].0,
+            vec![
+                Point::new(4f64, 0f64),
+                Point::new(5f64, 0f64),
+                Point::new(5f64, 2f64)
+            ]
+        );
+    }
+
+    #[test]
+    fn dashes_merge_across_the_start_of_closed_polylines() {
+        let points = [
+            Point::new(0f64, 0f64),
+            Point::new(4f64, 0f64),
+            Point::new(4f64, 4f64),
+            Point::new(0f64, 4f64),
+        ];
+
+        let pieces =
+            Stroke::new(1f64)
+                .with_dash(&[6f64, 1f64], 0f64)
+                .pieces(&points, true, (10, 10));
+
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(
+            pieces[1].0,
+            vec![
+                Point::new(0f64, 2f64),
+                Point::new(0f64, 0f64),
+                Point::new(4f64, 0f64),
+                Point::new(4f64, 2f64)
+            ]
+        );
+    }
+
+    #[test]
+    fn zero_length_dashes_are_dots() {
+        let points = [Point::new(0f64, 0f64), Point::new(4f64, 0f64)];
+
+        let pieces =
+            Stroke::new(1f64)
+                .with_dash(&[0f64, 2f64], 0f64)
+                .pieces(&points, false, (10, 10));
+
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(pieces[0].0, vec![Point::new(0f64, 0f64); 2]);
+        assert_eq!(pieces[1].0, vec![Point::new(2f64, 0f64); 2]);
+
+        let solid = Stroke::new(1f64).with_dash(&[0f64, 0f64], 0f64);
+        assert!(!solid.is_dashed());
+    }
+
+    #[test]
+    fn dashes_are_only_cut_near_the_image() {
+        let points = [Point::new(-1e8, 5f64), Point::new(1e8, 5f64)];
+
+        let pieces =
+            Stroke::new(1f64)
+                .with_dash(&[2f64, 2f64], 0f64)
+                .pieces(&points, false, (10, 10));
+
+        let spans: Vec<_> = pieces
+            .iter()
+            .map(|(dash, _)| (dash[0].x, dash[dash.len() - 1].x))
+            .collect();
+
+        assert_eq!(spans.len(), 5);
+        for ((from, to), expected) in spans.iter().zip([
+            (-3f64, -2f64),
+            (0f64, 2f64),
+            (4f64, 6f64),
+            (8f64, 10f64),
+            (12f64, 13f64),
+        ]) {
+            assert!((from - expected.0).abs() < 1e-6, "{spans:?}");
+            assert!((to - expected.1).abs() < 1e-6, "{spans:?}");
+        }
+    }
+
+    #[test]
+    fn dashes_too_short_to_see_are_lengthened() {
+        let points = [Point::new(0f64, 5f64), Point::new(9f64, 5f64)];
+
+        let pieces =
+            Stroke::new(1f64)
+                .with_dash(&[1e-20, 1e-20], 0f64)
+                .pieces(&points, false, (10, 10));
+
+        assert_eq!(pieces.len(), 4500);
+        assert_eq!(
+            pieces[0].0,
+            vec![Point::new(0f64, 5f64), Point::new(1e-3, 5f64)]
+        );
+    }
+
+    #[test]
+    fn closed_outline_joins_every_corner() {
+        let points = [
+            Point::new(0f64, 0f64),
+            Point::new(10f64, 0f64),
+            Point::new(10f64, 10f64),
+            Point::new(0f64, 10f64),
+            Point::new(0f64, 0f64),
+        ];
+
+        let polygons = Stroke::new(2f64).outline(&points, true);
+        assert_eq!(polygons.len(), 8);
+    }
+
+    #[test]
+    fn wide_strokes_have_caps_and_joins() {
+        let red = image::Rgba([255, 0, 0, 255]);
+        let clear = image::Rgba([0, 0, 0, 0]);
+
+        let mut img = image::RgbaImage::new(40, 40);
+        let r = BasicRenderer::default().with_stroke(Stroke::new(4f64));
+        r.draw_rect(&mut img, Rect::new(10f64, 10f64, 20f64, 20f64), red);
+
+        assert_eq!(*img.get_pixel(8, 8), red);
+        assert_eq!(*img.get_pixel(31, 20), red);
+        assert_eq!(*img.get_pixel(20, 12), clear);
+        assert_eq!(*img.get_pixel(7, 7), clear);
+
+        let mut img = image::RgbaImage::new(40, 40);
+        let r = BasicRenderer::default().with_stroke(Stroke::new(4f64).with_join(LineJoin::Bevel));
+        r.draw_rect(&mut img, Rect::new(10f64, 10f64, 20f64, 20f64), red);
+
+        assert_eq!(*img.get_pixel(8, 8), clear);
+        assert_eq!(*img.get_pixel(9, 9), red);
+
+        let mut img = image::RgbaImage::new(40, 40);
+        let r = BasicRenderer::default().with_stroke(Stroke::new(6f64).with_cap(LineCap::Square));
+        r.draw_line(
+            &mut img,
+            Point::new(10f64, 20f64),
+            Point::new(30f64, 20f64),
+            red,
+        );
+
+        assert_eq!(*img.get_pixel(8, 18), red);
+        assert_eq!(*img.get_pixel(32, 22), red);
+        assert_eq!(*img.get_pixel(20, 24), clear);
+
+        let mut img = image::RgbaImage::new(40, 40);
+        let aar = AntiAliasingRender::default().with_stroke(Stroke::new(2f64));
+        aar.draw_circle(&mut img, Circle::new((20f64, 20f64), 10f64), red);
+
+        assert_eq!(*img.get_pixel(30, 20), red);
+        assert_eq!(*img.get_pixel(20, 20), clear);
+        let edge = img.get_pixel(20, 9).0[3];
+        assert!(edge > 0 && edge < 255);
+    }
+
+    #[test]
+    fn dashed_strokes_leave_gaps() {
+        let red = image::Rgba([255, 0, 0, 255]);
+        let clear = image::Rgba([0, 0, 0, 0]);
+
+        let mut img = image::RgbaImage::new(20, 5);
+        let r =
+            BasicRenderer::default().with_stroke(Stroke::new(1f64).with_dash(&[3f64, 2f64], 0f64));
+        r.draw_line(
+            &mut img,
+            Point::new(0f64, 2f64),
+            Point::new(19f64, 2f64),
+            red,
+        );
+
+        let row: Vec<bool> = (0..20).map(|x| *img.get_pixel(x, 2) == red).collect();
+        assert_eq!(
+            &row[..10],
+            &[true, true, true, true, false, true, true, true, true, false]
+        );
+
+        let mut img = image::RgbaImage::new(40, 40);
+        let r =
+            BasicRenderer::default().with_stroke(Stroke::new(3f64).with_dash(&[10f64, 5f64], 0f64));
+        r.draw_rounded_rect(&mut img, Rect::new(5f64, 5f64, 30f64, 30f64), 6f64, red);
+
+        assert_eq!(*img.get_pixel(15, 5), red);
+        assert_eq!(*img.get_pixel(20, 5), red);
+        assert_eq!(*img.get_pixel(23, 5), clear);
+        assert_eq!(*img.get_pixel(27, 5), red);
+    }
+}
@@ -0,0 +1,597 @@
+use std::{f64::consts::SQRT_2, marker::PhantomData};
+
+use image::{GrayImage, Luma};
+
+use crate::{
+    paint::{blend_paint, Paint},
+    path::Path,
+    state::{render_state, RenderState},
+    stroke::{Stroke, Stroked},
+    target::Target,
+    types::{Angle, Circle, Ellipse, FillRule, Point, Rect},
+    Renderer,
+};
+
+/// How [`SupersampledRenderer`] combines the samples around a pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Filter {
+    /// Averages the samples within the pixel.
+    #[default]
+    Box,
+    /// Weighs the samples within a pixel's distance from its center by how
+    /// close they are, trading a little sharpness for less aliasing.
+    Tent,
+}
+
+impl Filter {
+    /// Offsets of the samples contributing to a pixel from its first sample
+    /// along one axis, with their weights. The weights add up to `factor`.
+    fn taps(&self, factor: u32) -> Vec<(i64, f64)> {
+        let n = factor as i64;
+
+        match self {
+            Filter::Box => (0..n).map(|i| (i, 1f64)).collect(),
+            Filter::Tent => {
+                let center = (n - 1) as f64 / 2f64;
+
+                (-n..2 * n)
+                    .map(|i| (i, 1f64 - (i as f64 - center).abs() / n as f64))
+                    .filter(|&(_, weight)| weight > 0f64)
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Anti-aliasing adaptor drawing with another renderer into a coverage mask
+/// `factor` times the resolution of the image, then filtering the mask down
+/// onto it.
+///
+/// The inner renderer draws `Luma([255])` into a [`GrayImage`] covering just
+/// the pixels a call can touch. Its stroke is set through the adaptor, which
+/// scales it along with the geometry, and the adaptor's composite applies to
+/// the filtered coverage: the inner renderer always draws the mask
+/// source-over.
+pub struct SupersampledRenderer<R, I> {
+    inner: R,
+    state: RenderState,
+    factor: u32,
+    filter: Filter,
+    _phantom_data: PhantomData<I>,
+}
+
+/// Largest supersampling factor; the mask takes `factor²` bytes per pixel.
+pub const MAX_FACTOR: u32 = 16;
+
+impl<R: Stroked, I> SupersampledRenderer<R, I> {
+    /// Draws with `inner` at `factor` times the resolution, clamped to between
+    /// 1 and [`MAX_FACTOR`].
+    pub fn new(inner: R, factor: u32) -> Self {
+        let factor = factor.clamp(1, MAX_FACTOR);
+        let stroke = inner.stroke().clone();
+
+        Self {
+            inner: inner.with_stroke(stroke.scaled(factor as f64)),
+            state: RenderState {
+                stroke,
+                ..RenderState::default()
+            },
+            factor,
+            filter: Filter::default(),
+            _phantom_data: PhantomData,
+        }
+    }
+
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn with_stroke(mut self, stroke: Stroke) -> Self {
+        self.inner = self.inner.with_stroke(stroke.scaled(self.factor as f64));
+        self.state.stroke = stroke;
+        self
+    }
+
+    pub fn factor(&self) -> u32 {
+        self.factor
+    }
+
+    pub fn filter(&self) -> Filter {
+        self.filter
+    }
+}
+
+render_state!(SupersampledRenderer<R, I>);
+
+impl<R: Stroked, I> Stroked for SupersampledRenderer<R, I> {
+    fn stroke(&self) -> &Stroke {
+        &self.state.stroke
+    }
+
+    fn with_stroke(self, stroke: Stroke) -> Self {
+        SupersampledRenderer::with_stroke(self, stroke)
+    }
+}
+
+/// The pixels a call can touch, mapped onto the mask it's drawn into.
+struct Window {
+    left: i64,
+    top: i64,
+    width: u32,
+    height: u32,
+    factor: f64,
+}
+
+impl Window {
+    fn point(&self, p: Point) -> Point {
+        Point::new(
+            (p.x - self.left as f64 + 0.5f64) * self.factor - 0.5f64,
+            (p.y - self.top as f64 + 0.5f64) * self.factor - 0.5f64,
+        )
+    }
+
+    fn points(&self, points: &[Point]) -> Vec<Point> {
+        points.iter().map(|p| self.point(*p)).collect()
+    }
+
+    fn length(&self, length: f64) -> f64 {
+        length * self.factor
+    }
+
+    fn rect(&self, rect: Rect) -> Rect {
+        let top_left = self.point(Point::new(rect.left(), rect.top()));
+
+        Rect::new(
+            top_left.x,
+            top_left.y,
+            self.length(rect.width()),
+            self.length(rect.height()),
+        )
+    }
+
+    fn circle(&self, circle: Circle) -> Circle {
+        Circle::new(self.point(circle.center()), self.length(circle.radius()))
+    }
+
+    fn ellipse(&self, ellipse: Ellipse) -> Ellipse {
+        Ellipse::new(
+            self.point(ellipse.center()),
+            self.length(ellipse.radius_x()),
+            self.length(ellipse.radius_y()),
+        )
+        .with_rotation(ellipse.rotation())
+    }
+
+    fn path(&self, path: &Path) -> Path {
+        path.map_points(|p| self.point(p))
+    }
+}
+
+const MASK: Luma<u8> = Luma([255]);
+
+impl<R, I> SupersampledRenderer<R, I>
+where
+    R: Renderer<Image = GrayImage, Pixel = Luma<u8>>,
+    I: Target,
+{
+    /// Has `draw` render into a mask covering the pixels around `extent`
+    /// (widened by the stroke when `stroked`), then blends `paint` onto them
+    /// by the filtered coverage.
+    fn draw<E, F>(&self, img: &mut I, extent: E, stroked: bool, paint: &Paint<I::Pixel>, draw: F)
+    where
+        E: IntoIterator<Item = Point>,
+        F: FnOnce(&R, &mut GrayImage, &Window),
+    {
+        let (width, height) = img.size();
+
+        let (min, max) = extent.into_iter().fold(
+            (
+                Point::new(f64::INFINITY, f64::INFINITY),
+                Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY),
+            ),
+            |(min, max), p| {
+                (
+                    Point::new(min.x.min(p.x), min.y.min(p.y)),
+                    Point::new(max.x.max(p.x), max.y.max(p.y)),
+                )
+            },
+        );
+
+        // Filled shapes can reach half a pixel past their extent, and tent
+        // filtering reads one more pixel around that.
+        let mut margin = 2f64;
+        if stroked {
+            let half_width = self.state.stroke.width().max(1f64) / 2f64;
+            margin += half_width * self.state.stroke.miter_limit().max(SQRT_2);
+        }
+
+        let left = (min.x - margin).floor().max(0f64) as i64;
+        let top = (min.y - margin).floor().max(0f64) as i64;
+        let right = (max.x + margin).ceil().min(width as f64) as i64;
+        let bottom = (max.y + margin).ceil().min(height as f64) as i64;
+
+        if left >= right || top >= bottom {
+            return;
+        }
+
+        let window = Window {
+            left,
+            top,
+            width: (right - left) as u32,
+            height: (bottom - top) as u32,
+            factor: self.factor as f64,
+        };
+
+        let (Some(mask_width), Some(mask_height)) = (
+            window.width.checked_mul(self.factor),
+            window.height.checked_mul(self.factor),
+        ) else {
+            return;
+        };
+
+        let mut mask = GrayImage::new(mask_width, mask_height);
+        draw(&self.inner, &mut mask, &window);
+
+        let taps = self.filter.taps(self.factor);
+        let n = self.factor as i64;
+        let scale = 255f64 * (n * n) as f64;
+
+        for y in 0..window.height as i64 {
+            for x in 0..window.width as i64 {
+                let mut sum = 0f64;
+
+                for &(dy, wy) in &taps {
+                    let sy = y * n + dy;
+                    if sy < 0 || sy >= mask.height() as i64 {
+                        continue;
+                    }
+
+                    for &(dx, wx) in &taps {
+                        let sx = x * n + dx;
+                        if sx < 0 || sx >= mask.width() as i64 {
+                            continue;
+                        }
+
+                        sum += wy * wx * mask.get_pixel(sx as u32, sy as u32)[0] as f64;
+                    }
+                }
+
+                blend_paint(img, left + x, top + y, paint, sum / scale, self.blending());
+            }
+        }
+    }
+}
+
+fn circle_extent(center: Point, radius: f64) -> [Point; 2] {
+    [
+        Point::new(center.x - radius, center.y - radius),
+        Point::new(center.x + radius, center.y + radius),
+    ]
+}
+
+fn rect_extent(rect: Rect) -> [Point; 2] {
+    [
+        Point::new(rect.left(), rect.top()),
+        Point::new(rect.right(), rect.bottom()),
+    ]
+}
+
+impl<R, I> Renderer for SupersampledRenderer<R, I>
+where
+    R: Renderer<Image = GrayImage, Pixel = Luma<u8>>,
+    I: Target,
+{
+    type Image = I;
+    type Pixel = I::Pixel;
+
+    fn draw_line(&self, img: &mut Self::Image, from: Point, to: Point, color: Self::Pixel) {
+        self.draw(img, [from, to], true, &Paint::Solid(color), |r, mask, w| {
+            r.draw_line(mask, w.point(from), w.point(to), MASK)
+        });
+    }
+
+    fn draw_rect(&self, img: &mut Self::Image, rect: Rect, color: Self::Pixel) {
+        self.draw(
+            img,
+            rect_extent(rect),
+            true,
+            &Paint::Solid(color),
+            |r, mask, w| r.draw_rect(mask, w.rect(rect), MASK),
+        );
+    }
+
+    fn draw_filled_rect<'a>(
+        &self,
+        img: &mut Self::Image,
+        rect: Rect,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = paint.into();
+
+        self.draw(img, rect_extent(rect), false, &paint, |r, mask, w| {
+            r.draw_filled_rect(mask, w.rect(rect), MASK)
+        });
+    }
+
+    fn draw_circle(&self, img: &mut Self::Image, circle: Circle, color: Self::Pixel) {
+        let extent = circle_extent(circle.center(), circle.radius());
+
+        self.draw(img, extent, true, &Paint::Solid(color), |r, mask, w| {
+            r.draw_circle(mask, w.circle(circle), MASK)
+        });
+    }
+
+    fn draw_filled_circle<'a>(
+        &self,
+        img: &mut Self::Image,
+        circle: Circle,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = paint.into();
+
+        let extent = circle_extent(circle.center(), circle.radius());
+
+        self.draw(img, extent, false, &paint, |r, mask, w| {
+            r.draw_filled_circle(mask, w.circle(circle), MASK)
+        });
+    }
+
+    fn draw_arc(
+        &self,
+        img: &mut Self::Image,
+        circle: Circle,
+        start: Angle,
+        end: Angle,
+        color: Self::Pixel,
+    ) {
+        let extent = circle_extent(circle.center(), circle.radius());
+
+        self.draw(img, extent, true, &Paint::Solid(color), |r, mask, w| {
+            r.draw_arc(mask, w.circle(circle), start, end, MASK)
+        });
+    }
+
+    fn draw_filled_arc<'a>(
+        &self,
+        img: &mut Self::Image,
+        circle: Circle,
+        start: Angle,
+        end: Angle,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = paint.into();
+
+        let extent = circle_extent(circle.center(), circle.radius());
+
+        self.draw(img, extent, false, &paint, |r, mask, w| {
+            r.draw_filled_arc(mask, w.circle(circle), start, end, MASK)
+        });
+    }
+
+    fn draw_ellipse(&self, img: &mut Self::Image, ellipse: Ellipse, color: Self::Pixel) {
+        let radius = ellipse.radius_x().max(ellipse.radius_y());
+        let extent = circle_extent(ellipse.center(), radius);
+
+        self.draw(img, extent, true, &Paint::Solid(color), |r, mask, w| {
+            r.draw_ellipse(mask, w.ellipse(ellipse), MASK)
+        });
+    }
+
+    fn draw_filled_ellipse<'a>(
+        &self,
+        img: &mut Self::Image,
+        ellipse: Ellipse,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = paint.into();
+
+        let radius = ellipse.radius_x().max(ellipse.radius_y());
+        let extent = circle_extent(ellipse.center(), radius);
+
+        self.draw(img, extent, false, &paint, |r, mask, w| {
+            r.draw_filled_ellipse(mask, w.ellipse(ellipse), MASK)
+        });
+    }
+
+    fn draw_elliptical_arc(
+        &self,
+        img: &mut Self::Image,
+        ellipse: Ellipse,
+        start: Angle,
+        end: Angle,
+        color: Self::Pixel,
+    ) {
+        let radius = ellipse.radius_x().max(ellipse.radius_y());
+        let extent = circle_extent(ellipse.center(), radius);
+
+        self.draw(img, extent, true, &Paint::Solid(color), |r, mask, w| {
+            r.draw_elliptical_arc(mask, w.ellipse(ellipse), start, end, MASK)
+        });
+    }
+
+    fn draw_filled_elliptical_arc<'a>(
+        &self,
+        img: &mut Self::Image,
+        ellipse: Ellipse,
+        start: Angle,
+        end: Angle,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = paint.into();
+
+        let radius = ellipse.radius_x().max(ellipse.radius_y());
+        let extent = circle_extent(ellipse.center(), radius);
+
+        self.draw(img, extent, false, &paint, |r, mask, w| {
+            r.draw_filled_elliptical_arc(mask, w.ellipse(ellipse), start, end, MASK)
+        });
+    }
+
+    fn draw_rounded_rect(
+        &self,
+        img: &mut Self::Image,
+        rect: Rect,
+        corner_radius: f64,
+        color: Self::Pixel,
+    ) {
+        self.draw(
+            img,
+            rect_extent(rect),
+            true,
+            &Paint::Solid(color),
+            |r, mask, w| r.draw_rounded_rect(mask, w.rect(rect), w.length(corner_radius), MASK),
+        );
+    }
+
+    fn draw_filled_rounded_rect<'a>(
+        &self,
+        img: &mut Self::Image,
+        rect: Rect,
+        corner_radius: f64,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = paint.into();
+
+        self.draw(img, rect_extent(rect), false, &paint, |r, mask, w| {
+            r.draw_filled_rounded_rect(mask, w.rect(rect), w.length(corner_radius), MASK)
+        });
+    }
+
+    fn draw_polyline(&self, img: &mut Self::Image, points: &[Point], color: Self::Pixel) {
+        self.draw(
+            img,
+            points.iter().copied(),
+            true,
+            &Paint::Solid(color),
+            |r, mask, w| r.draw_polyline(mask, &w.points(points), MASK),
+        );
+    }
+
+    fn draw_polygon(&self, img: &mut Self::Image, points: &[Point], color: Self::Pixel) {
+        self.draw(
+            img,
+            points.iter().copied(),
+            true,
+            &Paint::Solid(color),
+            |r, mask, w| r.draw_polygon(mask, &w.points(points), MASK),
+        );
+    }
+
+    fn draw_filled_polygon<'a>(
+        &self,
+        img: &mut Self::Image,
+        points: &[Point],
+        rule: FillRule,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = paint.into();
+
+        self.draw(img, points.iter().copied(), false, &paint, |r, mask, w| {
+            r.draw_filled_polygon(mask, &w.points(points), rule, MASK)
+        });
+    }
+
+    fn stroke_path(&self, img: &mut Self::Image, path: &Path, color: Self::Pixel) {
+        self.draw(
+            img,
+            path.points(),
+            true,
+            &Paint::Solid(color),
+            |r, mask, w| r.stroke_path(mask, &w.path(path), MASK),
+        );
+    }
+
+    fn fill_path<'a>(
+        &self,
+        img: &mut Self::Image,
+        path: &Path,
+        rule: FillRule,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = paint.into();
+
+        self.draw(img, path.points(), false, &paint, |r, mask, w| {
+            r.fill_path(mask, &w.path(path), rule, MASK)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{basic::BasicRenderer, coverage::CoverageRenderer};
+
+    use super::*;
+
+    #[test]
+    fn filter_taps_add_up_to_the_factor() {
+        for factor in 1..6 {
+            for filter in [Filter::Box, Filter::Tent] {
+                let sum: f64 = filter.taps(factor).iter().map(|(_, w)| w).sum();
+                assert!((sum - factor as f64).abs() < 1e-9);
+            }
+        }
+
+        assert_eq!(Filter::Tent.taps(1), vec![(0, 1f64)]);
+        assert_eq!(
+            Filter::Tent.taps(2),
+            vec![(-1, 0.25f64), (0, 0.75f64), (1, 0.75f64), (2, 0.25f64)]
+        );
+    }
+
+    #[test]
+    fn huge_factors_are_clamped() {
+        let red = image::Rgba([255, 0, 0, 255]);
+
+        let renderer = SupersampledRenderer::new(BasicRenderer::default(), u32::MAX);
+        assert_eq!(renderer.factor(), MAX_FACTOR);
+
+        let mut img = image::RgbaImage::new(40, 40);
+        renderer.draw_filled_rect(&mut img, Rect::new(10f64, 10f64, 20f64, 20f64), red);
+        assert_eq!(*img.get_pixel(20, 20), red);
+    }
+
+    #[test]
+    fn supersampling_matches_anti_aliasing() {
+        let red = image::Rgba([255, 0, 0, 255]);
+        let square = [
+            Point::new(1.5f64, 1.5f64),
+            Point::new(5f64, 1.5f64),
+            Point::new(5f64, 4.5f64),
+            Point::new(1.5f64, 4.5f64),
+        ];
+
+        let mut img = image::RgbaImage::new(8, 8);
+        SupersampledRenderer::new(BasicRenderer::default(), 4).draw_filled_polygon(
+            &mut img,
+            &square,
+            FillRule::NonZero,
+            red,
+        );
+        assert_eq!(*img.get_pixel(2, 2), red);
+        assert_eq!(*img.get_pixel(5, 3), image::Rgba([255, 0, 0, 128]));
+        assert_eq!(img.get_pixel(1, 3)[3], 0);
+
+        let circle = Circle::new((20.3f64, 19.6f64), 12.4f64);
+
+        let mut reference = image::RgbaImage::new(40, 40);
+        SupersampledRenderer::new(BasicRenderer::default(), 16)
+            .with_filter(Filter::Box)
+            .draw_filled_circle(&mut reference, circle, red);
+
+        // Flattening the circle can cost up to a tenth of a pixel's coverage.
+        let mut img = image::RgbaImage::new(40, 40);
+        CoverageRenderer::default().draw_filled_circle(&mut img, circle, red);
+        for (a, b) in img.pixels().zip(reference.pixels()) {
+            assert!((a[3] as i32 - b[3] as i32).abs() <= 26);
+        }
+
+        let mut img = image::RgbaImage::new(40, 40);
+        SupersampledRenderer::new(BasicRenderer::default(), 4)
+            .with_filter(Filter::Tent)
+            .with_stroke(Stroke::new(3f64))
+            .draw_circle(&mut img, circle, red);
+        assert_eq!(img.get_pixel(20, 20)[3], 0);
+        assert_eq!(*img.get_pixel(8, 20), red);
+    }
+}
@@ -0,0 +1,473 @@
+use std::{error::Error, fmt, str::FromStr};
+
+use crate::{
+    path::Path,
+    types::{Angle, Point},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    UnexpectedCharacter(char),
+    ExpectedNumber,
+    ExpectedFlag,
+    ExpectedMoveTo,
+}
+
+/// Error parsing SVG path data, pointing at the byte offset where it
+/// happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    offset: usize,
+    kind: ParseErrorKind,
+}
+
+impl ParseError {
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn kind(&self) -> &ParseErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ParseErrorKind::UnexpectedCharacter(c) => {
+                write!(f, "unexpected character {:?} at byte {}", c, self.offset)
+            }
+            ParseErrorKind::ExpectedNumber => {
+                write!(f, "expected a number at byte {}", self.offset)
+            }
+            ParseErrorKind::ExpectedFlag => {
+                write!(f, "expected a 0 or 1 flag at byte {}", self.offset)
+            }
+            ParseErrorKind::ExpectedMoveTo => {
+                write!(f, "expected a moveto command at byte {}", self.offset)
+            }
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+impl FromStr for Path {
+    type Err = ParseError;
+
+    fn from_str(d: &str) -> Result<Self, Self::Err> {
+        parse_path(d)
+    }
+}
+
+/// Parses the SVG path mini-language of a `d` attribute, e.g.
+/// `"M10 10 h 20 a5 5 0 0 1 5 5 Z"`.
+pub fn parse_path(d: &str) -> Result<Path, ParseError> {
+    Parser::new(d).parse()
+}
+
+struct Parser<'a> {
+    data: &'a str,
+    pos: usize,
+    path: Path,
+    current: Point,
+    start: Point,
+    cubic_control: Option<Point>,
+    quad_control: Option<Point>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(data: &'a str) -> Self {
+        Self {
+            data,
+            pos: 0,
+            path: Path::new(),
+            current: Point::default(),
+            start: Point::default(),
+            cubic_control: None,
+            quad_control: None,
+        }
+    }
+
+    fn parse(mut self) -> Result<Path, ParseError> {
+        self.skip_whitespace();
+
+        let mut command = match self.peek() {
+            None => return Ok(self.path),
+            Some(b'M' | b'm') => b'M',
+            Some(_) => return Err(self.error(ParseErrorKind::ExpectedMoveTo)),
+        };
+
+        loop {
+            self.skip_whitespace();
+
+            match self.peek() {
+                None => return Ok(self.path),
+                Some(c) if c.is_ascii_alphabetic() => {
+                    command = c;
+                    self.pos += 1;
+                }
+                Some(_) if self.at_number() && !matches!(command, b'Z' | b'z') => {
+                    command = match command {
+                        b'M' => b'L',
+                        b'm' => b'l',
+                        c => c,
+                    };
+                }
+                Some(_) => return Err(self.unexpected()),
+            }
+
+            self.command(command)?;
+        }
+    }
+
+    fn command(&mut self, command: u8) -> Result<(), ParseError> {
+        let relative = command.is_ascii_lowercase();
+        let origin = if relative {
+            self.current
+        } else {
+            Point::default()
+        };
+
+        let (mut cubic_control, mut quad_control) = (None, None);
+
+        match command.to_ascii_uppercase() {
+            b'M' => {
+                let p = self.point(origin)?;
+
+                self.path.move_to(p);
+                self.start = p;
+                self.current = p;
+            }
+            b'L' => {
+                let p = self.point(origin)?;
+
+                self.path.line_to(p);
+                self.current = p;
+            }
+            b'H' => {
+                let x = self.number()? + origin.x;
+                let p = Point::new(x, self.current.y);
+
+                self.path.line_to(p);
+                self.current = p;
+            }
+            b'V' => {
+                let y = self.number()? + origin.y;
+                let p = Point::new(self.current.x, y);
+
+                self.path.line_to(p);
+                self.current = p;
+            }
+            b'C' => {
+                let c1 = self.point(origin)?;
+                let c2 = self.point(origin)?;
+                let p = self.point(origin)?;
+
+                self.path.cubic_to(c1, c2, p);
+                self.current = p;
+                cubic_control = Some(c2);
+            }
+            b'S' => {
+                let c1 = reflect(self.cubic_control, self.current);
+                let c2 = self.point(origin)?;
+                let p = self.point(origin)?;
+
+                self.path.cubic_to(c1, c2, p);
+                self.current = p;
+                cubic_control = Some(c2);
+            }
+            b'Q' => {
+                let c = self.point(origin)?;
+                let p = self.point(origin)?;
+
+                self.path.quad_to(c, p);
+                self.current = p;
+                quad_control = Some(c);
+            }
+            b'T' => {
+                let c = reflect(self.quad_control, self.current);
+                let p = self.point(origin)?;
+
+                self.path.quad_to(c, p);
+                self.current = p;
+                quad_control = Some(c);
+            }
+            b'A' => {
+                let rx = self.number()?;
+                let ry = self.number()?;
+                let rotation = self.number()?;
+                let large_arc = self.flag()?;
+                let sweep = self.flag()?;
+                let p = self.point(origin)?;
+
+                self.path
+                    .svg_arc_to((rx, ry), Angle::Degrees(rotation), large_arc, sweep, p);
+                self.current = p;
+            }
+            b'Z' => {
+                self.path.close();
+                self.current = self.start;
+            }
+            _ => {
+                self.pos -= 1;
+                return Err(self.unexpected());
+            }
+        }
+
+        self.cubic_control = cubic_control;
+        self.quad_control = quad_control;
+
+        Ok(())
+    }
+
+    fn point(&mut self, origin: Point) -> Result<Point, ParseError> {
+        let x = self.number()?;
+        let y = self.number()?;
+
+        Ok(Point::new(origin.x + x, origin.y + y))
+    }
+
+    fn number(&mut self) -> Result<f64, ParseError> {
+        self.skip_whitespace();
+
+        let bytes = self.data.as_bytes();
+        let start = self.pos;
+        let mut end = start;
+
+        let digits = |mut i: usize| {
+            while i < bytes.len() && bytes[i].is_ascii_digit() {
+                i += 1;
+            }
+            i
+        };
+
+        if end < bytes.len() && matches!(bytes[end], b'+' | b'-') {
+            end += 1;
+        }
+
+        let integer_end = digits(end);
+        let mut mantissa_digits = integer_end - end;
+        end = integer_end;
+
+        if end < bytes.len() && bytes[end] == b'.' {
+            let fraction_end = digits(end + 1);
+            mantissa_digits += fraction_end - end - 1;
+            end = fraction_end;
+        }
+
+        if mantissa_digits == 0 {
+            return Err(self.error(ParseErrorKind::ExpectedNumber));
+        }
+
+        if end < bytes.len() && matches!(bytes[end], b'e' | b'E') {
+            let mut exponent = end + 1;
+            if exponent < bytes.len() && matches!(bytes[exponent], b'+' | b'-') {
+                exponent += 1;
+            }
+
+            let exponent_end = digits(exponent);
+            if exponent_end > exponent {
+                end = exponent_end;
+            }
+        }
+
+        let value = self.data[start..end]
+            .parse()
+            .map_err(|_| self.error(ParseErrorKind::ExpectedNumber))?;
+
+        self.pos = end;
+        self.skip_separator();
+
+        Ok(value)
+    }
+
+    fn flag(&mut self) -> Result<bool, ParseError> {
+        self.skip_whitespace();
+
+        let flag = match self.peek() {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err(self.error(ParseErrorKind::ExpectedFlag)),
+        };
+
+        self.pos += 1;
+        self.skip_separator();
+
+        Ok(flag)
+    }
+
+    fn at_number(&self) -> bool {
+        matches!(self.peek(), Some(b'0'..=b'9' | b'.' | b'-' | b'+'))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r' | b'\x0C')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_separator(&mut self) {
+        self.skip_whitespace();
+
+        if self.peek() == Some(b',') {
+            self.pos += 1;
+            self.skip_whitespace();
+        }
+    }
+
+    fn unexpected(&self) -> ParseError {
+        let c = self.data[self.pos..].chars().next().unwrap_or_default();
+        self.error(ParseErrorKind::UnexpectedCharacter(c))
+    }
+
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            offset: self.pos,
+            kind,
+        }
+    }
+}
+
+/// Reflection of the previous control point about the current point, or the
+/// current point itself when the previous command wasn't a matching curve.
+fn reflect(control: Option<Point>, current: Point) -> Point {
+    match control {
+        Some(c) => Point::new(2f64 * current.x - c.x, 2f64 * current.y - c.y),
+        None => current,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::path::PathEl;
+    use crate::{antialiased::AntiAliasingRender, basic::BasicRenderer, types::FillRule, Renderer};
+
+    use super::*;
+
+    #[test]
+    fn parses_absolute_and_relative_commands() {
+        let path: Path = "M10,10 h20 v 20 H10 z m5 5 l5-5 L 30 30".parse().unwrap();
+
+        assert_eq!(
+            path.elements(),
+            &[
+                PathEl::MoveTo(Point::new(10f64, 10f64)),
+                PathEl::LineTo(Point::new(30f64, 10f64)),
+                PathEl::LineTo(Point::new(30f64, 30f64)),
+                PathEl::LineTo(Point::new(10f64, 30f64)),
+                PathEl::Close,
+                PathEl::MoveTo(Point::new(15f64, 15f64)),
+                PathEl::LineTo(Point::new(20f64, 10f64)),
+                PathEl::LineTo(Point::new(30f64, 30f64)),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_compact_numbers_and_implicit_commands() {
+        let path = parse_path("M.5.5-1-1 1e1,2E-1").unwrap();
+
+        assert_eq!(
+            path.elements(),
+            &[
+                PathEl::MoveTo(Point::new(0.5f64, 0.5f64)),
+                PathEl::LineTo(Point::new(-1f64, -1f64)),
+                PathEl::LineTo(Point::new(10f64, 0.2f64)),
+            ]
+        );
+    }
+
+    #[test]
+    fn reflects_smooth_curve_controls() {
+        let path = parse_path("M0 0 C 0 10 10 10 10 0 s 10 -10 10 0 Q 25 5 30 0 t 10 0").unwrap();
+
+        assert_eq!(
+            path.elements()[2],
+            PathEl::CubicTo(
+                Point::new(10f64, -10f64),
+                Point::new(20f64, -10f64),
+                Point::new(20f64, 0f64)
+            )
+        );
+        assert_eq!(
+            path.elements()[4],
+            PathEl::QuadTo(Point::new(35f64, -5f64), Point::new(40f64, 0f64))
+        );
+
+        let path = parse_path("M0 0 L 10 0 S 20 10 30 0").unwrap();
+        assert_eq!(
+            path.elements()[2],
+            PathEl::CubicTo(
+                Point::new(10f64, 0f64),
+                Point::new(20f64, 10f64),
+                Point::new(30f64, 0f64)
+            )
+        );
+    }
+
+    #[test]
+    fn parses_arcs_with_packed_flags() {
+        let path = parse_path("M0 10 a10 10 0 0110-10").unwrap();
+
+        assert!(matches!(path.elements()[1], PathEl::CubicTo(..)));
+        assert_eq!(path.current_point(), Some(Point::new(10f64, 0f64)));
+    }
+
+    #[test]
+    fn reports_errors_with_offsets() {
+        let error = parse_path("L 10 10").unwrap_err();
+        assert_eq!(error.kind(), &ParseErrorKind::ExpectedMoveTo);
+        assert_eq!(error.offset(), 0);
+
+        let error = parse_path("M 10 10 L 20 x").unwrap_err();
+        assert_eq!(error.kind(), &ParseErrorKind::ExpectedNumber);
+        assert_eq!(error.offset(), 13);
+        assert_eq!(error.to_string(), "expected a number at byte 13");
+
+        let error = parse_path("M 10 10 A 5 5 0 2 1 0 0").unwrap_err();
+        assert_eq!(error.kind(), &ParseErrorKind::ExpectedFlag);
+        assert_eq!(error.offset(), 16);
+
+        let error = parse_path("M 10 10 Z 5").unwrap_err();
+        assert_eq!(error.kind(), &ParseErrorKind::UnexpectedCharacter('5'));
+        assert_eq!(error.offset(), 10);
+
+        let error = parse_path("M 10 10 é").unwrap_err();
+        assert_eq!(error.kind(), &ParseErrorKind::UnexpectedCharacter('é'));
+
+        let error = parse_path("M 10 10 K").unwrap_err();
+        assert_eq!(error.kind(), &ParseErrorKind::UnexpectedCharacter('K'));
+        assert_eq!(error.offset(), 8);
+
+        assert!(parse_path("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn svg_path_data_renders() {
+        let red = image::Rgba([255, 0, 0, 255]);
+        let clear = image::Rgba([0, 0, 0, 0]);
+
+        // A rounded square with a square hole, drawn in the opposite direction.
+        let icon: Path = "M8 4h24a4 4 0 0 1 4 4v24a4 4 0 0 1-4 4H8a4 4 0 0 1-4-4V8a4 4 0 0 1 4-4z\
+                          M14 14v12h12V14z"
+            .parse()
+            .unwrap();
+
+        let mut img = image::RgbaImage::new(40, 40);
+        BasicRenderer::default().fill_path(&mut img, &icon, FillRule::NonZero, red);
+        assert_eq!(*img.get_pixel(6, 20), red);
+        assert_eq!(*img.get_pixel(20, 20), clear);
+        assert_eq!(*img.get_pixel(4, 4), clear);
+
+        let mut img = image::RgbaImage::new(40, 40);
+        AntiAliasingRender::default().fill_path(&mut img, &icon, FillRule::NonZero, red);
+        assert_eq!(*img.get_pixel(6, 20), red);
+        assert_eq!(*img.get_pixel(20, 20), clear);
+        assert!(img.get_pixel(5, 5)[3] > 0 && img.get_pixel(5, 5)[3] < 255);
+    }
+}
@@ -0,0 +1,50 @@
+use image::{GenericImage, GenericImageView, Pixel};
+
+use crate::{
+    color::Channel,
+    composite::{composite_channels, Composite},
+};
+
+/// What renderers draw onto: a grid of pixels they can composite colors
+/// into.
+///
+/// Every [`GenericImage`] whose subpixels are a [`Channel`] is a target, and
+/// other pixel stores can implement it to take over blending.
+pub trait Target {
+    type Pixel: Pixel<Subpixel: Channel> + 'static;
+
+    fn size(&self) -> (u32, u32);
+
+    /// Composites `color` onto the pixel at `x`, `y` as if it covered
+    /// `coverage` of its area. Callers keep `x`, `y` within [`size`] and
+    /// `coverage` within `0..=1`.
+    ///
+    /// [`size`]: Target::size
+    fn blend(&mut self, x: u32, y: u32, color: Self::Pixel, coverage: f64, composite: Composite);
+}
+
+impl<I> Target for I
+where
+    I: GenericImage,
+    I::Pixel: 'static,
+    <I::Pixel as Pixel>::Subpixel: Channel,
+{
+    type Pixel = <I as GenericImageView>::Pixel;
+
+    fn size(&self) -> (u32, u32) {
+        self.dimensions()
+    }
+
+    fn blend(&mut self, x: u32, y: u32, color: Self::Pixel, coverage: f64, composite: Composite) {
+        let mut pixel = self.get_pixel(x, y);
+
+        composite_channels(
+            pixel.channels_mut(),
+            color.channels(),
+            Self::Pixel::COLOR_TYPE.has_alpha(),
+            coverage,
+            composite,
+        );
+        self.put_pixel(x, y, pixel);
+    }
+}
@@ -0,0 +1,835 @@
+use std::{collections::HashMap, error::Error, fmt, io, sync::OnceLock};
+
+use crate::{
+    color::{blend_coverage, Blending},
+    geom::pixel,
+    target::Target,
+    types::{Point, Rect},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FontErrorKind {
+    UnexpectedEnd,
+    BadMagic,
+    ExpectedNumber,
+    ExpectedBitmap,
+    MissingBoundingBox,
+    /// The font data is damaged or not a font at all, like TrueType data
+    /// that doesn't parse or a PSF header with empty glyphs.
+    Malformed,
+    /// The font file couldn't be read.
+    Io(io::ErrorKind),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FontError {
+    offset: usize,
+    kind: FontErrorKind,
+}
+
+impl FontError {
+    pub(crate) fn new(offset: usize, kind: FontErrorKind) -> Self {
+        Self { offset, kind }
+    }
+
+    /// The byte the error was found at; the start of the offending line for
+    /// BDF fonts.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn kind(&self) -> &FontErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            FontErrorKind::UnexpectedEnd => {
+                write!(f, "font data ends early at byte {}", self.offset)
+            }
+            FontErrorKind::BadMagic => write!(f, "not a PSF font"),
+            FontErrorKind::ExpectedNumber => {
+                write!(f, "expected a number at byte {}", self.offset)
+            }
+            FontErrorKind::ExpectedBitmap => {
+                write!(f, "expected a bitmap row at byte {}", self.offset)
+            }
+            FontErrorKind::MissingBoundingBox => {
+                write!(f, "glyph without a bounding box at byte {}", self.offset)
+            }
+            FontErrorKind::Malformed => write!(f, "malformed font data"),
+            FontErrorKind::Io(kind) => write!(f, "can't read the font: {}", kind),
+        }
+    }
+}
+
+impl Error for FontError {}
+
+impl From<io::Error> for FontError {
+    fn from(error: io::Error) -> Self {
+        FontError {
+            offset: 0,
+            kind: FontErrorKind::Io(error.kind()),
+        }
+    }
+}
+
+/// A typeface at a fixed size, in pixels.
+///
+/// Text is laid out along a baseline, glyphs standing on it and descenders
+/// going below it.
+pub trait Font {
+    /// Height of the font above the baseline.
+    fn ascent(&self) -> f64;
+
+    /// Depth of the font below the baseline.
+    fn descent(&self) -> f64;
+
+    /// Distance between the baselines of consecutive lines.
+    fn line_height(&self) -> f64 {
+        self.ascent() + self.descent()
+    }
+
+    fn has_glyph(&self, c: char) -> bool;
+
+    /// How far drawing `c` moves the pen.
+    fn advance(&self, c: char) -> f64;
+
+    /// The adjustment to the advance of `left` when `right` follows it,
+    /// negative to move the two closer.
+    fn kerning(&self, _left: char, _right: char) -> f64 {
+        0f64
+    }
+
+    /// The width and height of `text` drawn on a single line.
+    fn measure(&self, text: &str) -> (f64, f64) {
+        let mut width = 0f64;
+        let mut previous = None;
+
+        for c in text.chars() {
+            if let Some(previous) = previous {
+                width += self.kerning(previous, c);
+            }
+
+            width += self.advance(c);
+            previous = Some(c);
+        }
+
+        (width, self.line_height())
+    }
+
+    /// Calls `plot` with each pixel of an image `size` large covered by
+    /// `text` and how much of it, the pen starting at `origin` on the
+    /// baseline.
+    fn rasterize(
+        &self,
+        text: &str,
+        origin: Point,
+        size: (u32, u32),
+        plot: &mut dyn FnMut(i64, i64, f64),
+    );
+}
+
+/// Horizontal position of text relative to the point it's drawn at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// Vertical position of text relative to the point it's drawn at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Baseline {
+    /// The point is on top of the font's ascent.
+    Top,
+    /// The point is halfway between the top and bottom.
+    Middle,
+    /// The point is on the line letters stand on, descenders going below it.
+    #[default]
+    Alphabetic,
+    /// The point is below the font's descent.
+    Bottom,
+}
+
+#[derive(Debug, Clone)]
+struct Glyph {
+    width: u32,
+    height: u32,
+    /// Offset of the bitmap's left edge from the pen position.
+    left: i64,
+    /// Height of the bitmap's bottom row above the baseline.
+    bottom: i64,
+    advance: i64,
+    bits: Vec<bool>,
+}
+
+impl Glyph {
+    /// The set pixels, relative to the pen position on the baseline with y
+    /// growing downwards; row -1 is the one just above the baseline.
+    fn pixels(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        let top = -self.bottom - self.height as i64;
+
+        self.bits
+            .iter()
+            .enumerate()
+            .filter(|(_, &set)| set)
+            .map(move |(i, _)| {
+                let (col, row) = (i as u32 % self.width, i as u32 / self.width);
+                (self.left + col as i64, top + row as i64)
+            })
+    }
+}
+
+/// Fixed size font made of one bitmap per character, loaded from BDF or
+/// PSF data.
+///
+/// Characters the font has no glyph for are drawn as its default glyph, if
+/// it has one, and skipped otherwise.
+#[derive(Debug, Clone)]
+pub struct BitmapFont {
+    glyphs: HashMap<char, Glyph>,
+    default_glyph: Option<Glyph>,
+    ascent: u32,
+    descent: u32,
+}
+
+static BUILTIN: OnceLock<BitmapFont> = OnceLock::new();
+
+impl BitmapFont {
+    /// The bundled 8×16 font covering printable ASCII, with 12 pixels above
+    /// the baseline and 4 below it.
+    pub fn builtin() -> &'static BitmapFont {
+        BUILTIN.get_or_init(|| {
+            BitmapFont::parse_bdf(include_str!("../fonts/8x16.bdf"))
+                .expect("the bundled font is valid")
+        })
+    }
+
+    /// Reads a font in the Glyph Bitmap Distribution Format, taking glyph
+    /// encodings as Unicode code points.
+    pub fn parse_bdf(data: &str) -> Result<Self, FontError> {
+        BdfParser::new(data).parse()
+    }
+
+    /// Reads a PC Screen Font, version 1 or 2.
+    ///
+    /// Glyphs map to the characters listed in the font's Unicode table or,
+    /// without one, to the characters with their index as code point. PSF
+    /// fonts don't record a baseline, so the bottom quarter of the glyphs is
+    /// taken to be below it.
+    pub fn parse_psf(data: &[u8]) -> Result<Self, FontError> {
+        parse_psf(data)
+    }
+
+    fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs.get(&c).or(self.default_glyph.as_ref())
+    }
+}
+
+impl Font for BitmapFont {
+    fn ascent(&self) -> f64 {
+        self.ascent as f64
+    }
+
+    fn descent(&self) -> f64 {
+        self.descent as f64
+    }
+
+    fn has_glyph(&self, c: char) -> bool {
+        self.glyphs.contains_key(&c)
+    }
+
+    fn advance(&self, c: char) -> f64 {
+        self.glyph(c).map_or(0f64, |g| g.advance as f64)
+    }
+
+    /// Glyphs are drawn whole, from the pixel nearest to `origin`.
+    fn rasterize(
+        &self,
+        text: &str,
+        origin: Point,
+        _size: (u32, u32),
+        plot: &mut dyn FnMut(i64, i64, f64),
+    ) {
+        let (mut pen, baseline) = (pixel(origin.x), pixel(origin.y));
+
+        for glyph in text.chars().filter_map(|c| self.glyph(c)) {
+            for (x, y) in glyph.pixels() {
+                plot(pen + x, baseline + y, 1f64);
+            }
+
+            pen += glyph.advance;
+        }
+    }
+}
+
+/// How to draw text: the font and where the text goes relative to the point
+/// it's drawn at.
+#[derive(Clone, Copy)]
+pub struct TextStyle<'f> {
+    font: &'f dyn Font,
+    align: TextAlign,
+    baseline: Baseline,
+}
+
+impl Default for TextStyle<'static> {
+    fn default() -> Self {
+        Self::new(BitmapFont::builtin())
+    }
+}
+
+impl<'f> TextStyle<'f> {
+    /// Left aligned text standing on the point it's drawn at.
+    pub fn new(font: &'f dyn Font) -> Self {
+        Self {
+            font,
+            align: TextAlign::default(),
+            baseline: Baseline::default(),
+        }
+    }
+
+    pub fn with_align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn with_baseline(mut self, baseline: Baseline) -> Self {
+        self.baseline = baseline;
+        self
+    }
+
+    pub fn font(&self) -> &'f dyn Font {
+        self.font
+    }
+
+    pub fn align(&self) -> TextAlign {
+        self.align
+    }
+
+    pub fn baseline(&self) -> Baseline {
+        self.baseline
+    }
+
+    /// The width and height of `text` on a single line.
+    pub fn measure(&self, text: &str) -> (f64, f64) {
+        self.font.measure(text)
+    }
+
+    /// The area `text` drawn at `at` takes up, from the top of the font's
+    /// ascent to the bottom of its descent.
+    pub fn bounds(&self, text: &str, at: impl Into<Point>) -> Rect {
+        let (width, height) = self.measure(text);
+        let origin = self.origin(text, at.into());
+
+        Rect::new(
+            origin.x - 0.5f64,
+            origin.y - self.font.ascent() - 0.5f64,
+            width,
+            height,
+        )
+    }
+
+    /// Where the pen starts, on the baseline.
+    ///
+    /// Like shapes, glyphs are placed with pixel centers at integers: text
+    /// drawn at the origin starts at the left edge of pixel 0 and stands on
+    /// the top edge of row 0.
+    fn origin(&self, text: &str, at: Point) -> Point {
+        let font = self.font;
+        let (width, _) = font.measure(text);
+
+        let x = match self.align {
+            TextAlign::Left => at.x,
+            TextAlign::Center => at.x - width / 2f64,
+            TextAlign::Right => at.x - width,
+        };
+
+        let y = match self.baseline {
+            Baseline::Top => at.y + font.ascent(),
+            Baseline::Middle => at.y + (font.ascent() - font.descent()) / 2f64,
+            Baseline::Alphabetic => at.y,
+            Baseline::Bottom => at.y - font.descent(),
+        };
+
+        Point::new(x, y)
+    }
+}
+
+/// Composites `text` onto `img` in `color`, on a single line placed at `at`
+/// as `style` says.
+pub fn draw_text<T: Target>(
+    img: &mut T,
+    text: &str,
+    at: impl Into<Point>,
+    style: &TextStyle,
+    color: T::Pixel,
+) {
+    render_text(img, text, at.into(), style, color, Blending::default());
+}
+
+pub(crate) fn render_text<T: Target>(
+    img: &mut T,
+    text: &str,
+    at: Point,
+    style: &TextStyle,
+    color: T::Pixel,
+    blending: Blending,
+) {
+    let origin = style.origin(text, at);
+    blend_text(img, style.font, text, origin, color, blending);
+}
+
+/// Composites `text` in `font`, the pen starting at `origin`.
+pub(crate) fn blend_text<T: Target>(
+    img: &mut T,
+    font: &dyn Font,
+    text: &str,
+    origin: Point,
+    color: T::Pixel,
+    blending: Blending,
+) {
+    let size = img.size();
+
+    font.rasterize(text, origin, size, &mut |x, y, coverage| {
+        blend_coverage(img, x, y, color, coverage, blending)
+    });
+}
+
+struct BdfParser<'a> {
+    lines: std::iter::Peekable<std::str::Lines<'a>>,
+    data: &'a str,
+    offset: usize,
+}
+
+impl<'a> BdfParser<'a> {
+    fn new(data: &'a str) -> Self {
+        Self {
+            lines: data.lines().peekable(),
+            data,
+            offset: 0,
+        }
+    }
+
+    fn parse(mut self) -> Result<BitmapFont, FontError> {
+        let mut font = BitmapFont {
+            glyphs: HashMap::new(),
+            default_glyph: None,
+            ascent: 0,
+            descent: 0,
+        };
+        let (mut ascent, mut descent, mut default_char) = (None, None, None);
+        let mut bounding_box = None;
+        let mut glyphs = Vec::new();
+
+        while let Some((keyword, args)) = self.next_line() {
+            match keyword {
+                "FONTBOUNDINGBOX" => bounding_box = Some(self.numbers::<4>(args)?),
+                "FONT_ASCENT" => ascent = Some(self.numbers::<1>(args)?[0]),
+                "FONT_DESCENT" => descent = Some(self.numbers::<1>(args)?[0]),
+                "DEFAULT_CHAR" => default_char = Some(self.numbers::<1>(args)?[0]),
+                "STARTCHAR" => glyphs.push(self.glyph()?),
+                "ENDFONT" => break,
+                _ => {}
+            }
+        }
+
+        let [_, height, _, bottom] = bounding_box.unwrap_or([0, 0, 0, 0]);
+        font.ascent = ascent.unwrap_or(height + bottom).max(0) as u32;
+        font.descent = descent.unwrap_or(-bottom).max(0) as u32;
+
+        for (encoding, glyph) in glyphs {
+            if default_char.is_some() && encoding == default_char {
+                font.default_glyph = Some(glyph.clone());
+            }
+
+            if let Some(c) = encoding.and_then(|e| char::from_u32(e as u32)) {
+                font.glyphs.insert(c, glyph);
+            }
+        }
+
+        Ok(font)
+    }
+
+    fn glyph(&mut self) -> Result<(Option<i64>, Glyph), FontError> {
+        let (mut encoding, mut advance, mut bbx) = (None, None, None);
+
+        loop {
+            let (keyword, args) = self.next_line().ok_or_else(|| self.end())?;
+
+            match keyword {
+                "ENCODING" => {
+                    let mut numbers = args.split_whitespace().map(|n| n.parse::<i64>());
+                    encoding = match (numbers.next(), numbers.next()) {
+                        (Some(Ok(-1)), Some(Ok(e))) => Some(e),
+                        (Some(Ok(-1)), _) => None,
+                        (Some(Ok(e)), _) => Some(e),
+                        _ => return Err(self.error(FontErrorKind::ExpectedNumber)),
+                    };
+                }
+                "DWIDTH" => advance = Some(self.numbers::<1>(args)?[0]),
+                "BBX" => bbx = Some(self.numbers::<4>(args)?),
+                "BITMAP" => break,
+                _ => {}
+            }
+        }
+
+        let [width, height, left, bottom] =
+            bbx.ok_or_else(|| self.error(FontErrorKind::MissingBoundingBox))?;
+        let (width, height) = (width.max(0) as u32, height.max(0) as u32);
+
+        // The bounding box can claim any size, so the bits grow with the rows
+        // actually there.
+        let mut bits = Vec::new();
+        for _ in 0..height {
+            let (row, _) = self.next_line().ok_or_else(|| self.end())?;
+            let bytes = (0..row.len() / 2)
+                .map(|i| {
+                    row.get(2 * i..2 * i + 2)
+                        .and_then(|b| u8::from_str_radix(b, 16).ok())
+                })
+                .collect::<Option<Vec<_>>>()
+                .filter(|bytes| bytes.len() * 8 >= width as usize)
+                .ok_or_else(|| self.error(FontErrorKind::ExpectedBitmap))?;
+
+            bits.extend((0..width as usize).map(|x| bytes[x / 8] & (0x80 >> (x % 8)) != 0));
+        }
+
+        while let Some((keyword, _)) = self.next_line() {
+            if keyword == "ENDCHAR" {
+                break;
+            }
+        }
+
+        let glyph = Glyph {
+            width,
+            height,
+            left,
+            bottom,
+            advance: advance.unwrap_or(width as i64),
+            bits,
+        };
+
+        Ok((encoding, glyph))
+    }
+
+    /// The keyword and arguments of the next non-empty line.
+    fn next_line(&mut self) -> Option<(&'a str, &'a str)> {
+        loop {
+            let line = self.lines.next()?;
+            self.offset = line.as_ptr() as usize - self.data.as_ptr() as usize;
+
+            let line = line.trim();
+            if !line.is_empty() {
+                return Some(line.split_once(' ').unwrap_or((line, "")));
+            }
+        }
+    }
+
+    fn numbers<const N: usize>(&self, args: &str) -> Result<[i64; N], FontError> {
+        let mut numbers = [0; N];
+        let mut args = args.split_whitespace();
+
+        for n in &mut numbers {
+            *n = args
+                .next()
+                .and_then(|a| a.parse().ok())
+                .ok_or_else(|| self.error(FontErrorKind::ExpectedNumber))?;
+        }
+
+        Ok(numbers)
+    }
+
+    fn end(&self) -> FontError {
+        FontError {
+            offset: self.data.len(),
+            kind: FontErrorKind::UnexpectedEnd,
+        }
+    }
+
+    fn error(&self, kind: FontErrorKind) -> FontError {
+        FontError {
+            offset: self.offset,
+            kind,
+        }
+    }
+}
+
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xb5, 0x4a, 0x86];
+
+fn parse_psf(data: &[u8]) -> Result<BitmapFont, FontError> {
+    let end = |offset| FontError {
+        offset,
+        kind: FontErrorKind::UnexpectedEnd,
+    };
+    let u32_at = |offset: usize| {
+        data.get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or(end(offset))
+    };
+
+    // Glyph count, size and position, and where the Unicode table starts.
+    let (count, width, height, stride, start, table) = if data.starts_with(&PSF1_MAGIC) {
+        let (mode, size) = (*data.get(2).ok_or(end(2))?, *data.get(3).ok_or(end(3))?);
+        let count = if mode & 0x01 != 0 { 512 } else { 256 };
+        let size = size as usize;
+        let has_table = mode & 0x06 != 0;
+
+        (
+            count,
+            8,
+            size,
+            size,
+            4,
+            has_table.then_some(4 + count * size),
+        )
+    } else if data.starts_with(&PSF2_MAGIC) {
+        let (header, flags) = (u32_at(8)? as usize, u32_at(12)?);
+        let (count, size) = (u32_at(16)? as usize, u32_at(20)? as usize);
+        let (height, width) = (u32_at(24)? as usize, u32_at(28)? as usize);
+        let has_table = flags & 0x01 != 0;
+        let table = count
+            .checked_mul(size)
+            .and_then(|glyphs| glyphs.checked_add(header))
+            .ok_or(end(data.len()))?;
+
+        (
+            count,
+            width,
+            height,
+            size,
+            header,
+            has_table.then_some(table),
+        )
+    } else {
+        return Err(FontError {
+            offset: 0,
+            kind: FontErrorKind::BadMagic,
+        });
+    };
+
+    if width == 0 || height == 0 {
+        return Err(FontError {
+            offset: 0,
+            kind: FontErrorKind::Malformed,
+        });
+    }
+
+    let row_bytes = width.div_ceil(8);
+    if row_bytes
+        .checked_mul(height)
+        .is_none_or(|glyph_bytes| stride < glyph_bytes)
+    {
+        return Err(end(start));
+    }
+
+    // Every glyph has to be there before any of them is allocated.
+    if count > data.len().saturating_sub(start) / stride {
+        return Err(end(data.len()));
+    }
+
+    let glyphs = (0..count)
+        .map(|i| {
+            let offset = start + i * stride;
+            let bytes = &data[offset..offset + stride];
+            let bits = (0..width * height)
+                .map(|i| {
+                    let (x, y) = (i % width, i / width);
+                    bytes[y * row_bytes + x / 8] & (0x80 >> (x % 8)) != 0
+                })
+                .collect();
+
+            Ok(Glyph {
+                width: width as u32,
+                height: height as u32,
+                left: 0,
+                bottom: -(height as i64 / 4),
+                advance: width as i64,
+                bits,
+            })
+        })
+        .collect::<Result<Vec<_>, FontError>>()?;
+
+    let mut chars: Vec<Vec<char>> = vec![vec![]; count];
+    match table {
+        Some(table) if data.starts_with(&PSF1_MAGIC) => {
+            let mut entries = data.get(table..).ok_or(end(table))?.chunks_exact(2);
+
+            for chars in &mut chars {
+                let mut sequence = false;
+
+                for entry in &mut entries {
+                    match u16::from_le_bytes([entry[0], entry[1]]) {
+                        0xffff => break,
+                        0xfffe => sequence = true,
+                        c if !sequence => chars.extend(char::from_u32(c as u32)),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Some(table) => {
+            let mut entries = data.get(table..).ok_or(end(table))?.split(|&b| b == 0xff);
+
+            for (chars, entry) in chars.iter_mut().zip(&mut entries) {
+                // Multi-character sequences after 0xfe aren't single chars.
+                let singles = entry.split(|&b| b == 0xfe).next().unwrap_or(&[]);
+                chars.extend(String::from_utf8_lossy(singles).chars());
+            }
+        }
+        None => {
+            for (i, chars) in chars.iter_mut().enumerate() {
+                chars.extend(char::from_u32(i as u32));
+            }
+        }
+    }
+
+    let mut font = BitmapFont {
+        glyphs: HashMap::new(),
+        default_glyph: None,
+        ascent: (height - height / 4) as u32,
+        descent: (height / 4) as u32,
+    };
+
+    for (glyph, chars) in glyphs.into_iter().zip(chars) {
+        if chars.contains(&'\u{fffd}') || (font.default_glyph.is_none() && chars.contains(&'?')) {
+            font.default_glyph = Some(glyph.clone());
+        }
+
+        for c in chars {
+            font.glyphs.entry(c).or_insert_with(|| glyph.clone());
+        }
+    }
+
+    Ok(font)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{GrayImage, Luma};
+
+    use super::*;
+
+    const WHITE: Luma<u8> = Luma([255]);
+
+    fn lit(img: &GrayImage) -> Vec<(u32, u32)> {
+        img.enumerate_pixels()
+            .filter(|(_, _, p)| p[0] > 0)
+            .map(|(x, y, _)| (x, y))
+            .collect()
+    }
+
+    #[test]
+    fn text_is_placed_and_measured() {
+        let style = TextStyle::default();
+        assert_eq!(style.measure("Hello"), (40f64, 16f64));
+
+        let mut img = GrayImage::new(40, 40);
+        draw_text(&mut img, "I", (10f64, 20f64), &style, WHITE);
+        let pixels = lit(&img);
+        // The I's serifs span columns 2..=5 and its rows stand on the baseline.
+        assert_eq!(pixels.iter().map(|p| p.0).min(), Some(12));
+        assert_eq!(pixels.iter().map(|p| p.0).max(), Some(15));
+        assert_eq!(pixels.iter().map(|p| p.1).min(), Some(10));
+        assert_eq!(pixels.iter().map(|p| p.1).max(), Some(19));
+
+        let centered = style
+            .with_align(TextAlign::Center)
+            .with_baseline(Baseline::Top);
+        let mut moved = GrayImage::new(40, 40);
+        draw_text(&mut moved, "I", (14f64, 8f64), &centered, WHITE);
+        assert_eq!(lit(&moved), pixels);
+
+        let right = style
+            .with_align(TextAlign::Right)
+            .with_baseline(Baseline::Bottom);
+        let bounds = right.bounds("II", (30f64, 30f64));
+        assert_eq!((bounds.left(), bounds.top()), (13.5f64, 13.5f64));
+        assert_eq!((bounds.right(), bounds.bottom()), (29.5f64, 29.5f64));
+
+        // Characters without a glyph fall back to the hollow box.
+        let mut missing = GrayImage::new(10, 16);
+        draw_text(&mut missing, "é", (0f64, 12f64), &style, WHITE);
+        assert_eq!(missing.get_pixel(0, 2)[0], 255);
+        assert_eq!(missing.get_pixel(3, 6)[0], 0);
+    }
+
+    #[test]
+    fn psf_fonts_load() {
+        let mut psf1 = vec![0x36, 0x04, 0x02, 8];
+        psf1.extend((0..256 * 8).map(|i| if i < 8 { 0x81 } else { 0 }));
+        psf1.extend([0x41, 0x00, 0xff, 0xff, 0xff, 0xff]);
+        psf1.extend((2..256).flat_map(|_| [0xff, 0xff]));
+
+        let font = BitmapFont::parse_psf(&psf1).unwrap();
+        assert_eq!((font.ascent(), font.descent()), (6f64, 2f64));
+        assert!(font.has_glyph('A') && !font.has_glyph('B'));
+
+        let mut img = GrayImage::new(8, 8);
+        draw_text(&mut img, "A", (0f64, 6f64), &TextStyle::new(&font), WHITE);
+        assert_eq!(lit(&img).len(), 16);
+
+        let mut psf2 = PSF2_MAGIC.to_vec();
+        for field in [0u32, 32, 0, 2, 2 * 3, 3, 10] {
+            psf2.extend(field.to_le_bytes());
+        }
+        psf2.extend([0xff, 0xc0, 0, 0, 0, 0, 0x80, 0x40, 0x80, 0x40, 0x80, 0x40]);
+
+        let font = BitmapFont::parse_psf(&psf2).unwrap();
+        assert!(font.has_glyph('\u{0}') && font.has_glyph('\u{1}'));
+        assert_eq!(font.measure("\u{1}\u{1}"), (20f64, 3f64));
+
+        assert_eq!(
+            BitmapFont::parse_psf(&psf2[..40]).unwrap_err().kind(),
+            &FontErrorKind::UnexpectedEnd
+        );
+        assert_eq!(
+            BitmapFont::parse_psf(b"nope").unwrap_err().kind(),
+            &FontErrorKind::BadMagic
+        );
+
+        // Headers claiming more glyphs than the data holds, or empty ones,
+        // fail before anything is allocated for them.
+        let header = |count: u32, size: u32, height: u32, width: u32| {
+            let mut psf2 = PSF2_MAGIC.to_vec();
+            for field in [0u32, 32, 0, count, size, height, width] {
+                psf2.extend(field.to_le_bytes());
+            }
+            psf2
+        };
+        assert_eq!(
+            BitmapFont::parse_psf(&header(200_000_000, 0, 0, 0))
+                .unwrap_err()
+                .kind(),
+            &FontErrorKind::Malformed
+        );
+        assert_eq!(
+            BitmapFont::parse_psf(&header(200_000_000, 16, 16, 8))
+                .unwrap_err()
+                .kind(),
+            &FontErrorKind::UnexpectedEnd
+        );
+        assert_eq!(
+            BitmapFont::parse_psf(&header(u32::MAX, u32::MAX, 1, 1))
+                .unwrap_err()
+                .kind(),
+            &FontErrorKind::UnexpectedEnd
+        );
+    }
+
+    #[test]
+    fn bdf_errors_point_at_the_line() {
+        let bdf = "STARTFONT 2.1\nSTARTCHAR A\nENCODING 65\nBBX 8 2 0 0\nBITMAP\nFF\nZZ\nENDCHAR\n";
+        let error = BitmapFont::parse_bdf(bdf).unwrap_err();
+        assert_eq!(error.kind(), &FontErrorKind::ExpectedBitmap);
+        assert_eq!(error.offset(), bdf.find("ZZ").unwrap());
+
+        let huge = "STARTFONT 2.1\nSTARTCHAR A\nENCODING 65\nBBX 4000000000 4000000000 0 0\nBITMAP\nFF\nENDCHAR\n";
+        let error = BitmapFont::parse_bdf(huge).unwrap_err();
+        assert_eq!(error.kind(), &FontErrorKind::ExpectedBitmap);
+    }
+}
@@ -0,0 +1,602 @@
+use crate::{
+    geom::clockwise_arc,
+    paint::Paint,
+    path::Path,
+    types::{Angle, Circle, Ellipse, FillRule, Point, Rect},
+    Renderer,
+};
+
+/// 2D affine transform mapping `x`, `y` to `a·x + c·y + e`, `b·x + d·y + f`,
+/// with the coefficients in the order of SVG's `matrix(a b c d e f)`.
+///
+/// As the y axis points down, positive rotations turn clockwise.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    e: f64,
+    f: f64,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform::new(1f64, 0f64, 0f64, 1f64, 0f64, 0f64);
+
+    pub const fn new(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Self {
+        Self { a, b, c, d, e, f }
+    }
+
+    pub fn translate(tx: f64, ty: f64) -> Self {
+        Self::new(1f64, 0f64, 0f64, 1f64, tx, ty)
+    }
+
+    pub fn scale(sx: f64, sy: f64) -> Self {
+        Self::new(sx, 0f64, 0f64, sy, 0f64, 0f64)
+    }
+
+    /// Rotation by `angle` around the origin.
+    pub fn rotate(angle: Angle) -> Self {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Self::new(cos, sin, -sin, cos, 0f64, 0f64)
+    }
+
+    /// Skew slanting vertical lines by `x` and horizontal lines by `y`.
+    pub fn skew(x: Angle, y: Angle) -> Self {
+        Self::new(
+            1f64,
+            y.to_radians().tan(),
+            x.to_radians().tan(),
+            1f64,
+            0f64,
+            0f64,
+        )
+    }
+
+    pub fn coefficients(&self) -> [f64; 6] {
+        [self.a, self.b, self.c, self.d, self.e, self.f]
+    }
+
+    /// This transform followed by `other`.
+    pub fn then(&self, other: Transform) -> Transform {
+        Transform::new(
+            other.a * self.a + other.c * self.b,
+            other.b * self.a + other.d * self.b,
+            other.a * self.c + other.c * self.d,
+            other.b * self.c + other.d * self.d,
+            other.a * self.e + other.c * self.f + other.e,
+            other.b * self.e + other.d * self.f + other.f,
+        )
+    }
+
+    /// The transform undoing this one, or `None` if it collapses the plane
+    /// onto a line or a point.
+    pub fn invert(&self) -> Option<Transform> {
+        let det = self.determinant();
+        if det == 0f64 || !det.is_finite() {
+            return None;
+        }
+
+        Some(Transform::new(
+            self.d / det,
+            -self.b / det,
+            -self.c / det,
+            self.a / det,
+            (self.c * self.f - self.d * self.e) / det,
+            (self.b * self.e - self.a * self.f) / det,
+        ))
+    }
+
+    pub fn determinant(&self) -> f64 {
+        self.a * self.d - self.b * self.c
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Self::IDENTITY
+    }
+
+    pub fn apply(&self, p: impl Into<Point>) -> Point {
+        let p = p.into();
+        Point::new(
+            self.a * p.x + self.c * p.y + self.e,
+            self.b * p.x + self.d * p.y + self.f,
+        )
+    }
+
+    /// The factor this transform scales by if it only scales uniformly and
+    /// translates, which keeps every shape's kind, angles included.
+    pub(crate) fn uniform_scale(&self) -> Option<f64> {
+        (self.b == 0f64 && self.c == 0f64 && self.a == self.d && self.a > 0f64).then_some(self.a)
+    }
+}
+
+/// Adaptor drawing with another renderer through a current [`Transform`],
+/// which maps every shape from the coordinates it's given in onto the image.
+///
+/// Shapes that only get moved and uniformly scaled are handed to the inner
+/// renderer as they are; others become paths, so a rotated rectangle is
+/// filled as a polygon and a circle scaled unevenly as an ellipse. Gradients
+/// and patterns are transformed along with the shapes they fill. Strokes
+/// keep the inner renderer's width in pixels whatever the transform.
+///
+/// [`save`] and [`restore`] keep a stack of transforms to return to.
+///
+/// [`save`]: TransformedRenderer::save
+/// [`restore`]: TransformedRenderer::restore
+pub struct TransformedRenderer<R> {
+    inner: R,
+    transform: Transform,
+    saved: Vec<Transform>,
+}
+
+impl<R> TransformedRenderer<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            transform: Transform::IDENTITY,
+            saved: vec![],
+        }
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    pub fn transform(&self) -> Transform {
+        self.transform
+    }
+
+    pub fn set_transform(&mut self, transform: Transform) -> &mut Self {
+        self.transform = transform;
+        self
+    }
+
+    /// Applies `transform` to shapes before the current transform, so later
+    /// coordinates are given in the space it sets up.
+    pub fn concat(&mut self, transform: Transform) -> &mut Self {
+        self.transform = transform.then(self.transform);
+        self
+    }
+
+    pub fn translate(&mut self, tx: f64, ty: f64) -> &mut Self {
+        self.concat(Transform::translate(tx, ty))
+    }
+
+    pub fn scale(&mut self, sx: f64, sy: f64) -> &mut Self {
+        self.concat(Transform::scale(sx, sy))
+    }
+
+    pub fn rotate(&mut self, angle: Angle) -> &mut Self {
+        self.concat(Transform::rotate(angle))
+    }
+
+    pub fn skew(&mut self, x: Angle, y: Angle) -> &mut Self {
+        self.concat(Transform::skew(x, y))
+    }
+
+    /// Pushes the current transform onto the stack.
+    pub fn save(&mut self) -> &mut Self {
+        self.saved.push(self.transform);
+        self
+    }
+
+    /// Returns to the transform saved last, if any.
+    pub fn restore(&mut self) -> &mut Self {
+        if let Some(transform) = self.saved.pop() {
+            self.transform = transform;
+        }
+        self
+    }
+
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    fn point(&self, p: Point) -> Point {
+        self.transform.apply(p)
+    }
+
+    fn points(&self, points: &[Point]) -> Vec<Point> {
+        points.iter().map(|&p| self.point(p)).collect()
+    }
+
+    fn path(&self, path: &Path) -> Path {
+        path.transformed(self.transform)
+    }
+
+    fn paint<'a, P>(&self, paint: impl Into<Paint<'a, P>>) -> Paint<'a, P> {
+        paint.into().transformed(self.transform)
+    }
+
+    fn rect(&self, rect: Rect, scale: f64) -> Rect {
+        let p = self.point(Point::new(rect.left(), rect.top()));
+        Rect::new(p.x, p.y, rect.width() * scale, rect.height() * scale)
+    }
+
+    fn circle(&self, circle: Circle, scale: f64) -> Circle {
+        Circle::new(self.point(circle.center()), circle.radius() * scale)
+    }
+
+    fn ellipse(&self, ellipse: Ellipse, scale: f64) -> Ellipse {
+        Ellipse::new(
+            self.point(ellipse.center()),
+            ellipse.radius_x() * scale,
+            ellipse.radius_y() * scale,
+        )
+        .with_rotation(ellipse.rotation())
+    }
+}
+
+impl<R: Renderer> Renderer for TransformedRenderer<R> {
+    type Image = R::Image;
+    type Pixel = R::Pixel;
+
+    fn draw_line(&self, img: &mut Self::Image, from: Point, to: Point, color: Self::Pixel) {
+        self.inner
+            .draw_line(img, self.point(from), self.point(to), color);
+    }
+
+    fn draw_rect(&self, img: &mut Self::Image, rect: Rect, color: Self::Pixel) {
+        match self.transform.uniform_scale() {
+            Some(s) => self.inner.draw_rect(img, self.rect(rect, s), color),
+            None => self
+                .inner
+                .stroke_path(img, &self.path(&Path::rect(rect)), color),
+        }
+    }
+
+    fn draw_filled_rect<'a>(
+        &self,
+        img: &mut Self::Image,
+        rect: Rect,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = self.paint(paint);
+
+        match self.transform.uniform_scale() {
+            Some(s) => self.inner.draw_filled_rect(img, self.rect(rect, s), paint),
+            None => {
+                let path = self.path(&Path::rect(rect));
+                self.inner.fill_path(img, &path, FillRule::NonZero, paint)
+            }
+        }
+    }
+
+    fn draw_circle(&self, img: &mut Self::Image, circle: Circle, color: Self::Pixel) {
+        match self.transform.uniform_scale() {
+            Some(s) => self.inner.draw_circle(img, self.circle(circle, s), color),
+            None => self
+                .inner
+                .stroke_path(img, &self.path(&Path::circle(circle)), color),
+        }
+    }
+
+    fn draw_filled_circle<'a>(
+        &self,
+        img: &mut Self::Image,
+        circle: Circle,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = self.paint(paint);
+
+        match self.transform.uniform_scale() {
+            Some(s) => self
+                .inner
+                .draw_filled_circle(img, self.circle(circle, s), paint),
+            None => {
+                let path = self.path(&Path::circle(circle));
+                self.inner.fill_path(img, &path, FillRule::NonZero, paint)
+            }
+        }
+    }
+
+    fn draw_arc(
+        &self,
+        img: &mut Self::Image,
+        circle: Circle,
+        start: Angle,
+        end: Angle,
+        color: Self::Pixel,
+    ) {
+        match self.transform.uniform_scale() {
+            Some(s) => self
+                .inner
+                .draw_arc(img, self.circle(circle, s), start, end, color),
+            None => {
+                let (start, end) = clockwise_arc(start, end);
+                let path = self.path(&Path::arc(circle, start, end));
+                self.inner.stroke_path(img, &path, color)
+            }
+        }
+    }
+
+    fn draw_filled_arc<'a>(
+        &self,
+        img: &mut Self::Image,
+        circle: Circle,
+        start: Angle,
+        end: Angle,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = self.paint(paint);
+
+        match self.transform.uniform_scale() {
+            Some(s) => self
+                .inner
+                .draw_filled_arc(img, self.circle(circle, s), start, end, paint),
+            None => {
+                let (start, end) = clockwise_arc(start, end);
+                let path = self.path(&Path::pie(circle, start, end));
+                self.inner.fill_path(img, &path, FillRule::NonZero, paint)
+            }
+        }
+    }
+
+    fn draw_ellipse(&self, img: &mut Self::Image, ellipse: Ellipse, color: Self::Pixel) {
+        match self.transform.uniform_scale() {
+            Some(s) => self
+                .inner
+                .draw_ellipse(img, self.ellipse(ellipse, s), color),
+            None => self
+                .inner
+                .stroke_path(img, &self.path(&Path::ellipse(ellipse)), color),
+        }
+    }
+
+    fn draw_filled_ellipse<'a>(
+        &self,
+        img: &mut Self::Image,
+        ellipse: Ellipse,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = self.paint(paint);
+
+        match self.transform.uniform_scale() {
+            Some(s) => self
+                .inner
+                .draw_filled_ellipse(img, self.ellipse(ellipse, s), paint),
+            None => {
+                let path = self.path(&Path::ellipse(ellipse));
+                self.inner.fill_path(img, &path, FillRule::NonZero, paint)
+            }
+        }
+    }
+
+    fn draw_elliptical_arc(
+        &self,
+        img: &mut Self::Image,
+        ellipse: Ellipse,
+        start: Angle,
+        end: Angle,
+        color: Self::Pixel,
+    ) {
+        match self.transform.uniform_scale() {
+            Some(s) => {
+                self.inner
+                    .draw_elliptical_arc(img, self.ellipse(ellipse, s), start, end, color)
+            }
+            None => {
+                let (start, end) = clockwise_arc(start, end);
+                let path = self.path(&Path::elliptical_arc(ellipse, start, end));
+                self.inner.stroke_path(img, &path, color)
+            }
+        }
+    }
+
+    fn draw_filled_elliptical_arc<'a>(
+        &self,
+        img: &mut Self::Image,
+        ellipse: Ellipse,
+        start: Angle,
+        end: Angle,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = self.paint(paint);
+
+        match self.transform.uniform_scale() {
+            Some(s) => self.inner.draw_filled_elliptical_arc(
+                img,
+                self.ellipse(ellipse, s),
+                start,
+                end,
+                paint,
+            ),
+            None => {
+                let (start, end) = clockwise_arc(start, end);
+                let path = self.path(&Path::elliptical_pie(ellipse, start, end));
+                self.inner.fill_path(img, &path, FillRule::NonZero, paint)
+            }
+        }
+    }
+
+    fn draw_rounded_rect(
+        &self,
+        img: &mut Self::Image,
+        rect: Rect,
+        corner_radius: f64,
+        color: Self::Pixel,
+    ) {
+        match self.transform.uniform_scale() {
+            Some(s) => {
+                self.inner
+                    .draw_rounded_rect(img, self.rect(rect, s), corner_radius * s, color)
+            }
+            None => {
+                let path = self.path(&Path::rounded_rect(rect, corner_radius));
+                self.inner.stroke_path(img, &path, color)
+            }
+        }
+    }
+
+    fn draw_filled_rounded_rect<'a>(
+        &self,
+        img: &mut Self::Image,
+        rect: Rect,
+        corner_radius: f64,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = self.paint(paint);
+
+        match self.transform.uniform_scale() {
+            Some(s) => self.inner.draw_filled_rounded_rect(
+                img,
+                self.rect(rect, s),
+                corner_radius * s,
+                paint,
+            ),
+            None => {
+                let path = self.path(&Path::rounded_rect(rect, corner_radius));
+                self.inner.fill_path(img, &path, FillRule::NonZero, paint)
+            }
+        }
+    }
+
+    fn draw_polyline(&self, img: &mut Self::Image, points: &[Point], color: Self::Pixel) {
+        self.inner.draw_polyline(img, &self.points(points), color);
+    }
+
+    fn draw_polygon(&self, img: &mut Self::Image, points: &[Point], color: Self::Pixel) {
+        self.inner.draw_polygon(img, &self.points(points), color);
+    }
+
+    fn draw_filled_polygon<'a>(
+        &self,
+        img: &mut Self::Image,
+        points: &[Point],
+        rule: FillRule,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = self.paint(paint);
+
+        self.inner
+            .draw_filled_polygon(img, &self.points(points), rule, paint);
+    }
+
+    fn stroke_path(&self, img: &mut Self::Image, path: &Path, color: Self::Pixel) {
+        self.inner.stroke_path(img, &self.path(path), color);
+    }
+
+    fn fill_path<'a>(
+        &self,
+        img: &mut Self::Image,
+        path: &Path,
+        rule: FillRule,
+        paint: impl Into<Paint<'a, Self::Pixel>>,
+    ) {
+        let paint = self.paint(paint);
+
+        self.inner.fill_path(img, &self.path(path), rule, paint);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_approx_eq::assert_approx_eq;
+    use image::{GrayImage, Luma};
+
+    use crate::{coverage::CoverageRenderer, paint::Gradient};
+
+    use super::*;
+
+    fn assert_point_eq(a: Point, b: Point) {
+        assert_approx_eq!(a.x, b.x, 1e-9f64);
+        assert_approx_eq!(a.y, b.y, 1e-9f64);
+    }
+
+    #[test]
+    fn transforms_compose_and_invert() {
+        let rotate = Transform::rotate(Angle::Degrees(90f64));
+        assert_point_eq(rotate.apply((1f64, 0f64)), Point::new(0f64, 1f64));
+
+        let t = Transform::scale(2f64, 3f64)
+            .then(rotate)
+            .then(Transform::translate(5f64, -1f64));
+        assert_point_eq(t.apply((1f64, 1f64)), Point::new(2f64, 1f64));
+
+        let inverse = t.invert().unwrap();
+        assert_point_eq(
+            inverse.apply(t.apply((7f64, -2f64))),
+            Point::new(7f64, -2f64),
+        );
+        assert!(t.then(inverse).coefficients()[..4]
+            .iter()
+            .zip([1f64, 0f64, 0f64, 1f64])
+            .all(|(a, b)| (a - b).abs() < 1e-9f64));
+
+        let skew = Transform::skew(Angle::Degrees(45f64), Angle::Degrees(0f64));
+        assert_point_eq(skew.apply((0f64, 2f64)), Point::new(2f64, 2f64));
+
+        assert_eq!(Transform::scale(1f64, 0f64).invert(), None);
+    }
+
+    #[test]
+    fn shapes_follow_the_transform() {
+        let white = Luma([255u8]);
+        let filled = |img: &GrayImage, x, y| img.get_pixel(x, y)[0] == 255;
+
+        // Scaled unevenly, a circle becomes an ellipse.
+        let mut img = GrayImage::new(40, 20);
+        let mut r = TransformedRenderer::new(CoverageRenderer::default());
+        r.scale(2f64, 1f64);
+        r.draw_filled_circle(&mut img, Circle::new((10f64, 10f64), 4f64), white);
+        assert!(filled(&img, 27, 10) && filled(&img, 20, 13));
+        assert!(!filled(&img, 20, 15) && !filled(&img, 29, 10));
+
+        // Rotated by 45°, a square stands on its corner.
+        let mut img = GrayImage::new(40, 40);
+        r.save();
+        r.set_transform(Transform::IDENTITY)
+            .translate(20f64, 20f64)
+            .rotate(Angle::Degrees(45f64));
+        r.draw_filled_rect(&mut img, Rect::new(-8f64, -8f64, 16f64, 16f64), white);
+        assert!(filled(&img, 20, 11) && filled(&img, 11, 20));
+        assert!(!filled(&img, 10, 10) && !filled(&img, 30, 30));
+
+        r.restore();
+        assert_eq!(r.transform(), Transform::scale(2f64, 1f64));
+
+        // Mirrored, a triangle and its gradient point the other way.
+        let mut img = GrayImage::new(20, 10);
+        r.set_transform(Transform::translate(20f64, 0f64))
+            .scale(-1f64, 1f64);
+        let gradient = Gradient::linear((0f64, 0f64), (10f64, 0f64))
+            .with_stop(0f64, Luma([0u8]))
+            .with_stop(1f64, white);
+        r.draw_filled_polygon(
+            &mut img,
+            &[
+                Point::new(0f64, 0f64),
+                Point::new(10f64, 5f64),
+                Point::new(0f64, 10f64),
+            ],
+            FillRule::NonZero,
+            gradient,
+        );
+        assert_eq!(img.get_pixel(12, 5)[0], 204);
+        assert_eq!(img.get_pixel(5, 5)[0], 0);
+        assert!(img.get_pixel(18, 5)[0] > 0);
+    }
+
+    #[test]
+    fn restoring_more_than_was_saved_keeps_the_transform() {
+        let mut r = TransformedRenderer::new(CoverageRenderer::<GrayImage>::default());
+        r.translate(5f64, 0f64);
+        r.save().scale(2f64, 2f64);
+
+        r.restore();
+        assert_eq!(r.transform(), Transform::translate(5f64, 0f64));
+        r.restore();
+        assert_eq!(r.transform(), Transform::translate(5f64, 0f64));
+    }
+}
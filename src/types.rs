@@ -1,15 +1,40 @@
 use std::f64::consts::PI;
 
+use crate::{
+    error::{check_rect, finite, in_range, non_negative, GfxError},
+    geom::arc_sweep,
+};
+
+/// A position in image space. Integer coordinates fall on pixel centers, so
+/// `Point::new(3.0, 4.0)` is the center of the pixel in column 3, row 4.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Point<T = f64> {
+    pub x: T,
+    pub y: T,
+}
+
+impl<T> Point<T> {
+    pub fn new(x: T, y: T) -> Self {
+        Self { x, y }
+    }
+}
+
+impl<T> From<(T, T)> for Point<T> {
+    fn from((x, y): (T, T)) -> Self {
+        Self { x, y }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Rect {
-    left: u32,
-    top: u32,
-    width: u32,
-    height: u32,
+    left: f64,
+    top: f64,
+    width: f64,
+    height: f64,
 }
 
 impl Rect {
-    pub fn new(left: u32, top: u32, width: u32, height: u32) -> Rect {
+    pub fn new(left: f64, top: f64, width: f64, height: f64) -> Rect {
         Self {
             left,
             top,
@@ -18,56 +43,368 @@ impl Rect {
         }
     }
 
-    pub fn left(&self) -> u32 {
+    /// A rectangle from values that may not describe one, like user input:
+    /// fails unless its edges are finite and within
+    /// [`MAX_COORDINATE`](crate::error::MAX_COORDINATE) of the origin, and its
+    /// size isn't negative.
+    pub fn try_new(left: f64, top: f64, width: f64, height: f64) -> Result<Rect, GfxError> {
+        let rect = Self::new(left, top, width, height);
+        check_rect(rect)?;
+        Ok(rect)
+    }
+
+    pub fn left(&self) -> f64 {
         self.left
     }
 
-    pub fn right(&self) -> u32 {
+    pub fn right(&self) -> f64 {
         self.left + self.width
     }
 
-    pub fn top(&self) -> u32 {
+    pub fn top(&self) -> f64 {
         self.top
     }
 
-    pub fn bottom(&self) -> u32 {
+    pub fn bottom(&self) -> f64 {
         self.top + self.height
     }
 
-    pub fn width(&self) -> u32 {
+    pub fn width(&self) -> f64 {
         self.width
     }
 
-    pub fn height(&self) -> u32 {
+    pub fn height(&self) -> f64 {
         self.height
     }
 }
 
+/// The radii of a rounded corner along x and y, equal for a circular
+/// corner.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CornerRadius {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl CornerRadius {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    fn scaled(self, factor: f64) -> Self {
+        Self::new(self.x * factor, self.y * factor)
+    }
+
+    /// Negative and NaN radii are square corners.
+    fn sanitized(self) -> Self {
+        Self::new(self.x.max(0f64), self.y.max(0f64))
+    }
+
+    fn is_square(&self) -> bool {
+        self.x <= 0f64 || self.y <= 0f64
+    }
+}
+
+impl From<f64> for CornerRadius {
+    fn from(radius: f64) -> Self {
+        Self::new(radius, radius)
+    }
+}
+
+impl From<(f64, f64)> for CornerRadius {
+    fn from((x, y): (f64, f64)) -> Self {
+        Self::new(x, y)
+    }
+}
+
+/// Rectangle with each corner rounded on its own, like CSS `border-radius`.
+///
+/// Radii are clamped the way CSS does it: where the corners along a side
+/// add up to more than its length, all of them are scaled down by the same
+/// factor until they fit, so the shape keeps its proportions. Corners with
+/// a zero or negative radius are square.
+#[derive(Debug, Clone, Copy)]
+pub struct RoundedRect {
+    rect: Rect,
+    radii: [CornerRadius; 4],
+}
+
+impl RoundedRect {
+    /// `rect` with every corner rounded by `radius`.
+    pub fn new(rect: Rect, radius: impl Into<CornerRadius>) -> Self {
+        let radius = radius.into();
+        Self::with_radii(rect, [radius; 4])
+    }
+
+    /// Like [`RoundedRect::new`], but fails unless `rect` is valid as
+    /// [`Rect::try_new`] checks it and `radius` is finite and not negative.
+    /// Radii too large for `rect` are clamped, not rejected.
+    pub fn try_new(rect: Rect, radius: impl Into<CornerRadius>) -> Result<Self, GfxError> {
+        let radius = radius.into();
+        Self::try_with_radii(rect, [radius; 4])
+    }
+
+    /// Like [`RoundedRect::with_radii`], validating like
+    /// [`RoundedRect::try_new`].
+    pub fn try_with_radii(rect: Rect, radii: [CornerRadius; 4]) -> Result<Self, GfxError> {
+        check_rect(rect)?;
+        non_negative(&radii.map(|r| [r.x, r.y]).concat())?;
+        Ok(Self::with_radii(rect, radii))
+    }
+
+    /// `rect` with its top left, top right, bottom right and bottom left
+    /// corners rounded by `radii`, in that order.
+    ///
+    /// Infinite radii take as much of their sides as they can, sharing them
+    /// with other infinite radii, next to which finite ones shrink to nothing.
+    pub fn with_radii(rect: Rect, radii: [CornerRadius; 4]) -> Self {
+        let mut radii = radii.map(CornerRadius::sanitized);
+        let mut limit = 1f64;
+
+        if radii.iter().any(|r| r.x.is_infinite() || r.y.is_infinite()) {
+            let infinite = |v: f64| if v.is_infinite() { 1f64 } else { 0f64 };
+
+            radii = radii.map(|r| CornerRadius::new(infinite(r.x), infinite(r.y)));
+            limit = f64::INFINITY;
+        }
+
+        let [top_left, top_right, bottom_right, bottom_left] = radii;
+
+        let (width, height) = (rect.width().abs(), rect.height().abs());
+        let factor = [
+            width / (top_left.x + top_right.x),
+            width / (bottom_left.x + bottom_right.x),
+            height / (top_left.y + bottom_left.y),
+            height / (top_right.y + bottom_right.y),
+        ]
+        .into_iter()
+        .filter(|f| !f.is_nan())
+        .fold(limit, f64::min);
+
+        Self {
+            rect,
+            radii: radii.map(|r| r.scaled(factor)),
+        }
+    }
+
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    /// The clamped radii of the top left, top right, bottom right and bottom
+    /// left corners.
+    pub fn radii(&self) -> [CornerRadius; 4] {
+        self.radii
+    }
+
+    pub fn top_left(&self) -> CornerRadius {
+        self.radii[0]
+    }
+
+    pub fn top_right(&self) -> CornerRadius {
+        self.radii[1]
+    }
+
+    pub fn bottom_right(&self) -> CornerRadius {
+        self.radii[2]
+    }
+
+    pub fn bottom_left(&self) -> CornerRadius {
+        self.radii[3]
+    }
+
+    /// The radius of all corners if they're the same and circular.
+    pub(crate) fn uniform_radius(&self) -> Option<f64> {
+        let r = self.radii[0];
+
+        (r.x == r.y && self.radii.iter().all(|&c| c == r)).then_some(r.x)
+    }
+
+    pub(crate) fn is_square(&self, corner: usize) -> bool {
+        self.radii[corner].is_square()
+    }
+}
+
+impl From<Rect> for RoundedRect {
+    fn from(rect: Rect) -> Self {
+        Self::new(rect, 0f64)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Circle {
-    center_x: u32,
-    center_y: u32,
-    radius: u32,
+    center_x: f64,
+    center_y: f64,
+    radius: f64,
 }
 
 impl Circle {
-    pub fn new(center: (u32, u32), radius: u32) -> Self {
+    pub fn new(center: impl Into<Point>, radius: f64) -> Self {
+        let center = center.into();
+
         Self {
-            center_x: center.0,
-            center_y: center.1,
+            center_x: center.x,
+            center_y: center.y,
             radius,
         }
     }
 
-    pub fn center(&self) -> (u32, u32) {
-        (self.center_x, self.center_y)
+    /// Fails unless the center is finite, the radius isn't negative and the
+    /// whole circle is within [`MAX_COORDINATE`](crate::error::MAX_COORDINATE)
+    /// of the origin.
+    pub fn try_new(center: impl Into<Point>, radius: f64) -> Result<Self, GfxError> {
+        let circle = Self::new(center, radius);
+        let (x, y) = (circle.center_x, circle.center_y);
+
+        finite(&[x, y])?;
+        non_negative(&[radius])?;
+        in_range(&[x - radius, x + radius, y - radius, y + radius])?;
+        Ok(circle)
     }
 
-    pub fn radius(&self) -> u32 {
+    pub fn center(&self) -> Point {
+        Point::new(self.center_x, self.center_y)
+    }
+
+    pub fn radius(&self) -> f64 {
         self.radius
     }
 }
 
+/// Ellipse with radii along its own axes, which are rotated clockwise by
+/// `rotation` from the image axes.
+#[derive(Debug, Clone, Copy)]
+pub struct Ellipse {
+    center_x: f64,
+    center_y: f64,
+    radius_x: f64,
+    radius_y: f64,
+    rotation: Angle,
+}
+
+impl Ellipse {
+    pub fn new(center: impl Into<Point>, radius_x: f64, radius_y: f64) -> Self {
+        let center = center.into();
+
+        Self {
+            center_x: center.x,
+            center_y: center.y,
+            radius_x,
+            radius_y,
+            rotation: Angle::Radians(0f64),
+        }
+    }
+
+    /// Fails unless the center is finite, the radii aren't negative and the
+    /// whole ellipse, however it's rotated, is within
+    /// [`MAX_COORDINATE`](crate::error::MAX_COORDINATE) of the origin.
+    pub fn try_new(
+        center: impl Into<Point>,
+        radius_x: f64,
+        radius_y: f64,
+    ) -> Result<Self, GfxError> {
+        let ellipse = Self::new(center, radius_x, radius_y);
+        let (x, y) = (ellipse.center_x, ellipse.center_y);
+        let radius = radius_x.max(radius_y);
+
+        finite(&[x, y])?;
+        non_negative(&[radius_x, radius_y])?;
+        in_range(&[x - radius, x + radius, y - radius, y + radius])?;
+        Ok(ellipse)
+    }
+
+    pub fn with_rotation(mut self, rotation: Angle) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn center(&self) -> Point {
+        Point::new(self.center_x, self.center_y)
+    }
+
+    pub fn radius_x(&self) -> f64 {
+        self.radius_x
+    }
+
+    pub fn radius_y(&self) -> f64 {
+        self.radius_y
+    }
+
+    pub fn rotation(&self) -> Angle {
+        self.rotation
+    }
+
+    pub(crate) fn is_axis_aligned(&self) -> bool {
+        self.rotation.to_radians() == 0f64
+    }
+}
+
+impl From<Circle> for Ellipse {
+    fn from(circle: Circle) -> Self {
+        Self::new(circle.center(), circle.radius(), circle.radius())
+    }
+}
+
+/// Which way an arc runs from its start angle to its end angle. As the y axis
+/// points down, angles increase clockwise.
+///
+/// Renderers' `draw_arc` and friends run clockwise from `start` to `end`,
+/// wrapping around past 360°, and draw the whole circle once `end` is a turn
+/// or more past `start`. Their `directed` counterparts, and
+/// [`Path::directed_arc`](crate::path::Path::directed_arc), run either way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArcDirection {
+    #[default]
+    Clockwise,
+    CounterClockwise,
+}
+
+impl ArcDirection {
+    /// The degrees the arc running this way from `start` to `end` sweeps by,
+    /// negative counter-clockwise. Like clockwise arcs, it wraps around and
+    /// covers the full circle once `end` is a turn or more past `start` this
+    /// way.
+    pub fn sweep(&self, start: Angle, end: Angle) -> f64 {
+        match self {
+            ArcDirection::Clockwise => arc_sweep(start, end),
+            ArcDirection::CounterClockwise => -arc_sweep(end, start),
+        }
+    }
+
+    /// The same arc as the one running this way from `start` to `end`, as
+    /// clockwise start and end angles.
+    pub(crate) fn clockwise(&self, start: Angle, end: Angle) -> (Angle, Angle) {
+        let (from, sweep) = (start.to_degrees(), self.sweep(start, end));
+
+        (
+            Angle::Degrees(from.min(from + sweep)),
+            Angle::Degrees(from.max(from + sweep)),
+        )
+    }
+}
+
+/// Decides which regions of a self-intersecting or multi-contour shape are
+/// inside it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FillRule {
+    /// Inside where the outline winds around a point a non-zero number of
+    /// times.
+    #[default]
+    NonZero,
+    /// Inside where a ray from a point crosses the outline an odd number of
+    /// times.
+    EvenOdd,
+}
+
+impl FillRule {
+    pub(crate) fn is_inside(&self, winding: i32) -> bool {
+        match self {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum Angle {
     Degrees(f64),
@@ -84,7 +421,7 @@ impl Angle {
             Angle::Degrees(d) => {
                 let d = *d;
 
-                if 0f64 <= d && d <= 360f64 {
+                if (0f64..=360f64).contains(&d) {
                     Angle::Degrees(d)
                 } else {
                     let dd = d % 360f64;
@@ -94,7 +431,7 @@ impl Angle {
             Angle::Radians(r) => {
                 let r = *r;
 
-                if 0f64 <= r && r <= 2f64 * PI {
+                if (0f64..=2f64 * PI).contains(&r) {
                     Angle::Radians(r)
                 } else {
                     let rr = r % (2f64 * PI);
@@ -123,7 +460,8 @@ impl Angle {
 mod test {
     use std::f64::consts::PI;
 
-    use super::Angle;
+    use super::{Angle, Circle, CornerRadius, Ellipse, Rect, RoundedRect};
+    use crate::{antialiased::AntiAliasingRender, error::GfxError, Renderer};
 
     use assert_approx_eq::assert_approx_eq;
 
@@ -156,4 +494,118 @@ mod test {
             1e-12f64
         );
     }
+
+    #[test]
+    fn try_constructors_reject_bad_geometry() {
+        assert!(Rect::try_new(1f64, 2f64, 3f64, 0f64).is_ok());
+        assert_eq!(
+            Rect::try_new(f64::NAN, 0f64, 1f64, 1f64).unwrap_err(),
+            GfxError::NonFinite
+        );
+        assert_eq!(
+            Rect::try_new(f64::MAX, 0f64, f64::MAX, 1f64).unwrap_err(),
+            GfxError::NonFinite
+        );
+        assert_eq!(
+            Rect::try_new(0f64, 0f64, -1f64, 1f64).unwrap_err(),
+            GfxError::Negative
+        );
+
+        assert!(Circle::try_new((1f64, 1f64), 0f64).is_ok());
+        assert_eq!(
+            Circle::try_new((1f64, f64::INFINITY), 1f64).unwrap_err(),
+            GfxError::NonFinite
+        );
+        assert_eq!(
+            Ellipse::try_new((0f64, 0f64), 1f64, -2f64).unwrap_err(),
+            GfxError::Negative
+        );
+
+        // Finite, but too large for the rasterizers to draw.
+        assert_eq!(
+            Circle::try_new((25f64, 25f64), 1e19f64).unwrap_err(),
+            GfxError::OutOfRange
+        );
+        assert_eq!(
+            Ellipse::try_new((-1.6e7f64, 0f64), 1e6f64, 1f64).unwrap_err(),
+            GfxError::OutOfRange
+        );
+        assert_eq!(
+            Rect::try_new(0f64, 0f64, 1e9f64, 1f64).unwrap_err(),
+            GfxError::OutOfRange
+        );
+
+        let rect = Rect::new(2f64, 2f64, 16f64, 10f64);
+        assert_eq!(
+            RoundedRect::try_new(rect, 6f64).unwrap().uniform_radius(),
+            Some(5f64)
+        );
+        assert_eq!(
+            RoundedRect::try_new(rect, (1f64, -1f64)).unwrap_err(),
+            GfxError::Negative
+        );
+        assert_eq!(
+            RoundedRect::try_new(rect, f64::NAN).unwrap_err(),
+            GfxError::NonFinite
+        );
+    }
+
+    #[test]
+    fn corner_radii_are_clamped_proportionally() {
+        let rect = Rect::new(0f64, 0f64, 100f64, 50f64);
+
+        let pill = RoundedRect::new(rect, 40f64);
+        assert_eq!(pill.uniform_radius(), Some(25f64));
+
+        let r = RoundedRect::with_radii(
+            rect,
+            [
+                CornerRadius::new(60f64, 10f64),
+                CornerRadius::new(60f64, 10f64),
+                CornerRadius::from(-5f64),
+                CornerRadius::from(5f64),
+            ],
+        );
+        assert_approx_eq!(r.top_left().x, 50f64);
+        assert_approx_eq!(r.top_right().y, 10f64 * 100f64 / 120f64);
+        assert_approx_eq!(r.bottom_left().x, 5f64 * 100f64 / 120f64);
+        assert_eq!(r.bottom_right(), CornerRadius::from(0f64));
+        assert_eq!(r.uniform_radius(), None);
+
+        let pill = RoundedRect::new(rect, f64::INFINITY);
+        assert_eq!(pill.uniform_radius(), Some(25f64));
+
+        let r = RoundedRect::with_radii(
+            rect,
+            [
+                CornerRadius::from(f64::INFINITY),
+                CornerRadius::from(10f64),
+                CornerRadius::from(f64::NAN),
+                CornerRadius::new(f64::INFINITY, 10f64),
+            ],
+        );
+        assert_eq!(r.top_left(), CornerRadius::from(50f64));
+        assert_eq!(r.top_right(), CornerRadius::from(0f64));
+        assert_eq!(r.bottom_right(), CornerRadius::from(0f64));
+        assert_eq!(r.bottom_left(), CornerRadius::new(50f64, 0f64));
+    }
+
+    #[test]
+    fn corner_radii_from_user_input_are_validated() {
+        let red = image::Rgba([255, 0, 0, 255]);
+        let rect = Rect::new(2f64, 2f64, 16f64, 10f64);
+
+        assert_eq!(
+            RoundedRect::try_new(rect, -1f64).unwrap_err(),
+            GfxError::Negative
+        );
+
+        // Radii too large are clamped like everywhere else.
+        let mut img = image::RgbaImage::new(20, 20);
+        let rounded = RoundedRect::try_new(rect, 6f64).unwrap();
+        AntiAliasingRender::default().fill_rounded_rect(&mut img, rounded, red);
+        assert_eq!(*img.get_pixel(10, 7), red);
+        assert_eq!(*img.get_pixel(3, 7), red);
+        assert_eq!(img.get_pixel(2, 2)[3], 0);
+    }
 }